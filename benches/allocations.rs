@@ -0,0 +1,141 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Counts the heap allocations of building regular expressions from a large set
+//! of test cases instead of measuring the time. Run with `cargo bench --bench allocations`.
+
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{black_box, criterion_main, Criterion, Throughput};
+use grex::{Feature, RegExpBuilder};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> Self::Intermediate {
+        ALLOCATIONS.load(Ordering::Relaxed)
+    }
+
+    fn end(&self, before: Self::Intermediate) -> Self::Value {
+        ALLOCATIONS.load(Ordering::Relaxed) - before
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationFormatter
+    }
+}
+
+struct AllocationFormatter;
+
+impl ValueFormatter for AllocationFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "allocations"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        match throughput {
+            Throughput::Elements(elements) => {
+                for value in values.iter_mut() {
+                    *value /= *elements as f64;
+                }
+                "allocations/test case"
+            }
+            Throughput::Bytes(bytes) => {
+                for value in values.iter_mut() {
+                    *value /= *bytes as f64;
+                }
+                "allocations/byte"
+            }
+        }
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocations"
+    }
+}
+
+fn benchmark_allocations(c: &mut Criterion<Allocations>) {
+    let test_cases = (0..5000)
+        .map(|it| format!("item-{}", it * 37 + 1000))
+        .collect::<Vec<_>>();
+    let mut group = c.benchmark_group("allocations");
+    group.throughput(Throughput::Elements(test_cases.len() as u64));
+
+    group.bench_function("default", |b| {
+        b.iter(|| RegExpBuilder::from(black_box(&test_cases)).build())
+    });
+    group.bench_function("case-insensitive", |b| {
+        b.iter(|| {
+            RegExpBuilder::from(black_box(&test_cases))
+                .with_conversion_of(&[Feature::CaseInsensitivity])
+                .build()
+        })
+    });
+
+    group.finish();
+}
+
+// Identical allocation counts of all samples cannot be plotted, so the plots are disabled
+// after the command line arguments have been applied, which would enable them again.
+fn benches() {
+    let mut criterion = Criterion::default()
+        .configure_from_args()
+        .with_measurement(Allocations)
+        .sample_size(10)
+        .without_plots();
+    benchmark_allocations(&mut criterion);
+}
+
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#[path = "../tests/fixtures/mod.rs"]
+mod fixtures;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fixtures::{
+    mixed_test_cases, numeric_test_cases, random_lowercase_test_cases, word_test_cases,
+};
+use grex::{Feature, RegExpBuilder};
+
+fn benchmark_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    let profiles = [
+        ("numeric", numeric_test_cases()),
+        ("words", word_test_cases()),
+        ("mixed", mixed_test_cases()),
+        ("sparse", random_lowercase_test_cases(42, 300, 10)),
+    ];
+
+    for (name, test_cases) in profiles.iter() {
+        group.bench_with_input(
+            BenchmarkId::new("default", name),
+            test_cases,
+            |b, test_cases| b.iter(|| RegExpBuilder::from(black_box(test_cases)).build()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("conversion", name),
+            test_cases,
+            |b, test_cases| {
+                b.iter(|| {
+                    RegExpBuilder::from(black_box(test_cases))
+                        .with_conversion_of(&[Feature::Digit, Feature::Word, Feature::Repetition])
+                        .build()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+// Test cases of the same length which differ in a single position only
+// result in a concatenation chain of a thousand graphemes.
+fn benchmark_positional_inference(c: &mut Criterion) {
+    let test_case = (0..1000)
+        .map(|it| (b'a' + (it % 26) as u8) as char)
+        .collect::<String>();
+    let mut other_test_case = test_case.clone();
+    other_test_case.replace_range(500..501, "Z");
+    let test_cases = vec![test_case, other_test_case];
+
+    c.bench_function("positional inference of 1000 graphemes", |b| {
+        b.iter(|| {
+            RegExpBuilder::from(black_box(&test_cases))
+                .with_positional_inference()
+                .build()
+        })
+    });
+}
+
+criterion_group!(benches, benchmark_build, benchmark_positional_inference);
+criterion_main!(benches);
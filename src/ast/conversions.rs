@@ -0,0 +1,130 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::{Expression, Quantifier};
+use crate::char::{hex_digit_ranges, opposite_case, Grapheme};
+use crate::regexp::{Feature, RegExpConfig};
+use std::collections::BTreeSet;
+
+impl Expression {
+    /// Returns the conversion features which have actually changed this expression,
+    /// such as `Feature::Digit` if it contains `\d`. Capturing groups are not taken
+    /// into account because they only emerge while the expression is rendered.
+    pub(crate) fn applied_conversions(&self) -> BTreeSet<Feature> {
+        let mut conversions = BTreeSet::new();
+        self.collect_conversions(&mut conversions);
+        conversions
+    }
+
+    fn collect_conversions(&self, conversions: &mut BTreeSet<Feature>) {
+        match self {
+            Expression::Alternation(options, _) => {
+                for option in options {
+                    option.collect_conversions(conversions);
+                }
+            }
+            Expression::CharacterClass(char_set, config) => {
+                if config.is_hex_converted() && hex_digit_ranges(char_set).is_some() {
+                    conversions.insert(Feature::Hex);
+                }
+                if config.is_case_insensitive_matching()
+                    && char_set.iter().any(|&c| opposite_case(c).is_some())
+                {
+                    conversions.insert(Feature::CaseInsensitivity);
+                }
+            }
+            Expression::Concatenation(expr1, expr2, _) => {
+                expr1.collect_conversions(conversions);
+                expr2.collect_conversions(conversions);
+            }
+            Expression::Literal(cluster, config) => {
+                for grapheme in cluster.graphemes() {
+                    collect_grapheme_conversions(grapheme, config, conversions);
+                }
+            }
+            Expression::Repetition(expr, quantifier, config) => {
+                expr.collect_conversions(conversions);
+                // Counted repetitions of whole expressions are folded from optional ones.
+                if config.is_repetition_converted() && matches!(quantifier, Quantifier::Range(_, _))
+                {
+                    conversions.insert(Feature::Repetition);
+                }
+            }
+            Expression::AnyChar(_) | Expression::WordBoundary(_) => {}
+        }
+    }
+}
+
+fn collect_grapheme_conversions(
+    grapheme: &Grapheme,
+    config: &RegExpConfig,
+    conversions: &mut BTreeSet<Feature>,
+) {
+    if grapheme.has_repetitions() || grapheme.minimum() != 1 || grapheme.maximum() != 1 {
+        conversions.insert(Feature::Repetition);
+    }
+    for repetition in grapheme.repetitions.iter() {
+        collect_grapheme_conversions(repetition, config, conversions);
+    }
+    for value in grapheme.chars() {
+        let feature = match value.as_str() {
+            "\\d" => Feature::Digit,
+            "\\D" => Feature::NonDigit,
+            "\\s" => Feature::Space,
+            "\\S" => Feature::NonSpace,
+            "\\w" => Feature::Word,
+            "\\W" => Feature::NonWord,
+            value
+                if config.is_case_insensitive_matching()
+                    && value.chars().any(|c| opposite_case(c).is_some()) =>
+            {
+                Feature::CaseInsensitivity
+            }
+            _ => continue,
+        };
+        conversions.insert(feature);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::char::GraphemeCluster;
+    use std::rc::Rc;
+
+    #[test]
+    fn ensure_no_conversions_are_reported_for_plain_literal() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("abc", &config), &config);
+        assert!(literal.applied_conversions().is_empty());
+    }
+
+    #[test]
+    fn ensure_conversions_of_nested_repetitions_are_reported() {
+        let mut config = RegExpConfig::new();
+        config.conversion_features = vec![Feature::Digit, Feature::Repetition];
+        let config = Rc::new(config);
+        let mut cluster = GraphemeCluster::from("1a1a", &config);
+        cluster.convert_to_char_classes();
+        cluster.convert_repetitions();
+        let literal = Expression::new_literal(cluster, &config);
+        assert_eq!(literal.to_string(), "(?:\\da){2}");
+        assert_eq!(
+            literal.applied_conversions(),
+            BTreeSet::from([Feature::Digit, Feature::Repetition])
+        );
+    }
+}
@@ -0,0 +1,88 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::Expression;
+use itertools::Itertools;
+
+impl Expression {
+    /// Describes the structural differences between this expression and `other`,
+    /// such as `added 'c' to character class [ab]`. Both expressions are traversed
+    /// in parallel as long as their structure matches. Subexpressions whose structure
+    /// differs are reported as replaced as a whole.
+    pub(crate) fn diff(&self, other: &Expression) -> Vec<String> {
+        let mut changes = vec![];
+        self.collect_changes(other, &mut changes);
+        changes
+    }
+
+    fn collect_changes(&self, other: &Expression, changes: &mut Vec<String>) {
+        let (old, new) = (self.to_string(), other.to_string());
+        if old == new {
+            return;
+        }
+
+        match (self, other) {
+            (Expression::Alternation(old_options, _), Expression::Alternation(new_options, _)) => {
+                let old_branches = old_options.iter().map(|it| it.to_string()).collect_vec();
+                let new_branches = new_options.iter().map(|it| it.to_string()).collect_vec();
+                for branch in old_branches.iter() {
+                    if !new_branches.contains(branch) {
+                        changes.push(format!(
+                            "removed branch {} from alternation {}",
+                            branch, old
+                        ));
+                    }
+                }
+                for branch in new_branches.iter() {
+                    if !old_branches.contains(branch) {
+                        changes.push(format!("added branch {} to alternation {}", branch, old));
+                    }
+                }
+            }
+            (
+                Expression::CharacterClass(old_char_set, _),
+                Expression::CharacterClass(new_char_set, _),
+            ) => {
+                for c in old_char_set.difference(new_char_set) {
+                    changes.push(format!("removed '{}' from character class {}", c, old));
+                }
+                for c in new_char_set.difference(old_char_set) {
+                    changes.push(format!("added '{}' to character class {}", c, old));
+                }
+            }
+            (
+                Expression::Concatenation(old_expr1, old_expr2, _),
+                Expression::Concatenation(new_expr1, new_expr2, _),
+            ) => {
+                old_expr1.collect_changes(new_expr1, changes);
+                old_expr2.collect_changes(new_expr2, changes);
+            }
+            (
+                Expression::Repetition(old_expr, old_quantifier, _),
+                Expression::Repetition(new_expr, new_quantifier, _),
+            ) => {
+                if old_quantifier != new_quantifier {
+                    changes.push(format!(
+                        "changed quantifier of {} from {} to {}",
+                        old, old_quantifier, new_quantifier
+                    ));
+                }
+                old_expr.collect_changes(new_expr, changes);
+            }
+            _ => changes.push(format!("replaced {} with {}", old, new)),
+        }
+    }
+}
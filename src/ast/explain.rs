@@ -0,0 +1,143 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::{Expression, Quantifier};
+use crate::char::Grapheme;
+use itertools::Itertools;
+
+impl Expression {
+    /// Describes the language matched by this expression in plain English,
+    /// such as `"ab", then either a digit or "c"`.
+    pub(crate) fn explain(&self) -> String {
+        match self {
+            Expression::Alternation(options, _) => format!(
+                "either {}",
+                options
+                    .iter()
+                    .map(|option| option.explain_grouped())
+                    .join(" or ")
+            ),
+            Expression::AnyChar(_) => "any character".to_string(),
+            Expression::CharacterClass(char_set, _) => format!(
+                "one of {}",
+                char_set.iter().map(|c| format!("{:?}", c)).join(", ")
+            ),
+            Expression::Concatenation(expr1, expr2, _) => {
+                format!("{}, then {}", expr1.explain(), expr2.explain())
+            }
+            Expression::Literal(cluster, _) => {
+                if cluster.is_empty() {
+                    return "the empty string".to_string();
+                }
+                explain_graphemes(cluster.graphemes())
+            }
+            Expression::Repetition(expr, Quantifier::KleeneStar, _)
+                if matches!(**expr, Expression::AnyChar(_)) =>
+            {
+                "any characters".to_string()
+            }
+            Expression::Repetition(expr, quantifier, _) => {
+                let expr = expr.explain_grouped();
+                match quantifier {
+                    Quantifier::AtLeast(min) => format!("{} at least {} times", expr, min),
+                    Quantifier::KleeneStar => format!("{} zero or more times", expr),
+                    Quantifier::Plus => format!("{} one or more times", expr),
+                    Quantifier::QuestionMark => format!("optionally {}", expr),
+                    Quantifier::Range(min, max) if min == max => {
+                        format!("{} {} times", expr, min)
+                    }
+                    Quantifier::Range(min, max) => {
+                        format!("{} between {} and {} times", expr, min, max)
+                    }
+                }
+            }
+            Expression::WordBoundary(_) => "a word boundary".to_string(),
+        }
+    }
+
+    fn explain_grouped(&self) -> String {
+        match self {
+            Expression::Alternation(_, _) | Expression::Concatenation(_, _, _) => {
+                format!("({})", self.explain())
+            }
+            _ => self.explain(),
+        }
+    }
+}
+
+fn explain_graphemes(graphemes: &[Grapheme]) -> String {
+    let mut parts = vec![];
+    let mut literal = String::new();
+
+    for grapheme in graphemes {
+        let is_plain = !grapheme.has_repetitions()
+            && grapheme.minimum() == 1
+            && grapheme.maximum() == 1
+            && explain_char_class(&grapheme.value()).is_none();
+
+        if is_plain {
+            literal.push_str(&grapheme.value());
+            continue;
+        }
+        if !literal.is_empty() {
+            parts.push(format!("{:?}", literal));
+            literal.clear();
+        }
+        parts.push(explain_grapheme(grapheme));
+    }
+    if !literal.is_empty() {
+        parts.push(format!("{:?}", literal));
+    }
+
+    parts.join(", then ")
+}
+
+fn explain_grapheme(grapheme: &Grapheme) -> String {
+    let value = if grapheme.has_repetitions() {
+        let repetitions = explain_graphemes(&grapheme.repetitions);
+        if grapheme.repetitions.len() > 1 {
+            format!("({})", repetitions)
+        } else {
+            repetitions
+        }
+    } else {
+        explain_char_class(&grapheme.value()).unwrap_or_else(|| format!("{:?}", grapheme.value()))
+    };
+    let (min, max) = (grapheme.minimum(), grapheme.maximum());
+
+    if grapheme.is_repetition_count_exceeded() {
+        format!("{} one or more times", value)
+    } else if min == 1 && max == 1 {
+        value
+    } else if min == max {
+        format!("{} {} times", value, min)
+    } else {
+        format!("{} between {} and {} times", value, min, max)
+    }
+}
+
+fn explain_char_class(value: &str) -> Option<String> {
+    let explanation = match value {
+        "\\d" => "a digit",
+        "\\D" => "a non-digit",
+        "\\s" => "a whitespace character",
+        "\\S" => "a non-whitespace character",
+        "\\w" => "a word character",
+        "\\W" => "a non-word character",
+        _ => return None,
+    };
+    Some(explanation.to_string())
+}
@@ -15,179 +15,1296 @@
  */
 
 use crate::ast::{Quantifier, Substring};
-use crate::char::{Grapheme, GraphemeCluster};
+use crate::char::{hex_digit_ranges, Grapheme, GraphemeCluster, GraphemeKind};
 use crate::fsm::Dfa;
-use crate::regexp::RegExpConfig;
+use crate::regexp::{Error, MarkedPart, ProgressEvent, RegExpConfig};
+use crate::unicode_tables::{is_dash, DASH};
 use itertools::EitherOrBoth::Both;
 use itertools::Itertools;
-use ndarray::{Array1, Array2};
 use petgraph::prelude::EdgeRef;
-use std::cmp::Reverse;
-use std::collections::BTreeSet;
+use std::borrow::Cow;
+use std::cmp::{max, Reverse};
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+use std::time::Instant;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Expression {
-    Alternation(Vec<Expression>, RegExpConfig),
-    CharacterClass(BTreeSet<char>, RegExpConfig),
-    Concatenation(Box<Expression>, Box<Expression>, RegExpConfig),
-    Literal(GraphemeCluster, RegExpConfig),
-    Repetition(Box<Expression>, Quantifier, RegExpConfig),
+    Alternation(Vec<Expression>, Rc<RegExpConfig>),
+    AnyChar(Rc<RegExpConfig>),
+    CharacterClass(BTreeSet<char>, Rc<RegExpConfig>),
+    Concatenation(Box<Expression>, Box<Expression>, Rc<RegExpConfig>),
+    Literal(GraphemeCluster, Rc<RegExpConfig>),
+    Repetition(Box<Expression>, Quantifier, Rc<RegExpConfig>),
+    WordBoundary(Rc<RegExpConfig>),
+}
+
+/// Upper bounds of the nesting depth and the size of an expression built while the automaton
+/// is converted. They are derived from the bounds of the combined expressions, which is cheap
+/// compared to traversing the result. A missing expression has zero bounds.
+#[derive(Clone, Copy, Debug, Default)]
+struct Bounds {
+    depth: usize,
+    size: usize,
+}
+
+impl Bounds {
+    fn of(expr: &Expression) -> Self {
+        Self {
+            depth: expr.nesting_depth(),
+            size: expr.size(),
+        }
+    }
+
+    fn is_missing(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Factoring common affixes out of the options may split literals, which adds up to
+    /// two concatenations, and an empty option makes the alternation optional.
+    fn union(self, other: Option<Self>) -> Self {
+        match other {
+            Some(other) if !other.is_missing() && !self.is_missing() => Self {
+                depth: max(self.depth, other.depth) + 2,
+                size: self.size + other.size + 3,
+            },
+            Some(other) if self.is_missing() => other,
+            _ => self,
+        }
+    }
+
+    fn concatenation(self, other: Self) -> Self {
+        if self.is_missing() || other.is_missing() {
+            Self::default()
+        } else {
+            Self {
+                depth: max(self.depth, other.depth),
+                size: self.size + other.size + 1,
+            }
+        }
+    }
+
+    fn repetition(self) -> Self {
+        if self.is_missing() {
+            self
+        } else {
+            Self {
+                depth: self.depth + 1,
+                size: self.size + 1,
+            }
+        }
+    }
 }
 
 impl Expression {
-    pub(crate) fn from(dfa: Dfa, config: &RegExpConfig) -> Self {
+    /// Returns `None` if the deadline passes before all states have been eliminated.
+    pub(crate) fn from(
+        dfa: Dfa,
+        config: &Rc<RegExpConfig>,
+        progress: Option<&dyn Fn(ProgressEvent)>,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Self>, Error> {
         let states = dfa.states_in_depth_first_order();
         let state_count = dfa.state_count();
 
-        let mut a = Array2::<Option<Expression>>::default((state_count, state_count));
-        let mut b = Array1::<Option<Expression>>::default(state_count);
+        // The transitions are stored sparsely because most pairs of states are not connected,
+        // so memory scales with the number of transitions instead of state_count².
+        let mut a = HashMap::<(usize, usize), Expression>::new();
+        let mut b = vec![None; state_count];
+        let mut a_bounds = HashMap::<(usize, usize), Bounds>::new();
+        let mut b_bounds = vec![Bounds::default(); state_count];
+        let mut successors = vec![BTreeSet::<usize>::new(); state_count];
+        let mut predecessors = vec![BTreeSet::<usize>::new(); state_count];
 
         for (i, state) in states.iter().enumerate() {
             if dfa.is_final_state(*state) {
-                b[i] = Some(Expression::new_literal(
-                    GraphemeCluster::from("", config),
-                    config,
-                ));
+                let literal = Expression::new_literal(GraphemeCluster::from("", config), config);
+                b_bounds[i] = Bounds::of(&literal);
+                b[i] = Some(literal);
             }
 
             for edge in dfa.outgoing_edges(*state) {
-                let literal = Expression::new_literal(
+                let literal = Expression::new_cluster(
                     GraphemeCluster::new(edge.weight().clone(), config),
                     config,
                 );
                 let j = states.iter().position(|&it| it == edge.target()).unwrap();
+                let bounds = Bounds::of(&literal).union(a_bounds.remove(&(i, j)));
+                a_bounds.insert((i, j), bounds);
+                let expr = match a.remove(&(i, j)) {
+                    Some(expr) => Self::union(&Some(expr), &Some(literal), config).unwrap(),
+                    None => literal,
+                };
+                a.insert((i, j), expr);
+                successors[i].insert(j);
+                predecessors[j].insert(i);
+            }
+        }
 
-                a[(i, j)] = if a[(i, j)].is_some() {
-                    Self::union(&a[(i, j)], &Some(literal), config)
-                } else {
-                    Some(literal)
+        for n in (0..state_count).rev() {
+            if deadline.is_some_and(|it| Instant::now() >= it) {
+                return Ok(None);
+            }
+
+            let lower_successors = successors[n].range(..n).copied().collect_vec();
+            let lower_predecessors = predecessors[n].range(..n).copied().collect_vec();
+
+            // The automaton of finitely many test cases has no loops, so loops would only
+            // be the result of generalizing it, which is not wanted without repetitions.
+            let repetition = if config.is_repetition_disabled {
+                None
+            } else {
+                Self::repeat_zero_or_more_times(&a.get(&(n, n)).cloned(), config)
+            };
+            if repetition.is_some() {
+                let repetition_bounds = a_bounds[&(n, n)].repetition();
+                b[n] = Self::concatenate(&repetition, &b[n], config);
+                b_bounds[n] = repetition_bounds.concatenation(b_bounds[n]);
+                Self::check_limits(&b[n], &mut b_bounds[n], config)?;
+                for &j in lower_successors.iter() {
+                    let expr = a.remove(&(n, j));
+                    let concatenation = Self::concatenate(&repetition, &expr, config);
+                    let mut bounds = repetition_bounds.concatenation(a_bounds[&(n, j)]);
+                    Self::check_limits(&concatenation, &mut bounds, config)?;
+                    a.insert((n, j), concatenation.unwrap());
+                    a_bounds.insert((n, j), bounds);
+                }
+            }
+
+            for &i in lower_predecessors.iter() {
+                let expr = a.get(&(i, n)).cloned();
+                let expr_bounds = a_bounds[&(i, n)];
+                b[i] = Self::union(&b[i], &Self::concatenate(&expr, &b[n], config), config);
+                b_bounds[i] = expr_bounds
+                    .concatenation(b_bounds[n])
+                    .union(Some(b_bounds[i]));
+                Self::check_limits(&b[i], &mut b_bounds[i], config)?;
+                for &j in lower_successors.iter() {
+                    let concatenation = Self::concatenate(&expr, &a.get(&(n, j)).cloned(), config);
+                    let union = Self::union(&a.remove(&(i, j)), &concatenation, config);
+                    let mut bounds = expr_bounds
+                        .concatenation(a_bounds[&(n, j)])
+                        .union(a_bounds.remove(&(i, j)));
+                    Self::check_limits(&union, &mut bounds, config)?;
+                    a.insert((i, j), union.unwrap());
+                    a_bounds.insert((i, j), bounds);
+                    successors[i].insert(j);
+                    predecessors[j].insert(i);
                 }
             }
+
+            // Transitions from and to the eliminated state are not needed anymore.
+            for &j in successors[n].iter() {
+                a.remove(&(n, j));
+                a_bounds.remove(&(n, j));
+            }
+            for &i in predecessors[n].iter() {
+                a.remove(&(i, n));
+                a_bounds.remove(&(i, n));
+            }
+
+            if let Some(progress) = progress {
+                progress(ProgressEvent::StateEliminated {
+                    eliminated_count: state_count - n,
+                    state_count,
+                });
+            }
         }
 
-        for n in (0..state_count).rev() {
-            if a[(n, n)].is_some() {
-                b[n] = Self::concatenate(
-                    &Self::repeat_zero_or_more_times(&a[(n, n)], config),
-                    &b[n],
+        // If every test case is the empty string, the automaton consists of a single
+        // final state without any transitions, which is the empty literal.
+        if !b.is_empty() && b[0].is_some() {
+            Ok(b[0].clone())
+        } else {
+            Ok(Some(Expression::new_literal(
+                GraphemeCluster::from("", config),
+                config,
+            )))
+        }
+    }
+
+    /// Builds the plain alternation of the grapheme clusters without merging any of them,
+    /// which is cheap to compute for any number of test cases.
+    pub(crate) fn new_raw_alternation(
+        clusters: Vec<GraphemeCluster>,
+        config: &Rc<RegExpConfig>,
+    ) -> Self {
+        let (empty_clusters, clusters): (Vec<_>, Vec<_>) =
+            clusters.into_iter().partition(|it| it.is_empty());
+        let mut options = clusters
+            .into_iter()
+            .map(|it| Expression::new_cluster(it, config))
+            .collect_vec();
+        let alternation = match options.len() {
+            0 => return Expression::new_literal(GraphemeCluster::from("", config), config),
+            1 => options.remove(0),
+            _ => Expression::Alternation(options, Rc::clone(config)),
+        };
+        if empty_clusters.is_empty() {
+            alternation
+        } else {
+            Self::new_optional(alternation, config)
+        }
+    }
+
+    /// The first characters are converted like the test cases, but the remainder is matched
+    /// by a dot which matches line breaks as well if any test case contains one, so that
+    /// the fallback always matches all of the test cases.
+    pub(crate) fn new_fallback(test_cases: &[Cow<str>], config: &Rc<RegExpConfig>) -> Self {
+        let first_chars = test_cases
+            .iter()
+            .filter_map(|it| it.chars().next())
+            .collect::<BTreeSet<char>>();
+
+        let first_expr = first_chars
+            .iter()
+            .map(|c| {
+                let mut cluster = GraphemeCluster::from(&c.to_string(), config);
+                if config.is_char_class_feature_enabled() {
+                    cluster.convert_to_char_classes();
+                }
+                Expression::new_literal(cluster, config)
+            })
+            .unique_by(|it| it.to_string())
+            .reduce(|expr1, expr2| Expression::new_alternation(expr1, expr2, config))
+            .unwrap();
+
+        let is_line_break_contained = test_cases
+            .iter()
+            .any(|it| it.chars().skip(1).any(|c| c == '\n' || c == '\r'));
+        let dot_config = if is_line_break_contained && !config.is_dot_matching_newline {
+            let mut dot_config = RegExpConfig::clone(config);
+            dot_config.is_dot_matching_newline = true;
+            dot_config.is_newline_exclusion_explicit = false;
+            Rc::new(dot_config)
+        } else {
+            Rc::clone(config)
+        };
+
+        let fallback = Expression::new_concatenation(
+            first_expr,
+            Expression::new_repetition(
+                Expression::AnyChar(dot_config),
+                Quantifier::KleeneStar,
+                config,
+            ),
+            config,
+        );
+
+        if test_cases.iter().any(|it| it.is_empty()) {
+            Expression::new_repetition(fallback, Quantifier::QuestionMark, config)
+        } else {
+            fallback
+        }
+    }
+
+    /// Infers a character class for each position of test cases with the same number of
+    /// graphemes and folds consecutive equal classes into counted repetitions, such as in
+    /// `\d{3}-\d{4}`. Returns `None` if the test cases differ in length or if the graphemes
+    /// at some position cannot be combined into a character class.
+    pub(crate) fn new_positional(
+        clusters: &[GraphemeCluster],
+        config: &Rc<RegExpConfig>,
+    ) -> Option<Self> {
+        let size = clusters.first()?.size();
+        if size == 0 || clusters.iter().any(|it| it.size() != size) {
+            return None;
+        }
+
+        let mut positions = vec![];
+
+        for i in 0..size {
+            let graphemes = clusters
+                .iter()
+                .map(|it| &it.graphemes()[i])
+                .unique()
+                .collect_vec();
+
+            if graphemes.len() == 1 {
+                positions.push(Expression::new_cluster(
+                    GraphemeCluster::new(graphemes[0].clone(), config),
                     config,
-                );
-                for j in 0..n {
-                    a[(n, j)] = Self::concatenate(
-                        &Self::repeat_zero_or_more_times(&a[(n, n)], config),
-                        &a[(n, j)],
+                ));
+                continue;
+            }
+
+            let char_set = graphemes
+                .iter()
+                .map(|grapheme| match grapheme.chars().as_slice() {
+                    [s] if !grapheme.has_repetitions() && s.chars().count() == 1 => {
+                        s.chars().next()
+                    }
+                    _ => None,
+                })
+                .collect::<Option<BTreeSet<char>>>()?;
+
+            if char_set.len() < config.minimum_class_size {
+                return None;
+            }
+
+            positions.push(Expression::new_character_class(
+                char_set,
+                BTreeSet::new(),
+                config,
+            ));
+        }
+
+        let mut folded_positions = vec![];
+
+        for (count, expr) in positions.into_iter().dedup_with_count() {
+            let count = count as u32;
+            match expr {
+                Expression::CharacterClass(_, _) if count > 1 => {
+                    folded_positions.push(Expression::new_repetition(
+                        expr,
+                        Quantifier::Range(count, count),
                         config,
-                    );
+                    ));
+                }
+                Expression::Literal(cluster, _)
+                    if count > 1 && cluster.graphemes()[0].is_char_class() =>
+                {
+                    let chars = cluster.graphemes()[0].chars().clone();
+                    folded_positions.push(Expression::new_literal(
+                        GraphemeCluster::new(Grapheme::new(chars, count, count, config), config),
+                        config,
+                    ));
+                }
+                _ => {
+                    for _ in 0..count {
+                        folded_positions.push(expr.clone());
+                    }
                 }
             }
+        }
 
-            for i in 0..n {
-                if a[(i, n)].is_some() {
-                    b[i] =
-                        Self::union(&b[i], &Self::concatenate(&a[(i, n)], &b[n], config), config);
-                    for j in 0..n {
-                        a[(i, j)] = Self::union(
-                            &a[(i, j)],
-                            &Self::concatenate(&a[(i, n)], &a[(n, j)], config),
-                            config,
-                        );
+        Self::merge_adjacent_literals(folded_positions, config)
+            .into_iter()
+            .map(|expr| match expr {
+                // The positional inference bypasses the automaton, whose clusters
+                // have their repetitions converted beforehand.
+                Expression::Literal(mut cluster, _) if config.is_repetition_converted() => {
+                    cluster.convert_repetitions();
+                    Some(Expression::new_literal(cluster, config))
+                }
+                _ => Some(expr),
+            })
+            .reduce(|expr1, expr2| Self::concatenate(&expr1, &expr2, config))?
+    }
+
+    /// Merges each run of adjacent literals into a single literal at once. Concatenating
+    /// them one by one would copy the growing literal again for each of its graphemes.
+    fn merge_adjacent_literals(
+        exprs: Vec<Expression>,
+        config: &Rc<RegExpConfig>,
+    ) -> Vec<Expression> {
+        let mut merged_exprs = vec![];
+        let mut clusters = vec![];
+
+        for expr in exprs {
+            match expr {
+                Expression::Literal(cluster, _) => clusters.push(cluster),
+                _ => {
+                    if !clusters.is_empty() {
+                        merged_exprs.push(Self::merge_literals(&clusters, config));
+                        clusters.clear();
                     }
+                    merged_exprs.push(expr);
                 }
             }
         }
-
-        if !b.is_empty() && b[0].is_some() {
-            b[0].as_ref().unwrap().clone()
-        } else {
-            Expression::new_literal(GraphemeCluster::from("", config), config)
+        if !clusters.is_empty() {
+            merged_exprs.push(Self::merge_literals(&clusters, config));
         }
+        merged_exprs
+    }
+
+    fn merge_literals(clusters: &[GraphemeCluster], config: &Rc<RegExpConfig>) -> Expression {
+        Expression::new_literal(
+            GraphemeCluster::merge_all(&clusters.iter().collect_vec(), config),
+            config,
+        )
     }
 
-    fn new_alternation(expr1: Expression, expr2: Expression, config: &RegExpConfig) -> Self {
+    /// Builds the concatenation of the parts of a test case split at the optional markers,
+    /// in which each part enclosed in the markers is an optional repetition of its own,
+    /// such as `colou?r` for `colo[u]r`. The literal parts are converted like the grapheme
+    /// clusters of the test cases without markers.
+    pub(crate) fn new_marked(parts: &[MarkedPart], config: &Rc<RegExpConfig>) -> Self {
+        parts
+            .iter()
+            .map(|part| match part {
+                MarkedPart::Text(text) => {
+                    let mut cluster = GraphemeCluster::from(text, config);
+                    if let Some(wildcard) = config.wildcard {
+                        cluster.convert_wildcards(wildcard);
+                    }
+                    if config.is_char_class_feature_enabled() {
+                        cluster.convert_to_char_classes();
+                    }
+                    if config.is_repetition_converted() {
+                        cluster.convert_repetitions();
+                    }
+                    Self::new_cluster(cluster, config)
+                }
+                MarkedPart::Optional(parts) => {
+                    let expr = Self::new_marked(parts, config);
+                    if expr.is_empty() {
+                        expr
+                    } else {
+                        Self::new_repetition(expr, Quantifier::QuestionMark, config)
+                    }
+                }
+            })
+            .fold(
+                Self::new_literal(GraphemeCluster::from("", config), config),
+                |expr1, expr2| Self::concatenate(&Some(expr1), &Some(expr2), config).unwrap(),
+            )
+    }
+
+    /// Returns the union of two expressions which have been built separately from each other,
+    /// with their common prefix and suffix factored out.
+    pub(crate) fn new_union(
+        expr1: Expression,
+        expr2: Expression,
+        config: &Rc<RegExpConfig>,
+    ) -> Self {
+        Self::union(&Some(expr1), &Some(expr2), config).unwrap()
+    }
+
+    fn new_alternation(expr1: Expression, expr2: Expression, config: &Rc<RegExpConfig>) -> Self {
         let mut options: Vec<Expression> = vec![];
         Self::flatten_alternations(&mut options, vec![expr1, expr2]);
-        options.sort_by_key(|option| Reverse(option.len()));
-        Expression::Alternation(options, config.clone())
+
+        // An empty option would leave a dangling pipe, such as in a|b|, so the remaining
+        // options are made optional instead. Prefix branches which are kept distinct rely on
+        // the empty option, which is resolved once the whole expression has been built.
+        let is_empty_option_contained = !config.is_prefix_branch_kept_distinct
+            && options.iter().any(|option| option.is_empty());
+        if is_empty_option_contained {
+            options.retain(|option| !option.is_empty());
+            if options.is_empty() {
+                return Self::new_literal(GraphemeCluster::from("", config), config);
+            }
+        }
+
+        Self::merge_single_codepoint_options(&mut options, config);
+        let alternation = if options.len() == 1 {
+            options.remove(0)
+        } else {
+            // The sort is stable, so options of equal length keep the depth-first,
+            // left-to-right order in which they have been flattened.
+            options.sort_by_key(|option| Reverse(option.len()));
+            Expression::Alternation(options, Rc::clone(config))
+        };
+
+        if is_empty_option_contained {
+            Self::new_optional(alternation, config)
+        } else {
+            alternation
+        }
     }
 
     fn new_character_class(
         first_char_set: BTreeSet<char>,
         second_char_set: BTreeSet<char>,
-        config: &RegExpConfig,
+        config: &Rc<RegExpConfig>,
     ) -> Self {
-        let union_set = first_char_set.union(&second_char_set).copied().collect();
-        Expression::CharacterClass(union_set, config.clone())
+        let mut union_set: BTreeSet<char> =
+            first_char_set.union(&second_char_set).copied().collect();
+        if config.is_dash_class_extended && union_set.iter().filter(|&&c| is_dash(c)).count() > 1 {
+            for &(start, end) in DASH {
+                union_set.extend(start..=end);
+            }
+        }
+        Expression::CharacterClass(union_set, Rc::clone(config))
     }
 
-    fn new_concatenation(expr1: Expression, expr2: Expression, config: &RegExpConfig) -> Self {
-        Expression::Concatenation(Box::from(expr1), Box::from(expr2), config.clone())
+    fn new_concatenation(expr1: Expression, expr2: Expression, config: &Rc<RegExpConfig>) -> Self {
+        Expression::Concatenation(Box::from(expr1), Box::from(expr2), Rc::clone(config))
     }
 
-    fn new_literal(cluster: GraphemeCluster, config: &RegExpConfig) -> Self {
-        Expression::Literal(cluster, config.clone())
+    pub(crate) fn new_literal(cluster: GraphemeCluster, config: &Rc<RegExpConfig>) -> Self {
+        Expression::Literal(cluster, Rc::clone(config))
     }
 
-    fn new_repetition(expr: Expression, quantifier: Quantifier, config: &RegExpConfig) -> Self {
-        Expression::Repetition(Box::from(expr), quantifier, config.clone())
+    /// Creates a literal of the grapheme cluster unless it contains wildcards,
+    /// in which case the literals between them are concatenated with the wildcards.
+    fn new_cluster(cluster: GraphemeCluster, config: &Rc<RegExpConfig>) -> Self {
+        if cluster
+            .graphemes()
+            .iter()
+            .all(|it| it.kind() != GraphemeKind::Wildcard)
+        {
+            return Self::new_literal(cluster, config);
+        }
+        let exprs = cluster
+            .graphemes()
+            .iter()
+            .map(|it| match it.kind() {
+                GraphemeKind::Wildcard => Self::new_wildcard(config),
+                _ => Self::new_literal(GraphemeCluster::new(it.clone(), config), config),
+            })
+            .collect_vec();
+        Self::merge_adjacent_literals(exprs, config)
+            .into_iter()
+            .map(Some)
+            .reduce(|expr1, expr2| Self::concatenate(&expr1, &expr2, config))
+            .flatten()
+            .unwrap()
+    }
+
+    /// A wildcard matches any run of characters, or of non-whitespace characters only
+    /// if whitespace is excluded.
+    fn new_wildcard(config: &Rc<RegExpConfig>) -> Self {
+        let expr = if config.is_wildcard_whitespace_excluded {
+            Self::new_literal(
+                GraphemeCluster::new(Grapheme::from("\\S", config), config),
+                config,
+            )
+        } else {
+            Expression::AnyChar(Rc::clone(config))
+        };
+        Self::new_repetition(expr, Quantifier::KleeneStar, config)
+    }
+
+    fn new_separator(separators: &BTreeSet<char>, config: &Rc<RegExpConfig>) -> Self {
+        let separator = if separators.len() == 1 {
+            let c = separators.iter().next().unwrap();
+            Self::new_literal(GraphemeCluster::from(&c.to_string(), config), config)
+        } else {
+            Expression::CharacterClass(separators.clone(), Rc::clone(config))
+        };
+        Self::new_repetition(separator, Quantifier::QuestionMark, config)
+    }
+
+    fn new_optional(expr: Expression, config: &Rc<RegExpConfig>) -> Self {
+        match expr {
+            // (?:x*)? matches the same language as x*, so stacking both quantifiers
+            // is nothing but noise.
+            Expression::Repetition(_, Quantifier::KleeneStar, _) => expr,
+            _ => match Self::fold_into_upper_bounded_repetition(&expr, config) {
+                Some(repetition) => repetition,
+                None => Self::new_repetition(expr, Quantifier::QuestionMark, config),
+            },
+        }
+    }
+
+    /// Folds `x` followed by up to n - 1 optional copies of `x`, such as in `(?:xx?)?`,
+    /// or repetitions of `x` between 1 and n times, such as in `(?:x{1,n})?` or
+    /// `(?:x|x{2,n})?`, into the optional counted repetition `x{0,n}`. Like any other
+    /// counted repetition, this is done only if repetitions are converted and n exceeds
+    /// their minimum quantity.
+    fn fold_into_upper_bounded_repetition(
+        expr: &Expression,
+        config: &Rc<RegExpConfig>,
+    ) -> Option<Expression> {
+        if !config.is_repetition_converted() {
+            return None;
+        }
+
+        let (unit, max) = match expr {
+            Expression::Concatenation(first, _, _) => {
+                (*first.clone(), Self::optional_chain_length(expr, first)?)
+            }
+            Expression::Repetition(inner, Quantifier::Range(1, max), _) => (*inner.clone(), *max),
+            Expression::Literal(_, _) | Expression::Alternation(_, _) => {
+                let options = match expr {
+                    Expression::Alternation(options, _) => options.iter().collect_vec(),
+                    _ => vec![expr],
+                };
+                let mut repeated_units = options
+                    .into_iter()
+                    .map(Self::repeated_unit)
+                    .collect::<Option<Vec<_>>>()?;
+                repeated_units.sort_by_key(|(_, min, _)| *min);
+
+                let (unit, _, _) = repeated_units[0].clone();
+                let mut max = 0;
+                for (other_unit, other_min, other_max) in repeated_units {
+                    if other_unit != unit || other_min > max + 1 {
+                        return None;
+                    }
+                    max = max.max(other_max);
+                }
+                // A single occurrence is not a repetition to be folded.
+                if max == 1 {
+                    return None;
+                }
+                let graphemes = unit
+                    .into_iter()
+                    .map(|it| Grapheme::new(vec![it], 1, 1, config))
+                    .collect_vec();
+                let unit = Expression::new_literal(
+                    GraphemeCluster::from_graphemes(graphemes, config),
+                    config,
+                );
+                (unit, max)
+            }
+            _ => return None,
+        };
+
+        if max <= config.minimum_repetitions || config.is_repetition_count_exceeded(max) {
+            return None;
+        }
+        Some(Self::new_repetition(
+            unit,
+            Quantifier::Range(0, max),
+            config,
+        ))
+    }
+
+    /// Returns how many copies of `unit` a chain such as `x(?:x(?:xx?)?)?` or `xx{0,2}` matches
+    /// at most, provided that the first copy is required and all the others are optional.
+    fn optional_chain_length(expr: &Expression, unit: &Expression) -> Option<u32> {
+        if expr == unit {
+            return Some(1);
+        }
+        match expr {
+            Expression::Concatenation(first, second, _) if **first == *unit => match &**second {
+                Expression::Repetition(inner, quantifier, _) if **inner == *unit => {
+                    match quantifier.bounds() {
+                        (0, Some(max)) => Some(max + 1),
+                        _ => None,
+                    }
+                }
+                Expression::Repetition(inner, Quantifier::QuestionMark, _) => {
+                    Some(Self::optional_chain_length(inner, unit)? + 1)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the characters a literal repeats, together with the minimum and
+    /// the maximum number of repetitions, such as `ab`, 2 and 3 for `(?:ab){2,3}`.
+    fn repeated_unit(expr: &Expression) -> Option<(Vec<String>, u32, u32)> {
+        let cluster = match expr {
+            Expression::Literal(cluster, _) if !cluster.is_empty() => cluster,
+            _ => return None,
+        };
+        if cluster.graphemes().iter().any(|it| it.has_repetitions()) {
+            return None;
+        }
+
+        match cluster.graphemes().as_slice() {
+            [grapheme] if !grapheme.is_repetition_count_exceeded() => Some((
+                grapheme.chars().clone(),
+                grapheme.minimum(),
+                grapheme.maximum(),
+            )),
+            graphemes if graphemes.iter().all(|it| it.maximum() == 1) => Some((
+                graphemes
+                    .iter()
+                    .flat_map(|it| it.chars().clone())
+                    .collect_vec(),
+                1,
+                1,
+            )),
+            _ => None,
+        }
+    }
+
+    fn new_repetition(expr: Expression, quantifier: Quantifier, config: &Rc<RegExpConfig>) -> Self {
+        Expression::Repetition(Box::from(expr), quantifier, Rc::clone(config))
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        match self {
+            Expression::Literal(cluster, _) => cluster.is_empty(),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn is_single_codepoint(&self) -> bool {
+        match self {
+            Expression::AnyChar(_) | Expression::CharacterClass(_, _) => true,
+            Expression::Literal(cluster, config) => {
+                cluster.char_count(config.is_non_ascii_char_escaped) == 1
+                    && cluster.graphemes().first().unwrap().maximum() == 1
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if the expression is a single shorthand or POSIX class such as `\S`,
+    /// which matches a single code point although it is written with several characters.
+    pub(crate) fn is_shorthand_class(&self) -> bool {
+        matches!(self, Expression::Literal(cluster, _)
+            if matches!(cluster.graphemes().as_slice(), [grapheme]
+                if grapheme.is_char_class() && grapheme.maximum() == 1))
+    }
+
+    /// Returns true if the expression is a wildcard taken from the test cases,
+    /// such as `.*` or `\S*`.
+    fn is_wildcard(&self) -> bool {
+        matches!(self, Expression::Repetition(expr, Quantifier::KleeneStar, config)
+            if config.wildcard.is_some()
+                && (matches!(**expr, Expression::AnyChar(_)) || expr.is_shorthand_class()))
+    }
+
+    /// Returns true if the expression is a concatenation of literals and wildcards only,
+    /// as built from test cases containing wildcards. The literals at both of its ends
+    /// take part in factoring common affixes just like a single literal.
+    fn is_wildcard_concatenation(&self) -> bool {
+        match self {
+            Expression::Concatenation(expr1, expr2, _) => [expr1, expr2].iter().all(|it| {
+                matches!(***it, Expression::Literal(_, _))
+                    || it.is_wildcard()
+                    || it.is_wildcard_concatenation()
+            }),
+            _ => false,
+        }
+    }
+
+    /// Returns true if every string matched by the expression has the same length.
+    /// Alternations are not examined further and are treated as having several lengths.
+    pub(crate) fn is_fixed_length(&self) -> bool {
+        match self {
+            Expression::Alternation(_, _) => false,
+            Expression::AnyChar(_)
+            | Expression::CharacterClass(_, _)
+            | Expression::WordBoundary(_) => true,
+            Expression::Concatenation(expr1, expr2, _) => {
+                expr1.is_fixed_length() && expr2.is_fixed_length()
+            }
+            Expression::Literal(cluster, _) => {
+                cluster.graphemes().iter().all(|it| it.is_fixed_length())
+            }
+            Expression::Repetition(expr, quantifier, _) => {
+                let (min, max) = quantifier.bounds();
+                max == Some(min) && expr.is_fixed_length()
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Expression::Alternation(options, _) => options.first().unwrap().len(),
+            Expression::AnyChar(_) | Expression::CharacterClass(_, _) => 1,
+            Expression::Concatenation(expr1, expr2, _) => expr1.len() + expr2.len(),
+            Expression::Literal(cluster, _) => cluster.size(),
+            Expression::Repetition(expr, _, _) => expr.len(),
+            Expression::WordBoundary(_) => 0,
+        }
+    }
+
+    pub(crate) fn precedence(&self) -> u8 {
+        match self {
+            Expression::Alternation(_, _)
+            | Expression::AnyChar(_)
+            | Expression::CharacterClass(_, _) => 1,
+            Expression::Concatenation(_, _, _)
+            | Expression::Literal(_, _)
+            | Expression::WordBoundary(_) => 2,
+            Expression::Repetition(_, _, _) => 3,
+        }
+    }
+
+    pub(crate) fn with_separators(self, separators: &BTreeSet<char>) -> Self {
+        match self {
+            Expression::Alternation(options, config) => Expression::Alternation(
+                options
+                    .into_iter()
+                    .map(|it| it.with_separators(separators))
+                    .collect_vec(),
+                config,
+            ),
+            Expression::Concatenation(expr1, expr2, config) => Self::new_concatenation(
+                expr1.with_separators(separators),
+                Self::new_concatenation(
+                    Self::new_separator(separators, &config),
+                    expr2.with_separators(separators),
+                    &config,
+                ),
+                &config,
+            ),
+            Expression::Literal(cluster, config) if cluster.size() > 1 => cluster
+                .graphemes()
+                .iter()
+                .map(|it| Self::new_literal(GraphemeCluster::new(it.clone(), &config), &config))
+                .reduce(|expr1, expr2| {
+                    Self::new_concatenation(
+                        expr1,
+                        Self::new_concatenation(
+                            Self::new_separator(separators, &config),
+                            expr2,
+                            &config,
+                        ),
+                        &config,
+                    )
+                })
+                .unwrap(),
+            Expression::Repetition(expr, quantifier, config) => {
+                Self::new_repetition(expr.with_separators(separators), quantifier, &config)
+            }
+            other => other,
+        }
+    }
+
+    /// Distributes the expression preceding an alternation with an empty option
+    /// over all of its options, so that a test case which is a prefix of other
+    /// test cases ends up in an alternative of its own.
+    pub(crate) fn with_distinct_prefix_branches(self) -> Self {
+        match self {
+            // Options of an alternation are never alternations themselves,
+            // so an empty option left over here is written as optionality.
+            Expression::Alternation(options, config) => {
+                let (empty_options, options): (Vec<_>, Vec<_>) = options
+                    .into_iter()
+                    .map(|it| it.with_distinct_prefix_branches())
+                    .partition(|it| it.is_empty());
+                match options
+                    .into_iter()
+                    .reduce(|expr1, expr2| Self::new_alternation(expr1, expr2, &config))
+                {
+                    Some(alternation) if !empty_options.is_empty() => {
+                        Self::new_optional(alternation, &config)
+                    }
+                    Some(alternation) => alternation,
+                    None => Self::new_literal(GraphemeCluster::from("", &config), &config),
+                }
+            }
+            Expression::Concatenation(expr1, expr2, config) => {
+                let expr1 = Some(expr1.with_distinct_prefix_branches());
+                match *expr2 {
+                    Expression::Alternation(options, _)
+                        if options.iter().any(|it| it.is_empty()) =>
+                    {
+                        // Alternations resulting from nested distributions are flattened
+                        // so that the prefix is distributed over their options as well.
+                        let mut flattened_options = vec![];
+                        Self::flatten_alternations(
+                            &mut flattened_options,
+                            options
+                                .into_iter()
+                                .map(|it| it.with_distinct_prefix_branches())
+                                .collect_vec(),
+                        );
+                        flattened_options
+                            .into_iter()
+                            .filter_map(|it| Self::concatenate(&expr1, &Some(it), &config))
+                            .reduce(|expr1, expr2| Self::new_alternation(expr1, expr2, &config))
+                            .unwrap()
+                    }
+                    expr2 => {
+                        let expr2 = Some(expr2.with_distinct_prefix_branches());
+                        Self::concatenate(&expr1, &expr2, &config).unwrap()
+                    }
+                }
+            }
+            Expression::Repetition(expr, quantifier, config) => {
+                Self::new_repetition(expr.with_distinct_prefix_branches(), quantifier, &config)
+            }
+            other => other,
+        }
+    }
+
+    /// Distributes the literals which are shorter than the minimum common substring length
+    /// over the alternation or optional group they precede or follow. The automaton merges
+    /// common prefixes regardless of their length, so the test cases `abc` and `ade` would
+    /// result in `a(?:bc|de)` otherwise.
+    pub(crate) fn with_expanded_short_affixes(self) -> Self {
+        match self {
+            Expression::Alternation(options, config) => {
+                let mut flattened_options = vec![];
+                Self::flatten_alternations(
+                    &mut flattened_options,
+                    options
+                        .into_iter()
+                        .map(|it| it.with_expanded_short_affixes())
+                        .collect_vec(),
+                );
+                Expression::Alternation(flattened_options, config)
+            }
+            Expression::Concatenation(_, _, ref config) => {
+                let config = config.clone();
+                let mut elements = vec![];
+                Self::flatten_concatenations(&mut elements, self);
+
+                let elements = elements
+                    .into_iter()
+                    .map(|it| it.with_expanded_short_affixes())
+                    .collect_vec();
+                // Prefixes are distributed first, as the automaton merges them.
+                let elements =
+                    Self::distribute_short_literals(elements, &Substring::Prefix, &config);
+                let elements =
+                    Self::distribute_short_literals(elements, &Substring::Suffix, &config);
+
+                elements
+                    .into_iter()
+                    .reduce(|expr1, expr2| Self::new_concatenation(expr1, expr2, &config))
+                    .unwrap()
+            }
+            Expression::Repetition(expr, quantifier, config) => {
+                Self::new_repetition(expr.with_expanded_short_affixes(), quantifier, &config)
+            }
+            other => other,
+        }
+    }
+
+    fn distribute_short_literals(
+        elements: Vec<Expression>,
+        substring: &Substring,
+        config: &Rc<RegExpConfig>,
+    ) -> Vec<Expression> {
+        let mut distributed_elements: Vec<Expression> = vec![];
+        for element in elements {
+            let previous = match distributed_elements.pop() {
+                Some(previous) => previous,
+                None => {
+                    distributed_elements.push(element);
+                    continue;
+                }
+            };
+            let (literal, group) = match substring {
+                Substring::Prefix => (&previous, &element),
+                Substring::Suffix => (&element, &previous),
+            };
+            let is_short_literal = matches!(
+                literal,
+                Expression::Literal(cluster, _)
+                    if !cluster.is_empty() && cluster.size() < config.minimum_common_substring_length
+            );
+            match Self::grouped_options(group).filter(|_| is_short_literal) {
+                Some(options) => {
+                    let literal = Some(literal.clone());
+                    let options = options
+                        .into_iter()
+                        .filter_map(|it| match substring {
+                            Substring::Prefix => Self::concatenate(&literal, &Some(it), config),
+                            Substring::Suffix => Self::concatenate(&Some(it), &literal, config),
+                        })
+                        .collect_vec();
+                    distributed_elements.push(Expression::Alternation(options, Rc::clone(config)));
+                }
+                None => {
+                    distributed_elements.push(previous);
+                    distributed_elements.push(element);
+                }
+            }
+        }
+        distributed_elements
+    }
+
+    /// Returns the options of an alternation or of an optional group, the latter
+    /// including the empty option, or `None` if the expression is written without a group.
+    fn grouped_options(expr: &Expression) -> Option<Vec<Expression>> {
+        match expr {
+            Expression::Alternation(options, _) => Some(options.clone()),
+            Expression::Repetition(inner, Quantifier::QuestionMark, config)
+                if !inner.is_single_codepoint() =>
+            {
+                let mut options = inner.alternatives();
+                options.push(Self::new_literal(GraphemeCluster::from("", config), config));
+                Some(options)
+            }
+            _ => None,
+        }
+    }
+
+    /// Prepends any number of zeros to the expression, which matches numbers
+    /// without leading zeros only.
+    pub(crate) fn with_optional_leading_zeros(self, config: &Rc<RegExpConfig>) -> Self {
+        let zero = Self::new_literal(GraphemeCluster::from("0", config), config);
+        let zeros = Self::new_repetition(zero, Quantifier::KleeneStar, config);
+        Self::new_concatenation(zeros, self, config)
+    }
+
+    pub(crate) fn with_word_boundaries(self) -> Self {
+        match self {
+            Expression::Alternation(options, config) => Expression::Alternation(
+                options
+                    .into_iter()
+                    .map(|it| it.with_word_boundaries())
+                    .collect_vec(),
+                config,
+            ),
+            Expression::Concatenation(expr1, expr2, config) => Self::new_concatenation(
+                expr1.with_word_boundaries(),
+                expr2.with_word_boundaries(),
+                &config,
+            ),
+            Expression::Literal(cluster, config) => {
+                let mut clusters: Vec<Vec<Grapheme>> = vec![];
+                for grapheme in cluster.graphemes() {
+                    match clusters.last_mut() {
+                        Some(current)
+                            if !Self::is_word_boundary(current.last().unwrap(), grapheme) =>
+                        {
+                            current.push(grapheme.clone())
+                        }
+                        _ => clusters.push(vec![grapheme.clone()]),
+                    }
+                }
+                clusters
+                    .into_iter()
+                    .map(|it| {
+                        Self::new_literal(GraphemeCluster::from_graphemes(it, &config), &config)
+                    })
+                    .reduce(|expr1, expr2| {
+                        Self::new_concatenation(
+                            expr1,
+                            Self::new_concatenation(
+                                Expression::WordBoundary(config.clone()),
+                                expr2,
+                                &config,
+                            ),
+                            &config,
+                        )
+                    })
+                    .unwrap_or(Expression::Literal(cluster, config))
+            }
+            Expression::Repetition(expr, quantifier, config) => {
+                Self::new_repetition(expr.with_word_boundaries(), quantifier, &config)
+            }
+            other => other,
+        }
+    }
+
+    /// Collapses adjacent alternations which are structurally equal, such as
+    /// `(?:ab|cd)(?:ab|cd)`, into a counted repetition like `(?:ab|cd){2}`.
+    pub(crate) fn with_collapsed_alternation_repeats(self) -> Self {
+        self.with_collapsed_repeats(&|expr| matches!(expr, Expression::Alternation(_, _)))
+    }
+
+    /// Collapses adjacent classes of hexadecimal digits, such as `[0-9a-f][0-9a-f]`,
+    /// into a counted repetition like `[0-9a-f]{2}`.
+    pub(crate) fn with_collapsed_hex_class_repeats(self) -> Self {
+        self.with_collapsed_repeats(&|expr| {
+            matches!(expr, Expression::CharacterClass(char_set, _)
+                if hex_digit_ranges(char_set).is_some())
+        })
+    }
+
+    fn with_collapsed_repeats(self, is_collapsible: &dyn Fn(&Expression) -> bool) -> Self {
+        match self {
+            Expression::Alternation(options, config) => Expression::Alternation(
+                options
+                    .into_iter()
+                    .map(|it| it.with_collapsed_repeats(is_collapsible))
+                    .collect_vec(),
+                config,
+            ),
+            Expression::Concatenation(_, _, ref config) => {
+                let config = config.clone();
+                let mut elements = vec![];
+                Self::flatten_concatenations(&mut elements, self);
+
+                let mut repeats: Vec<(Expression, u32)> = vec![];
+                for element in elements {
+                    let element = element.with_collapsed_repeats(is_collapsible);
+                    match repeats.last_mut() {
+                        Some((previous, count))
+                            if is_collapsible(&element) && *previous == element =>
+                        {
+                            *count += 1
+                        }
+                        _ => repeats.push((element, 1)),
+                    }
+                }
+
+                repeats
+                    .into_iter()
+                    .map(|(element, count)| match count {
+                        1 => element,
+                        _ => {
+                            Self::new_repetition(element, Quantifier::Range(count, count), &config)
+                        }
+                    })
+                    .reduce(|expr1, expr2| Self::new_concatenation(expr1, expr2, &config))
+                    .unwrap()
+            }
+            Expression::Repetition(expr, quantifier, config) => Self::new_repetition(
+                expr.with_collapsed_repeats(is_collapsible),
+                quantifier,
+                &config,
+            ),
+            other => other,
+        }
+    }
+
+    /// Collects the elements of nested concatenations from left to right.
+    fn flatten_concatenations(elements: &mut Vec<Expression>, expr: Expression) {
+        if let Expression::Concatenation(expr1, expr2, _) = expr {
+            Self::flatten_concatenations(elements, *expr1);
+            Self::flatten_concatenations(elements, *expr2);
+        } else {
+            elements.push(expr);
+        }
+    }
+
+    // Only transitions between a converted \w or \d run and a literal
+    // non-word character are considered, as only those are known for sure.
+    fn is_word_boundary(first: &Grapheme, second: &Grapheme) -> bool {
+        (first.is_word_class() && second.is_non_word_char())
+            || (first.is_non_word_char() && second.is_word_class())
+    }
+
+    /// Returns the options of this expression if it is an alternation,
+    /// including those of nested alternations, or the expression itself otherwise.
+    pub(crate) fn alternatives(&self) -> Vec<Expression> {
+        match self {
+            Expression::Alternation(options, _) => {
+                let mut alternatives = vec![];
+                Self::flatten_alternations(&mut alternatives, options.clone());
+                alternatives
+            }
+            _ => vec![self.clone()],
+        }
     }
 
-    fn is_empty(&self) -> bool {
+    pub(crate) fn alternation_branch_count(&self) -> usize {
         match self {
-            Expression::Literal(cluster, _) => cluster.is_empty(),
-            _ => false,
+            Expression::Alternation(options, _) => options
+                .iter()
+                .map(|option| option.alternation_branch_count())
+                .max()
+                .unwrap_or(0)
+                .max(options.len()),
+            Expression::Concatenation(expr1, expr2, _) => max(
+                expr1.alternation_branch_count(),
+                expr2.alternation_branch_count(),
+            ),
+            Expression::Repetition(expr, _, _) => expr.alternation_branch_count(),
+            _ => 0,
         }
     }
 
-    pub(crate) fn is_single_codepoint(&self) -> bool {
-        match self {
-            Expression::CharacterClass(_, _) => true,
-            Expression::Literal(cluster, config) => {
-                cluster.char_count(config.is_non_ascii_char_escaped) == 1
-                    && cluster.graphemes().first().unwrap().maximum() == 1
+    /// Deeply nested expressions are processed recursively and large expressions are cloned
+    /// repeatedly while the automaton is converted, so the conversion is aborted as soon as
+    /// an intermediate expression exceeds one of the limits.
+    ///
+    /// Traversing each intermediate expression would make the conversion quadratic, so the
+    /// expression is only measured if its bounds exceed a limit. As the bounds never fall
+    /// below the actual values, no expression exceeding a limit is missed. If the expression
+    /// turns out to be within the limits, the bounds are lowered to its actual values.
+    fn check_limits(
+        expr: &Option<Expression>,
+        bounds: &mut Bounds,
+        config: &Rc<RegExpConfig>,
+    ) -> Result<(), Error> {
+        let is_depth_exceeded = config
+            .maximum_nesting_depth
+            .is_some_and(|limit| bounds.depth > limit);
+        let is_size_exceeded = config
+            .maximum_expression_size
+            .is_some_and(|limit| bounds.size > limit);
+
+        if let Some(expr) = expr
+            .as_ref()
+            .filter(|_| is_depth_exceeded || is_size_exceeded)
+        {
+            *bounds = Bounds::of(expr);
+            if let Some(limit) = config.maximum_nesting_depth {
+                if bounds.depth > limit {
+                    return Err(Error::NestingTooDeep {
+                        depth: bounds.depth,
+                        limit,
+                    });
+                }
+            }
+            if let Some(limit) = config.maximum_expression_size {
+                if bounds.size > limit {
+                    return Err(Error::ExpressionTooLarge {
+                        size: bounds.size,
+                        limit,
+                    });
+                }
             }
-            _ => false,
         }
+        Ok(())
     }
 
-    fn len(&self) -> usize {
-        match self {
-            Expression::Alternation(options, _) => options.first().unwrap().len(),
-            Expression::CharacterClass(_, _) => 1,
-            Expression::Concatenation(expr1, expr2, _) => expr1.len() + expr2.len(),
-            Expression::Literal(cluster, _) => cluster.size(),
-            Expression::Repetition(expr, _, _) => expr.len(),
+    /// Returns the maximum number of alternations and repetitions nested within each other.
+    /// The expression tree is traversed iteratively, so that arbitrarily deep expressions
+    /// can be checked without overflowing the stack.
+    pub(crate) fn nesting_depth(&self) -> usize {
+        let mut max_depth = 0;
+        let mut stack = vec![(self, 0)];
+
+        while let Some((expr, depth)) = stack.pop() {
+            match expr {
+                Expression::Alternation(options, _) => {
+                    max_depth = max(max_depth, depth + 1);
+                    stack.extend(options.iter().map(|option| (option, depth + 1)));
+                }
+                Expression::Concatenation(expr1, expr2, _) => {
+                    stack.push((expr1, depth));
+                    stack.push((expr2, depth));
+                }
+                Expression::Repetition(expr, _, _) => {
+                    max_depth = max(max_depth, depth + 1);
+                    stack.push((expr, depth + 1));
+                }
+                _ => {}
+            }
         }
+
+        max_depth
     }
 
-    pub(crate) fn precedence(&self) -> u8 {
-        match self {
-            Expression::Alternation(_, _) | Expression::CharacterClass(_, _) => 1,
-            Expression::Concatenation(_, _, _) | Expression::Literal(_, _) => 2,
-            Expression::Repetition(_, _, _) => 3,
+    /// Returns the number of subexpressions including the expression itself, where each
+    /// literal counts once per grapheme, so that long literals are not considered small.
+    /// Like the nesting depth, it is computed iteratively to support arbitrarily deep expressions.
+    pub(crate) fn size(&self) -> usize {
+        let mut size = 0;
+        let mut stack = vec![self];
+
+        while let Some(expr) = stack.pop() {
+            size += match expr {
+                Expression::Literal(cluster, _) => max(cluster.size(), 1),
+                _ => 1,
+            };
+            match expr {
+                Expression::Alternation(options, _) => stack.extend(options.iter()),
+                Expression::Concatenation(expr1, expr2, _) => {
+                    stack.push(expr1);
+                    stack.push(expr2);
+                }
+                Expression::Repetition(expr, _, _) => stack.push(expr),
+                _ => {}
+            }
         }
+
+        size
     }
 
     pub(crate) fn remove_substring(&mut self, substring: &Substring, length: usize) {
         match self {
             Expression::Concatenation(expr1, expr2, _) => match substring {
                 Substring::Prefix => {
-                    if let Expression::Literal(_, _) = **expr1 {
-                        expr1.remove_substring(substring, length)
+                    if matches!(**expr1, Expression::Literal(_, _))
+                        || expr1.is_wildcard_concatenation()
+                    {
+                        expr1.remove_substring(substring, length);
+                        // an exhausted literal must not linger as an empty operand
+                        if expr1.is_empty() {
+                            *self = *expr2.clone();
+                        }
                     }
                 }
                 Substring::Suffix => {
-                    if let Expression::Literal(_, _) = **expr2 {
-                        expr2.remove_substring(substring, length)
+                    if matches!(**expr2, Expression::Literal(_, _))
+                        || expr2.is_wildcard_concatenation()
+                    {
+                        expr2.remove_substring(substring, length);
+                        if expr2.is_empty() {
+                            *self = *expr1.clone();
+                        }
                     }
                 }
             },
             Expression::Literal(cluster, _) => match substring {
                 Substring::Prefix => {
-                    cluster.graphemes_mut().drain(..length);
+                    let graphemes = cluster.graphemes_mut();
+                    graphemes.drain(..length.min(graphemes.len()));
                 }
                 Substring::Suffix => {
                     let graphemes = cluster.graphemes_mut();
-                    graphemes.drain(graphemes.len() - length..);
+                    graphemes.drain(graphemes.len().saturating_sub(length)..);
                 }
             },
             _ => (),
@@ -198,6 +1315,12 @@ impl Expression {
         match self {
             Expression::Concatenation(expr1, expr2, _) => match substring {
                 Some(value) => match value {
+                    Substring::Prefix if expr1.is_wildcard_concatenation() => {
+                        expr1.value(substring)
+                    }
+                    Substring::Suffix if expr2.is_wildcard_concatenation() => {
+                        expr2.value(substring)
+                    }
                     Substring::Prefix => expr1.value(None),
                     Substring::Suffix => expr2.value(None),
                 },
@@ -210,23 +1333,29 @@ impl Expression {
 
     fn repeat_zero_or_more_times(
         expr: &Option<Expression>,
-        config: &RegExpConfig,
+        config: &Rc<RegExpConfig>,
     ) -> Option<Expression> {
-        if let Some(value) = expr {
-            Some(Expression::new_repetition(
-                value.clone(),
-                Quantifier::KleeneStar,
-                config,
-            ))
-        } else {
-            None
-        }
+        expr.as_ref().map(|value| match value {
+            // (?:x?)* matches the same language as x*
+            Expression::Repetition(inner, Quantifier::QuestionMark, _)
+                if config.is_kleene_star_preferred =>
+            {
+                Expression::new_repetition(*inner.clone(), Quantifier::KleeneStar, config)
+            }
+            // (?:x*)* matches the same language as x*
+            Expression::Repetition(_, Quantifier::KleeneStar, _)
+                if config.is_kleene_star_preferred =>
+            {
+                value.clone()
+            }
+            _ => Expression::new_repetition(value.clone(), Quantifier::KleeneStar, config),
+        })
     }
 
     fn concatenate(
         a: &Option<Expression>,
         b: &Option<Expression>,
-        config: &RegExpConfig,
+        config: &Rc<RegExpConfig>,
     ) -> Option<Expression> {
         if a.is_none() || b.is_none() {
             return None;
@@ -242,78 +1371,229 @@ impl Expression {
             return a.clone();
         }
 
-        if let (Expression::Literal(graphemes_a, config), Expression::Literal(graphemes_b, _)) =
-            (&expr1, &expr2)
-        {
-            return Some(Expression::new_literal(
-                GraphemeCluster::merge(graphemes_a, graphemes_b, config),
+        // Adjacent literals are merged even if they are nested within concatenation chains
+        // of any associativity, so that no two literals ever end up next to each other.
+        if let (Some((rest1, cluster1)), Some((cluster2, rest2))) = (
+            Self::split_last_literal(expr1, config),
+            Self::split_first_literal(expr2, config),
+        ) {
+            let mut result = Some(Expression::new_literal(
+                GraphemeCluster::merge(&cluster1, &cluster2, config),
                 config,
             ));
+            if let Some(rest) = rest1 {
+                result = Self::concatenate(&Some(rest), &result, config);
+            }
+            if let Some(rest) = rest2 {
+                result = Self::concatenate(&result, &Some(rest), config);
+            }
+            return result;
         }
 
-        if let (Expression::Literal(graphemes_a, _), Expression::Concatenation(first, second, _)) =
-            (&expr1, &expr2)
-        {
-            if let Expression::Literal(graphemes_first, config) = &**first {
-                let literal = Expression::new_literal(
-                    GraphemeCluster::merge(graphemes_a, graphemes_first, config),
-                    config,
-                );
-                return Some(Expression::new_concatenation(
-                    literal,
-                    *second.clone(),
-                    config,
-                ));
+        if let Some(repetition) = Self::fold_into_lower_bounded_repetition(expr1, expr2, config) {
+            return Some(repetition);
+        }
+
+        Some(Expression::new_concatenation(
+            expr1.clone(),
+            expr2.clone(),
+            config,
+        ))
+    }
+
+    /// Splits a concatenation chain into the literal at its very start and the remainder.
+    fn split_first_literal(
+        expr: &Expression,
+        config: &Rc<RegExpConfig>,
+    ) -> Option<(GraphemeCluster, Option<Expression>)> {
+        match expr {
+            Expression::Literal(cluster, _) => Some((cluster.clone(), None)),
+            Expression::Concatenation(first, second, _) => {
+                let (cluster, rest) = Self::split_first_literal(first, config)?;
+                let remainder = match rest {
+                    Some(rest) => Self::new_concatenation(rest, *second.clone(), config),
+                    None => *second.clone(),
+                };
+                Some((cluster, Some(remainder)))
             }
+            _ => None,
         }
+    }
 
-        if let (Expression::Literal(graphemes_b, _), Expression::Concatenation(first, second, _)) =
-            (&expr2, &expr1)
-        {
-            if let Expression::Literal(graphemes_second, config) = &**second {
-                let literal = Expression::new_literal(
-                    GraphemeCluster::merge(graphemes_second, graphemes_b, config),
-                    config,
-                );
-                return Some(Expression::new_concatenation(
-                    *first.clone(),
-                    literal,
+    /// Splits a concatenation chain into the remainder and the literal at its very end.
+    fn split_last_literal(
+        expr: &Expression,
+        config: &Rc<RegExpConfig>,
+    ) -> Option<(Option<Expression>, GraphemeCluster)> {
+        match expr {
+            Expression::Literal(cluster, _) => Some((None, cluster.clone())),
+            Expression::Concatenation(first, second, _) => {
+                let (rest, cluster) = Self::split_last_literal(second, config)?;
+                let remainder = match rest {
+                    Some(rest) => Self::new_concatenation(*first.clone(), rest, config),
+                    None => *first.clone(),
+                };
+                Some((Some(remainder), cluster))
+            }
+            _ => None,
+        }
+    }
+
+    /// Splits a concatenation chain into the remainder and the element at its very end.
+    fn split_last_element(
+        expr: &Expression,
+        config: &Rc<RegExpConfig>,
+    ) -> Option<(Expression, Expression)> {
+        match expr {
+            Expression::Concatenation(first, second, _) => {
+                match Self::split_last_element(second, config) {
+                    Some((rest, last)) => {
+                        Some((Self::new_concatenation(*first.clone(), rest, config), last))
+                    }
+                    None => Some((*first.clone(), *second.clone())),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Folds a literal ending with n copies of `x` followed by `x*`
+    /// (or `x*` followed by a literal starting with n copies of `x`) into `x{n,}`.
+    fn fold_into_lower_bounded_repetition(
+        expr1: &Expression,
+        expr2: &Expression,
+        config: &Rc<RegExpConfig>,
+    ) -> Option<Expression> {
+        match (expr1, expr2) {
+            (Expression::Repetition(expr, Quantifier::KleeneStar, _), other)
+            | (other, Expression::Repetition(expr, Quantifier::KleeneStar, _))
+                if **expr == *other =>
+            {
+                return Some(Expression::new_repetition(
+                    other.clone(),
+                    Quantifier::Plus,
                     config,
                 ));
             }
+            _ => (),
         }
 
-        Some(Expression::new_concatenation(
-            expr1.clone(),
-            expr2.clone(),
+        let (literal, unit, substring) = match (expr1, expr2) {
+            (Expression::Literal(literal, _), Expression::Repetition(expr, quantifier, _))
+                if quantifier == &Quantifier::KleeneStar =>
+            {
+                (literal, &**expr, Substring::Suffix)
+            }
+            (Expression::Repetition(expr, quantifier, _), Expression::Literal(literal, _))
+                if quantifier == &Quantifier::KleeneStar =>
+            {
+                (literal, &**expr, Substring::Prefix)
+            }
+            _ => return None,
+        };
+
+        let unit_graphemes = match unit {
+            Expression::Literal(cluster, _) if !cluster.is_empty() => cluster.graphemes(),
+            _ => return None,
+        };
+
+        let mut remaining_graphemes = literal.graphemes().clone();
+        let mut count = 0;
+
+        while remaining_graphemes.len() >= unit_graphemes.len() {
+            let is_unit_matched = match substring {
+                Substring::Prefix => remaining_graphemes.starts_with(unit_graphemes),
+                Substring::Suffix => remaining_graphemes.ends_with(unit_graphemes),
+            };
+            if !is_unit_matched {
+                break;
+            }
+            match substring {
+                Substring::Prefix => remaining_graphemes.drain(..unit_graphemes.len()),
+                Substring::Suffix => {
+                    remaining_graphemes.drain(remaining_graphemes.len() - unit_graphemes.len()..)
+                }
+            };
+            count += 1;
+        }
+
+        if count == 0 {
+            return None;
+        }
+
+        let quantifier = if count == 1 || config.is_repetition_count_exceeded(count) {
+            Quantifier::Plus
+        } else {
+            Quantifier::AtLeast(count)
+        };
+        let repetition = Expression::new_repetition(unit.clone(), quantifier, config);
+
+        if remaining_graphemes.is_empty() {
+            return Some(repetition);
+        }
+
+        let remaining_literal = Expression::new_literal(
+            GraphemeCluster::from_graphemes(remaining_graphemes, config),
             config,
-        ))
+        );
+
+        Some(match substring {
+            Substring::Prefix => {
+                Expression::new_concatenation(repetition, remaining_literal, config)
+            }
+            Substring::Suffix => {
+                Expression::new_concatenation(remaining_literal, repetition, config)
+            }
+        })
     }
 
     fn union(
         a: &Option<Expression>,
         b: &Option<Expression>,
-        config: &RegExpConfig,
+        config: &Rc<RegExpConfig>,
     ) -> Option<Expression> {
         if let (Some(mut expr1), Some(mut expr2)) = (a.clone(), b.clone()) {
             if expr1 != expr2 {
-                let common_prefix =
-                    Self::remove_common_substring(&mut expr1, &mut expr2, Substring::Prefix);
-                let common_suffix =
-                    Self::remove_common_substring(&mut expr1, &mut expr2, Substring::Suffix);
+                // Common literal suffixes are factored below grapheme by grapheme, but an identical
+                // non-literal element at the end of both concatenations needs to be factored as a
+                // whole, such as the optional quote in "abc"? and abc"? which yields "?abc"?.
+                if config.is_common_suffix_factored {
+                    if let (Some((rest1, last1)), Some((rest2, last2))) = (
+                        Self::split_last_element(&expr1, config),
+                        Self::split_last_element(&expr2, config),
+                    ) {
+                        if last1 == last2 && !matches!(last1, Expression::Literal(_, _)) {
+                            let union = Self::union(&Some(rest1), &Some(rest2), config);
+                            return Self::concatenate(&union, &Some(last1), config);
+                        }
+                    }
+                }
 
-                let mut result = if expr1.is_empty() {
-                    Some(Expression::new_repetition(
-                        expr2.clone(),
-                        Quantifier::QuestionMark,
+                let common_prefix = if config.is_common_prefix_factored {
+                    Self::remove_common_substring(&mut expr1, &mut expr2, Substring::Prefix, config)
+                } else {
+                    None
+                };
+                let common_suffix = if config.is_common_suffix_factored {
+                    Self::remove_common_substring(&mut expr1, &mut expr2, Substring::Suffix, config)
+                } else {
+                    None
+                };
+
+                let is_prefix_contained =
+                    common_suffix.is_none() && (expr1.is_empty() || expr2.is_empty());
+                if is_prefix_contained && config.is_prefix_branch_kept_distinct {
+                    return Some(Expression::new_alternation(
+                        a.clone().unwrap(),
+                        b.clone().unwrap(),
                         config,
-                    ))
+                    ));
+                }
+
+                let mut result = if expr1.is_empty() {
+                    Some(Expression::new_optional(expr2.clone(), config))
                 } else if expr2.is_empty() {
-                    Some(Expression::new_repetition(
-                        expr1.clone(),
-                        Quantifier::QuestionMark,
-                        config,
-                    ))
+                    Some(Expression::new_optional(expr1.clone(), config))
                 } else {
                     None
                 };
@@ -349,11 +1629,17 @@ impl Expression {
                 if result.is_none() && expr1.is_single_codepoint() && expr2.is_single_codepoint() {
                     let first_char_set = Self::extract_character_set(expr1.clone());
                     let second_char_set = Self::extract_character_set(expr2.clone());
-                    result = Some(Expression::new_character_class(
-                        first_char_set,
-                        second_char_set,
-                        config,
-                    ));
+                    if !first_char_set.is_empty()
+                        && !second_char_set.is_empty()
+                        && first_char_set.union(&second_char_set).count()
+                            >= config.minimum_class_size
+                    {
+                        result = Some(Expression::new_character_class(
+                            first_char_set,
+                            second_char_set,
+                            config,
+                        ));
+                    }
                 }
 
                 if result.is_none() {
@@ -399,6 +1685,7 @@ impl Expression {
         }
     }
 
+    /// Collects the options of nested alternations depth-first from left to right.
     fn flatten_alternations(
         flattened_options: &mut Vec<Expression>,
         current_options: Vec<Expression>,
@@ -412,83 +1699,333 @@ impl Expression {
         }
     }
 
-    fn extract_character_set(expr: Expression) -> BTreeSet<char> {
-        match expr {
-            Expression::Literal(cluster, _) => {
-                let single_char = cluster
-                    .graphemes()
-                    .first()
-                    .unwrap()
-                    .value()
-                    .chars()
-                    .next()
-                    .unwrap();
-                btreeset![single_char]
-            }
-            Expression::CharacterClass(char_set, _) => char_set,
-            _ => BTreeSet::new(),
+    /// Merges all options matching a single character into one character class.
+    /// Unions only build a class from two such options directly, so options
+    /// which are joined with an existing alternation are collected here.
+    fn merge_single_codepoint_options(options: &mut Vec<Expression>, config: &Rc<RegExpConfig>) {
+        let is_mergeable = |option: &Expression| {
+            option.is_single_codepoint() && !matches!(option, Expression::AnyChar(_))
+        };
+        if options
+            .iter()
+            .filter(|&option| is_mergeable(option))
+            .count()
+            < 2
+        {
+            return;
+        }
+        let position = options.iter().position(is_mergeable).unwrap();
+        let char_set = options
+            .iter()
+            .filter(|&option| is_mergeable(option))
+            .flat_map(|option| Self::extract_character_set(option.clone()))
+            .collect::<BTreeSet<char>>();
+        if char_set.len() < config.minimum_class_size {
+            return;
+        }
+        options[position] = Self::new_character_class(char_set, BTreeSet::new(), config);
+        let mut index = 0;
+        options.retain(|option| {
+            index += 1;
+            index - 1 == position || !is_mergeable(option)
+        });
+    }
+
+    fn extract_character_set(expr: Expression) -> BTreeSet<char> {
+        match expr {
+            Expression::Literal(cluster, _) => cluster
+                .graphemes()
+                .iter()
+                .find_map(|grapheme| grapheme.value().chars().next())
+                .map(|single_char| btreeset![single_char])
+                .unwrap_or_default(),
+            Expression::CharacterClass(char_set, _) => char_set,
+            _ => BTreeSet::new(),
+        }
+    }
+
+    fn remove_common_substring(
+        a: &mut Expression,
+        b: &mut Expression,
+        substring: Substring,
+        config: &Rc<RegExpConfig>,
+    ) -> Option<Vec<Grapheme>> {
+        // The common substring's length is a number of graphemes, not of code points,
+        // which is what remove_substring() expects as well.
+        let common_substring = Self::find_common_substring(a, b, &substring, config);
+        if let Some(value) = &common_substring {
+            a.remove_substring(&substring, value.len());
+            b.remove_substring(&substring, value.len());
+        }
+        common_substring
+    }
+
+    fn find_common_substring(
+        a: &Expression,
+        b: &Expression,
+        substring: &Substring,
+        config: &Rc<RegExpConfig>,
+    ) -> Option<Vec<Grapheme>> {
+        let mut graphemes_a = a.value(Some(substring)).unwrap_or_default();
+        let mut graphemes_b = b.value(Some(substring)).unwrap_or_default();
+        let mut common_graphemes = vec![];
+
+        if let Substring::Suffix = substring {
+            graphemes_a.reverse();
+            graphemes_b.reverse();
+        }
+
+        for pair in graphemes_a.iter().zip_longest(graphemes_b.iter()) {
+            match pair {
+                Both(grapheme_a, grapheme_b) => {
+                    if grapheme_a == grapheme_b {
+                        common_graphemes.push(grapheme_a.clone());
+                    } else {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        if let Substring::Suffix = substring {
+            common_graphemes.reverse();
+        }
+
+        // Shorter affixes are kept in the alternatives because factoring them out
+        // adds a group without making the expression any shorter.
+        if common_graphemes.is_empty()
+            || common_graphemes.len() < config.minimum_common_substring_length
+        {
+            None
+        } else {
+            Some(common_graphemes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regexp::Feature;
+    use ndarray::{Array1, Array2};
+
+    /// The former conversion based on dense matrices of state_count² entries,
+    /// which the sparse conversion has to be equivalent to.
+    fn from_dense(dfa: Dfa, config: &Rc<RegExpConfig>) -> Expression {
+        let states = dfa.states_in_depth_first_order();
+        let state_count = dfa.state_count();
+
+        let mut a = Array2::<Option<Expression>>::default((state_count, state_count));
+        let mut b = Array1::<Option<Expression>>::default(state_count);
+
+        for (i, state) in states.iter().enumerate() {
+            if dfa.is_final_state(*state) {
+                b[i] = Some(Expression::new_literal(
+                    GraphemeCluster::from("", config),
+                    config,
+                ));
+            }
+
+            for edge in dfa.outgoing_edges(*state) {
+                let literal = Expression::new_cluster(
+                    GraphemeCluster::new(edge.weight().clone(), config),
+                    config,
+                );
+                let j = states.iter().position(|&it| it == edge.target()).unwrap();
+
+                a[(i, j)] = if a[(i, j)].is_some() {
+                    Expression::union(&a[(i, j)], &Some(literal), config)
+                } else {
+                    Some(literal)
+                }
+            }
+        }
+
+        for n in (0..state_count).rev() {
+            if a[(n, n)].is_some() {
+                b[n] = Expression::concatenate(
+                    &Expression::repeat_zero_or_more_times(&a[(n, n)], config),
+                    &b[n],
+                    config,
+                );
+                for j in 0..n {
+                    a[(n, j)] = Expression::concatenate(
+                        &Expression::repeat_zero_or_more_times(&a[(n, n)], config),
+                        &a[(n, j)],
+                        config,
+                    );
+                }
+            }
+
+            for i in 0..n {
+                if a[(i, n)].is_some() {
+                    b[i] = Expression::union(
+                        &b[i],
+                        &Expression::concatenate(&a[(i, n)], &b[n], config),
+                        config,
+                    );
+                    for j in 0..n {
+                        a[(i, j)] = Expression::union(
+                            &a[(i, j)],
+                            &Expression::concatenate(&a[(i, n)], &a[(n, j)], config),
+                            config,
+                        );
+                    }
+                }
+            }
+        }
+
+        if !b.is_empty() && b[0].is_some() {
+            b[0].as_ref().unwrap().clone()
+        } else {
+            Expression::new_literal(GraphemeCluster::from("", config), config)
+        }
+    }
+
+    #[test]
+    fn ensure_correct_nesting_depth_of_deeply_nested_expression() {
+        let config = Rc::new(RegExpConfig::new());
+        let mut expr = Expression::new_literal(GraphemeCluster::from("a", &config), &config);
+        for _ in 0..5000 {
+            let literal = Expression::new_literal(GraphemeCluster::from("b", &config), &config);
+            let repetition = Expression::new_repetition(expr, Quantifier::QuestionMark, &config);
+            expr = Expression::new_concatenation(literal, repetition, &config);
         }
+        assert_eq!(expr.nesting_depth(), 5000);
     }
 
-    fn remove_common_substring(
-        a: &mut Expression,
-        b: &mut Expression,
-        substring: Substring,
-    ) -> Option<Vec<Grapheme>> {
-        let common_substring = Self::find_common_substring(a, b, &substring);
-        if let Some(value) = &common_substring {
-            a.remove_substring(&substring, value.len());
-            b.remove_substring(&substring, value.len());
+    #[test]
+    fn ensure_correct_size_of_deeply_nested_expression() {
+        let config = Rc::new(RegExpConfig::new());
+        let mut expr = Expression::new_literal(GraphemeCluster::from("a", &config), &config);
+        for _ in 0..5000 {
+            let literal = Expression::new_literal(GraphemeCluster::from("b", &config), &config);
+            let repetition = Expression::new_repetition(expr, Quantifier::QuestionMark, &config);
+            expr = Expression::new_concatenation(literal, repetition, &config);
         }
-        common_substring
+        assert_eq!(expr.size(), 15001);
     }
 
-    fn find_common_substring(
-        a: &Expression,
-        b: &Expression,
-        substring: &Substring,
-    ) -> Option<Vec<Grapheme>> {
-        let mut graphemes_a = a.value(Some(substring)).unwrap_or_else(Vec::new);
-        let mut graphemes_b = b.value(Some(substring)).unwrap_or_else(Vec::new);
-        let mut common_graphemes = vec![];
+    #[test]
+    fn ensure_size_of_literal_is_its_grapheme_count() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("abcde", &config), &config);
+        let empty_literal = Expression::new_literal(GraphemeCluster::from("", &config), &config);
+        let repetition =
+            Expression::new_repetition(literal.clone(), Quantifier::KleeneStar, &config);
+        assert_eq!(literal.size(), 5);
+        assert_eq!(empty_literal.size(), 1);
+        assert_eq!(repetition.size(), 6);
+    }
 
-        if let Substring::Suffix = substring {
-            graphemes_a.reverse();
-            graphemes_b.reverse();
-        }
+    #[test]
+    fn ensure_correct_diff_of_character_classes() {
+        let config = Rc::new(RegExpConfig::new());
+        let old = Expression::CharacterClass(btreeset!['a', 'b'], Rc::clone(&config));
+        let new = Expression::CharacterClass(btreeset!['a', 'b', 'c'], Rc::clone(&config));
+        assert_eq!(old.diff(&new), vec!["added 'c' to character class [ab]"]);
+        assert_eq!(
+            new.diff(&old),
+            vec!["removed 'c' from character class [a-c]"]
+        );
+        assert!(old.diff(&old).is_empty());
+    }
 
-        for pair in graphemes_a.iter().zip_longest(graphemes_b.iter()) {
-            match pair {
-                Both(grapheme_a, grapheme_b) => {
-                    if grapheme_a == grapheme_b {
-                        common_graphemes.push(grapheme_a.clone());
-                    } else {
-                        break;
-                    }
-                }
-                _ => break,
-            }
-        }
+    #[test]
+    fn ensure_correct_diff_of_nested_expressions() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("x", &config), &config);
+        let old = Expression::new_concatenation(
+            literal.clone(),
+            Expression::new_repetition(
+                Expression::CharacterClass(btreeset!['a', 'b'], Rc::clone(&config)),
+                Quantifier::QuestionMark,
+                &config,
+            ),
+            &config,
+        );
+        let new = Expression::new_concatenation(
+            literal,
+            Expression::new_repetition(
+                Expression::CharacterClass(btreeset!['a', 'b'], Rc::clone(&config)),
+                Quantifier::KleeneStar,
+                &config,
+            ),
+            &config,
+        );
+        assert_eq!(
+            old.diff(&new),
+            vec!["changed quantifier of [ab]? from ? to *"]
+        );
+    }
 
-        if let Substring::Suffix = substring {
-            common_graphemes.reverse();
-        }
+    #[test]
+    fn ensure_equivalence_of_sparse_and_dense_conversion() {
+        let mut config_with_repetitions = RegExpConfig::new();
+        config_with_repetitions.conversion_features = vec![Feature::Digit, Feature::Repetition];
+        let config_with_repetitions = Rc::new(config_with_repetitions);
+        let mut config_with_substrings = RegExpConfig::new();
+        config_with_substrings.is_substring_matched = true;
+        let config_with_substrings = Rc::new(config_with_substrings);
+        let configs = [
+            Rc::new(RegExpConfig::new()),
+            config_with_repetitions,
+            config_with_substrings,
+        ];
+        // A linear congruential generator, so that the random test cases are reproducible.
+        let mut state = 7u64;
+        let mut next_u64 = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state
+        };
 
-        if common_graphemes.is_empty() {
-            None
-        } else {
-            Some(common_graphemes)
+        for _ in 0..300 {
+            let test_case_count = 1 + (next_u64() % 8) as usize;
+            let test_cases = (0..test_case_count)
+                .map(|_| {
+                    let seed = next_u64();
+                    let length = (seed >> 60) as usize % 7;
+                    (0..length)
+                        .map(|k| ['a', 'b', 'c', '1'][(seed >> (40 + 2 * k)) as usize % 4])
+                        .collect::<String>()
+                })
+                .collect_vec();
+
+            for config in configs.iter() {
+                let clusters = test_cases
+                    .iter()
+                    .map(|it| {
+                        let mut cluster = GraphemeCluster::from(it, config);
+                        if config.is_char_class_feature_enabled() {
+                            cluster.convert_to_char_classes();
+                        }
+                        if config.is_repetition_converted() {
+                            cluster.convert_repetitions();
+                        }
+                        cluster
+                    })
+                    .collect_vec();
+                // Minimization does not number states deterministically, so both
+                // conversions have to start from the very same automaton.
+                let dfa = Dfa::from(clusters, config);
+                assert_eq!(
+                    Expression::from(dfa.clone(), config, None, None)
+                        .unwrap()
+                        .unwrap(),
+                    from_dense(dfa, config),
+                    "test cases: {:?}",
+                    test_cases
+                );
+            }
         }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
     fn ensure_correct_string_representation_of_alternation_1() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let literal1 = Expression::new_literal(GraphemeCluster::from("abc", &config), &config);
         let literal2 = Expression::new_literal(GraphemeCluster::from("def", &config), &config);
         let alternation = Expression::new_alternation(literal1, literal2, &config);
@@ -497,7 +2034,7 @@ mod tests {
 
     #[test]
     fn ensure_correct_string_representation_of_alternation_2() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let literal1 = Expression::new_literal(GraphemeCluster::from("a", &config), &config);
         let literal2 = Expression::new_literal(GraphemeCluster::from("ab", &config), &config);
         let literal3 = Expression::new_literal(GraphemeCluster::from("abc", &config), &config);
@@ -506,24 +2043,116 @@ mod tests {
         assert_eq!(alternation2.to_string(), "abc|ab|a");
     }
 
+    #[test]
+    fn ensure_correct_string_representation_of_alternation_3() {
+        let config = Rc::new(RegExpConfig::new());
+        let literals = ["de", "fg", "bc", "hij", "xy"]
+            .iter()
+            .map(|&it| Expression::new_literal(GraphemeCluster::from(it, &config), &config))
+            .collect_vec();
+        let alternation1 =
+            Expression::new_alternation(literals[0].clone(), literals[1].clone(), &config);
+        let alternation2 =
+            Expression::new_alternation(literals[2].clone(), literals[3].clone(), &config);
+        let alternation3 = Expression::new_alternation(alternation1, alternation2, &config);
+        let alternation4 = Expression::new_alternation(literals[4].clone(), alternation3, &config);
+        assert_eq!(alternation4.to_string(), "hij|xy|de|fg|bc");
+    }
+
     #[test]
     fn ensure_correct_string_representation_of_character_class_1() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let char_class = Expression::new_character_class(btreeset!['a'], btreeset!['b'], &config);
         assert_eq!(char_class.to_string(), "[ab]");
     }
 
     #[test]
     fn ensure_correct_string_representation_of_character_class_2() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let char_class =
             Expression::new_character_class(btreeset!['a', 'b'], btreeset!['c'], &config);
         assert_eq!(char_class.to_string(), "[a-c]");
     }
 
+    #[test]
+    fn ensure_correct_string_representation_of_character_class_3() {
+        let config = Rc::new(RegExpConfig::new());
+        let char_class = Expression::new_character_class(
+            btreeset!['\u{d7fd}', '\u{d7fe}', '\u{d7ff}'],
+            btreeset!['\u{e000}', '\u{e001}', '\u{e002}'],
+            &config,
+        );
+        assert_eq!(
+            char_class.to_string(),
+            "[\u{d7fd}-\u{d7ff}\u{e000}-\u{e002}]"
+        );
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_character_class_4() {
+        let mut config = RegExpConfig::new();
+        config.is_dash_class_extended = true;
+        let config = Rc::new(config);
+        let char_class =
+            Expression::new_character_class(btreeset!['-'], btreeset!['\u{2013}'], &config);
+        assert_eq!(
+            char_class.to_string(),
+            "[\\-\u{2010}-\u{2015}\u{2212}\u{fe58}\u{fe63}\u{ff0d}]"
+        );
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_character_class_5() {
+        let mut config = RegExpConfig::new();
+        config.is_dash_class_extended = true;
+        let config = Rc::new(config);
+        let char_class = Expression::new_character_class(btreeset!['-'], btreeset!['a'], &config);
+        assert_eq!(char_class.to_string(), "[\\-a]");
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_character_class_6() {
+        let config = Rc::new(RegExpConfig::new());
+        let char_class = Expression::new_character_class(
+            btreeset!['x', 'a', 'y', 'c', 'z'],
+            btreeset!['b', 'm', 'n'],
+            &config,
+        );
+        assert_eq!(char_class.to_string(), "[a-cmnx-z]");
+    }
+
+    #[test]
+    fn ensure_no_range_across_gap_between_uppercase_and_lowercase_letters() {
+        let config = Rc::new(RegExpConfig::new());
+        let char_class =
+            Expression::new_character_class(btreeset!['A', 'Z'], btreeset!['a', 'z'], &config);
+        assert_eq!(char_class.to_string(), "[AZaz]");
+
+        let char_class =
+            Expression::new_character_class(('A'..='Z').collect(), ('a'..='z').collect(), &config);
+        assert_eq!(char_class.to_string(), "[A-Za-z]");
+    }
+
+    #[test]
+    fn ensure_canonical_string_representation_of_character_class_regardless_of_construction() {
+        let config = Rc::new(RegExpConfig::new());
+        let char_class1 = Expression::new_character_class(
+            btreeset!['a', 'b', 'c'],
+            btreeset!['x', 'y', 'z', 'm'],
+            &config,
+        );
+        let char_class2 = Expression::new_character_class(
+            btreeset!['z', 'm'],
+            btreeset!['y', 'c', 'x', 'b', 'a'],
+            &config,
+        );
+        assert_eq!(char_class1.to_string(), "[a-cmx-z]");
+        assert_eq!(char_class1.to_string(), char_class2.to_string());
+    }
+
     #[test]
     fn ensure_correct_string_representation_of_concatenation_1() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let literal1 = Expression::new_literal(GraphemeCluster::from("abc", &config), &config);
         let literal2 = Expression::new_literal(GraphemeCluster::from("def", &config), &config);
         let concatenation = Expression::new_concatenation(literal1, literal2, &config);
@@ -532,7 +2161,7 @@ mod tests {
 
     #[test]
     fn ensure_correct_string_representation_of_concatenation_2() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let literal1 = Expression::new_literal(GraphemeCluster::from("abc", &config), &config);
         let literal2 = Expression::new_literal(GraphemeCluster::from("def", &config), &config);
         let repetition = Expression::new_repetition(literal1, Quantifier::KleeneStar, &config);
@@ -540,15 +2169,418 @@ mod tests {
         assert_eq!(concatenation.to_string(), "(?:abc)*def");
     }
 
+    #[test]
+    fn ensure_correct_string_representation_of_repetition_3() {
+        let mut config = RegExpConfig::new();
+        config.is_quantifier_braced = true;
+        let config = Rc::new(config);
+        let literal = Expression::new_literal(GraphemeCluster::from("abc", &config), &config);
+        let repetition = Expression::new_repetition(literal, Quantifier::KleeneStar, &config);
+        assert_eq!(repetition.to_string(), "(?:abc){0,}");
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_repetition_4() {
+        let mut config = RegExpConfig::new();
+        config.is_quantifier_braced = true;
+        let config = Rc::new(config);
+        let literal = Expression::new_literal(GraphemeCluster::from("a", &config), &config);
+        let repetition = Expression::new_repetition(literal, Quantifier::QuestionMark, &config);
+        assert_eq!(repetition.to_string(), "a{0,1}");
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_nested_repetition() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("ab", &config), &config);
+        let inner = Expression::new_repetition(literal, Quantifier::Range(2, 2), &config);
+        let outer = Expression::new_repetition(inner.clone(), Quantifier::Range(3, 3), &config);
+        assert_eq!(outer.to_string(), "(?:ab){2}{3}");
+        let outer = Expression::new_repetition(inner, Quantifier::AtLeast(1), &config);
+        assert_eq!(outer.to_string(), "(?:ab){2}{1,}");
+    }
+
+    #[test]
+    fn ensure_correct_union_of_empty_literal_and_kleene_star_1() {
+        let config = Rc::new(RegExpConfig::new());
+        let (empty, repetition) = empty_literal_and_kleene_star(&config);
+        let union = Expression::union(&Some(empty), &Some(repetition), &config).unwrap();
+        assert_eq!(union.to_string(), "(?:ab)*");
+        assert_that_languages_are_equal("^(?:(?:ab)*)?$", &format!("^{}$", union));
+    }
+
+    #[test]
+    fn ensure_correct_union_of_empty_literal_and_kleene_star_2() {
+        let mut config = RegExpConfig::new();
+        config.is_kleene_star_preferred = true;
+        let config = Rc::new(config);
+        let (empty, repetition) = empty_literal_and_kleene_star(&config);
+        let union = Expression::union(&Some(empty), &Some(repetition), &config).unwrap();
+        assert_eq!(union.to_string(), "(?:ab)*");
+        assert_that_languages_are_equal("^(?:(?:ab)*)?$", &format!("^{}$", union));
+    }
+
+    #[test]
+    fn ensure_correct_repetition_of_question_mark_1() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("ab", &config), &config);
+        let optional = Expression::new_repetition(literal, Quantifier::QuestionMark, &config);
+        let repetition = Expression::repeat_zero_or_more_times(&Some(optional), &config).unwrap();
+        assert_eq!(repetition.to_string(), "(?:(?:ab)?)*");
+    }
+
+    #[test]
+    fn ensure_correct_repetition_of_question_mark_2() {
+        let mut config = RegExpConfig::new();
+        config.is_kleene_star_preferred = true;
+        let config = Rc::new(config);
+        let literal = Expression::new_literal(GraphemeCluster::from("ab", &config), &config);
+        let optional = Expression::new_repetition(literal, Quantifier::QuestionMark, &config);
+        let repetition = Expression::repeat_zero_or_more_times(&Some(optional), &config).unwrap();
+        assert_eq!(repetition.to_string(), "(?:ab)*");
+        assert_that_languages_are_equal("^(?:(?:ab)?)*$", &format!("^{}$", repetition));
+    }
+
+    #[test]
+    fn ensure_correct_union_of_empty_literal_and_wildcard_1() {
+        let config = Rc::new(RegExpConfig::new());
+        let (empty, wildcard) = empty_literal_and_wildcard(&config);
+        let union = Expression::union(&Some(empty), &Some(wildcard), &config).unwrap();
+        assert_eq!(union.to_string(), ".*");
+        assert_that_languages_are_equal("^(?:.*)?$", &format!("^{}$", union));
+    }
+
+    #[test]
+    fn ensure_correct_union_of_empty_literal_and_wildcard_2() {
+        let mut config = RegExpConfig::new();
+        config.is_kleene_star_preferred = true;
+        let config = Rc::new(config);
+        let (empty, wildcard) = empty_literal_and_wildcard(&config);
+        let union = Expression::union(&Some(empty), &Some(wildcard), &config).unwrap();
+        assert_eq!(union.to_string(), ".*");
+        assert_that_languages_are_equal("^(?:.*)?$", &format!("^{}$", union));
+    }
+
+    #[test]
+    fn ensure_correct_union_of_empty_literal_and_optional_chain() {
+        let mut config = RegExpConfig::new();
+        config.conversion_features = vec![Feature::Repetition];
+        let config = Rc::new(config);
+        let literal = Expression::new_literal(GraphemeCluster::from("ab", &config), &config);
+        let empty = Expression::new_literal(GraphemeCluster::from("", &config), &config);
+        let optional =
+            Expression::new_repetition(literal.clone(), Quantifier::QuestionMark, &config);
+        let chain = Expression::new_concatenation(literal, optional, &config);
+        let union = Expression::union(&Some(empty.clone()), &Some(chain.clone()), &config).unwrap();
+        assert_eq!(union.to_string(), "(?:ab){0,2}");
+        assert_that_languages_are_equal("^(?:ab(?:ab)?)?$", &format!("^{}$", union));
+
+        let mut config = RegExpConfig::clone(&config);
+        config.conversion_features = vec![];
+        let config = Rc::new(config);
+        let union = Expression::union(&Some(empty), &Some(chain), &config).unwrap();
+        assert_eq!(union.to_string(), "(?:ab(?:ab)?)?");
+    }
+
+    #[test]
+    fn ensure_correct_repetition_of_wildcard() {
+        let mut config = RegExpConfig::new();
+        config.is_kleene_star_preferred = true;
+        let config = Rc::new(config);
+        let (_, wildcard) = empty_literal_and_wildcard(&config);
+        let repetition = Expression::repeat_zero_or_more_times(&Some(wildcard), &config).unwrap();
+        assert_eq!(repetition.to_string(), ".*");
+        assert_that_languages_are_equal("^(?:.*)*$", &format!("^{}$", repetition));
+    }
+
+    #[test]
+    fn ensure_correct_union_of_literal_and_trailing_character_class() {
+        let config = Rc::new(RegExpConfig::new());
+        let word = Expression::new_literal(GraphemeCluster::from("word", &config), &config);
+        let class = Expression::new_character_class(btreeset!['.'], btreeset![','], &config);
+        let concatenation = Expression::new_concatenation(word.clone(), class, &config);
+        let union = Expression::union(&Some(concatenation), &Some(word), &config).unwrap();
+        assert_eq!(union.to_string(), "word[,.]?");
+    }
+
+    #[test]
+    fn ensure_merged_literals_equal_pairwise_concatenation() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal =
+            |value: &str| Expression::new_literal(GraphemeCluster::from(value, &config), &config);
+        let exprs = vec![
+            literal("ab"),
+            literal("c"),
+            Expression::new_character_class(btreeset!['x'], btreeset!['y'], &config),
+            literal("d"),
+            literal("ef"),
+            literal("g"),
+        ];
+        let concatenate = |exprs: Vec<Expression>| {
+            exprs
+                .into_iter()
+                .map(Some)
+                .reduce(|expr1, expr2| Expression::concatenate(&expr1, &expr2, &config))
+                .unwrap()
+                .unwrap()
+        };
+        let merged_exprs = Expression::merge_adjacent_literals(exprs.clone(), &config);
+        assert_eq!(merged_exprs.len(), 3);
+        assert_eq!(concatenate(merged_exprs), concatenate(exprs));
+    }
+
+    #[test]
+    fn ensure_wildcards_are_converted_to_repetitions_of_any_char() {
+        let config = Rc::new(RegExpConfig::new());
+        let wildcard = Expression::Repetition(
+            Box::from(Expression::AnyChar(Rc::clone(&config))),
+            Quantifier::KleeneStar,
+            Rc::clone(&config),
+        );
+        let literal =
+            |value: &str| Expression::new_literal(GraphemeCluster::from(value, &config), &config);
+        let expected = vec![literal("a"), wildcard, literal("b")]
+            .into_iter()
+            .map(Some)
+            .reduce(|expr1, expr2| Expression::concatenate(&expr1, &expr2, &config))
+            .unwrap()
+            .unwrap();
+
+        let mut cluster = GraphemeCluster::from("a%%b", &config);
+        cluster.convert_wildcards('%');
+        let expr = Expression::new_cluster(cluster, &config);
+        assert_eq!(expr, expected);
+        assert_eq!(expr.to_string(), "a.*b");
+    }
+
+    fn empty_literal_and_wildcard(config: &Rc<RegExpConfig>) -> (Expression, Expression) {
+        let empty = Expression::new_literal(GraphemeCluster::from("", config), config);
+        (empty, Expression::new_wildcard(config))
+    }
+
+    fn empty_literal_and_kleene_star(config: &Rc<RegExpConfig>) -> (Expression, Expression) {
+        let empty = Expression::new_literal(GraphemeCluster::from("", config), config);
+        let literal = Expression::new_literal(GraphemeCluster::from("ab", config), config);
+        let repetition = Expression::new_repetition(literal, Quantifier::KleeneStar, config);
+        (empty, repetition)
+    }
+
+    fn assert_that_languages_are_equal(first_regexp: &str, second_regexp: &str) {
+        let first = regex::Regex::new(first_regexp).unwrap();
+        let second = regex::Regex::new(second_regexp).unwrap();
+        for test_case in ["", "a", "ab", "aba", "abab", "ababab", "ba", "abb"].iter() {
+            assert_eq!(first.is_match(test_case), second.is_match(test_case));
+        }
+    }
+
+    #[test]
+    fn ensure_correct_folding_of_literal_and_kleene_star_into_lower_bound_1() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("aaa", &config), &config);
+        let repetition = Expression::new_repetition(
+            Expression::new_literal(GraphemeCluster::from("a", &config), &config),
+            Quantifier::KleeneStar,
+            &config,
+        );
+        let concatenation =
+            Expression::concatenate(&Some(literal), &Some(repetition), &config).unwrap();
+        assert_eq!(concatenation.to_string(), "a{3,}");
+    }
+
+    #[test]
+    fn ensure_correct_folding_of_literal_and_kleene_star_into_lower_bound_2() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("ababx", &config), &config);
+        let repetition = Expression::new_repetition(
+            Expression::new_literal(GraphemeCluster::from("ab", &config), &config),
+            Quantifier::KleeneStar,
+            &config,
+        );
+        let concatenation =
+            Expression::concatenate(&Some(repetition), &Some(literal), &config).unwrap();
+        assert_eq!(concatenation.to_string(), "(?:ab){2,}x");
+    }
+
+    #[test]
+    fn ensure_correct_folding_of_literal_and_kleene_star_into_lower_bound_3() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("xbaa", &config), &config);
+        let repetition = Expression::new_repetition(
+            Expression::new_literal(GraphemeCluster::from("a", &config), &config),
+            Quantifier::KleeneStar,
+            &config,
+        );
+        let concatenation =
+            Expression::concatenate(&Some(literal), &Some(repetition), &config).unwrap();
+        assert_eq!(concatenation.to_string(), "xba{2,}");
+    }
+
+    #[test]
+    fn ensure_correct_folding_of_literal_and_kleene_star_into_plus_above_maximum_count() {
+        let mut config = RegExpConfig::new();
+        config.maximum_repetition_count = Some(2);
+        let config = Rc::new(config);
+        let literal = Expression::new_literal(GraphemeCluster::from("xaaa", &config), &config);
+        let repetition = Expression::new_repetition(
+            Expression::new_literal(GraphemeCluster::from("a", &config), &config),
+            Quantifier::KleeneStar,
+            &config,
+        );
+        let concatenation =
+            Expression::concatenate(&Some(literal), &Some(repetition), &config).unwrap();
+        assert_eq!(concatenation.to_string(), "xa+");
+    }
+
+    #[test]
+    fn ensure_correct_folding_of_kleene_star_and_literal_into_plus_1() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("ab", &config), &config);
+        let repetition =
+            Expression::new_repetition(literal.clone(), Quantifier::KleeneStar, &config);
+        let concatenation =
+            Expression::concatenate(&Some(repetition), &Some(literal), &config).unwrap();
+        assert_eq!(concatenation.to_string(), "(?:ab)+");
+    }
+
+    #[test]
+    fn ensure_correct_folding_of_kleene_star_and_literal_into_plus_2() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("ab", &config), &config);
+        let repetition =
+            Expression::new_repetition(literal.clone(), Quantifier::KleeneStar, &config);
+        let concatenation =
+            Expression::concatenate(&Some(literal), &Some(repetition), &config).unwrap();
+        assert_eq!(concatenation.to_string(), "(?:ab)+");
+    }
+
+    #[test]
+    fn ensure_correct_folding_of_kleene_star_and_character_class_into_plus() {
+        let config = Rc::new(RegExpConfig::new());
+        let char_class = Expression::new_character_class(btreeset!['a'], btreeset!['b'], &config);
+        let repetition =
+            Expression::new_repetition(char_class.clone(), Quantifier::KleeneStar, &config);
+        let concatenation =
+            Expression::concatenate(&Some(char_class), &Some(repetition), &config).unwrap();
+        assert_eq!(concatenation.to_string(), "[ab]+");
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_braced_plus() {
+        let mut config = RegExpConfig::new();
+        config.is_quantifier_braced = true;
+        let config = Rc::new(config);
+        let literal = Expression::new_literal(GraphemeCluster::from("a", &config), &config);
+        let repetition = Expression::new_repetition(literal, Quantifier::Plus, &config);
+        assert_eq!(repetition.to_string(), "a{1,}");
+    }
+
+    #[test]
+    fn ensure_correct_merging_of_literals_in_unbalanced_concatenation() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = |s: &str| Expression::new_literal(GraphemeCluster::from(s, &config), &config);
+        let left_leaning = Expression::new_concatenation(
+            Expression::new_concatenation(literal("a"), literal("bc"), &config),
+            literal("de"),
+            &config,
+        );
+        let right_leaning = Expression::new_concatenation(
+            literal("fg"),
+            Expression::new_concatenation(literal("h"), literal("ij"), &config),
+            &config,
+        );
+        let concatenation =
+            Expression::concatenate(&Some(left_leaning), &Some(right_leaning), &config).unwrap();
+        assert_eq!(concatenation, literal("abcdefghij"));
+    }
+
+    #[test]
+    fn ensure_correct_merging_of_literals_around_nested_concatenation() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = |s: &str| Expression::new_literal(GraphemeCluster::from(s, &config), &config);
+        let class = Expression::new_character_class(btreeset!['x'], btreeset!['y'], &config);
+        let expr1 = Expression::new_concatenation(
+            class.clone(),
+            Expression::new_concatenation(literal("a"), literal("bc"), &config),
+            &config,
+        );
+        let expr2 = Expression::new_concatenation(
+            Expression::new_concatenation(literal("de"), class.clone(), &config),
+            literal("f"),
+            &config,
+        );
+        let concatenation = Expression::concatenate(&Some(expr1), &Some(expr2), &config).unwrap();
+        let expected = Expression::new_concatenation(
+            Expression::new_concatenation(class.clone(), literal("abcde"), &config),
+            Expression::new_concatenation(class, literal("f"), &config),
+            &config,
+        );
+        assert_eq!(concatenation, expected);
+        assert_eq!(concatenation.to_string(), "[xy]abcde[xy]f");
+    }
+
+    #[test]
+    fn ensure_no_folding_of_literal_and_kleene_star_without_common_unit() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("ab", &config), &config);
+        let repetition = Expression::new_repetition(
+            Expression::new_literal(GraphemeCluster::from("a", &config), &config),
+            Quantifier::KleeneStar,
+            &config,
+        );
+        let concatenation =
+            Expression::concatenate(&Some(literal), &Some(repetition), &config).unwrap();
+        assert_eq!(concatenation.to_string(), "aba*");
+    }
+
+    #[test]
+    fn ensure_correct_removal_of_common_prefix_with_multi_codepoint_grapheme() {
+        let config = Rc::new(RegExpConfig::new());
+        let mut literal1 = Expression::new_literal(GraphemeCluster::from("👨‍👩‍👧ab", &config), &config);
+        let mut literal2 = Expression::new_literal(GraphemeCluster::from("👨‍👩‍👧cd", &config), &config);
+        let common_prefix = Expression::remove_common_substring(
+            &mut literal1,
+            &mut literal2,
+            Substring::Prefix,
+            &config,
+        );
+        assert_eq!(common_prefix, Some(vec![Grapheme::from("👨‍👩‍👧", &config)]));
+        assert_eq!(literal1.to_string(), "ab");
+        assert_eq!(literal2.to_string(), "cd");
+    }
+
+    #[test]
+    fn ensure_correct_removal_of_common_suffix_with_multi_codepoint_grapheme() {
+        let config = Rc::new(RegExpConfig::new());
+        let mut literal1 =
+            Expression::new_literal(GraphemeCluster::from("ab👍🏽x", &config), &config);
+        let mut literal2 =
+            Expression::new_literal(GraphemeCluster::from("cd👍🏽x", &config), &config);
+        let common_suffix = Expression::remove_common_substring(
+            &mut literal1,
+            &mut literal2,
+            Substring::Suffix,
+            &config,
+        );
+        assert_eq!(
+            common_suffix,
+            Some(vec![
+                Grapheme::from("👍🏽", &config),
+                Grapheme::from("x", &config)
+            ])
+        );
+        assert_eq!(literal1.to_string(), "ab");
+        assert_eq!(literal2.to_string(), "cd");
+    }
+
     #[test]
     fn ensure_correct_removal_of_prefix_in_literal() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let mut literal =
             Expression::new_literal(GraphemeCluster::from("abcdef", &config), &config);
         assert_eq!(
             literal.value(None),
             Some(
-                vec!["a", "b", "c", "d", "e", "f"]
+                ["a", "b", "c", "d", "e", "f"]
                     .iter()
                     .map(|&it| Grapheme::from(it, &config))
                     .collect_vec()
@@ -559,7 +2591,7 @@ mod tests {
         assert_eq!(
             literal.value(None),
             Some(
-                vec!["c", "d", "e", "f"]
+                ["c", "d", "e", "f"]
                     .iter()
                     .map(|&it| Grapheme::from(it, &config))
                     .collect_vec()
@@ -569,13 +2601,13 @@ mod tests {
 
     #[test]
     fn ensure_correct_removal_of_suffix_in_literal() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let mut literal =
             Expression::new_literal(GraphemeCluster::from("abcdef", &config), &config);
         assert_eq!(
             literal.value(None),
             Some(
-                vec!["a", "b", "c", "d", "e", "f"]
+                ["a", "b", "c", "d", "e", "f"]
                     .iter()
                     .map(|&it| Grapheme::from(it, &config))
                     .collect_vec()
@@ -586,7 +2618,7 @@ mod tests {
         assert_eq!(
             literal.value(None),
             Some(
-                vec!["a", "b", "c", "d"]
+                ["a", "b", "c", "d"]
                     .iter()
                     .map(|&it| Grapheme::from(it, &config))
                     .collect_vec()
@@ -594,9 +2626,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ensure_removal_of_prefix_longer_than_literal_does_not_panic() {
+        let config = Rc::new(RegExpConfig::new());
+        let mut literal = Expression::new_literal(GraphemeCluster::from("ab", &config), &config);
+        literal.remove_substring(&Substring::Prefix, 3);
+        assert_eq!(literal.value(None), Some(vec![]));
+    }
+
+    #[test]
+    fn ensure_removal_of_suffix_longer_than_literal_does_not_panic() {
+        let config = Rc::new(RegExpConfig::new());
+        let mut literal = Expression::new_literal(GraphemeCluster::from("ab", &config), &config);
+        literal.remove_substring(&Substring::Suffix, 3);
+        assert_eq!(literal.value(None), Some(vec![]));
+    }
+
     #[test]
     fn ensure_correct_string_representation_of_repetition_1() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let literal = Expression::new_literal(GraphemeCluster::from("abc", &config), &config);
         let repetition = Expression::new_repetition(literal, Quantifier::KleeneStar, &config);
         assert_eq!(repetition.to_string(), "(?:abc)*");
@@ -604,9 +2652,101 @@ mod tests {
 
     #[test]
     fn ensure_correct_string_representation_of_repetition_2() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let literal = Expression::new_literal(GraphemeCluster::from("a", &config), &config);
         let repetition = Expression::new_repetition(literal, Quantifier::QuestionMark, &config);
         assert_eq!(repetition.to_string(), "a?");
     }
+
+    #[test]
+    fn ensure_correct_string_representation_of_repetition_5() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("a", &config), &config);
+        let repetition = Expression::new_repetition(literal, Quantifier::KleeneStar, &config);
+        assert_eq!(repetition.to_string(), "a*");
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_repetition_6() {
+        let config = Rc::new(RegExpConfig::new());
+        let char_class = Expression::new_character_class(btreeset!['b'], btreeset!['c'], &config);
+        let repetition = Expression::new_repetition(char_class, Quantifier::KleeneStar, &config);
+        assert_eq!(repetition.to_string(), "[bc]*");
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_repetition_7() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("a", &config), &config);
+        let char_class = Expression::new_character_class(btreeset!['b'], btreeset!['c'], &config);
+        let concatenation = Expression::new_concatenation(literal, char_class, &config);
+        assert_eq!(concatenation.to_string(), "a[bc]");
+        let repetition = Expression::new_repetition(concatenation, Quantifier::KleeneStar, &config);
+        assert_eq!(repetition.to_string(), "(?:a[bc])*");
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_alternation_with_empty_option_1() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = |s: &str| Expression::new_literal(GraphemeCluster::from(s, &config), &config);
+        let alternation = Expression::Alternation(
+            vec![literal("bc"), literal("a"), literal("")],
+            Rc::clone(&config),
+        );
+        assert_eq!(alternation.to_string(), "(?:bc|a)?");
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_alternation_with_empty_option_2() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = |s: &str| Expression::new_literal(GraphemeCluster::from(s, &config), &config);
+        let alternation = Expression::new_alternation(literal(""), literal("a"), &config);
+        let concatenation = Expression::new_concatenation(literal("x"), alternation, &config);
+        assert_eq!(concatenation.to_string(), "xa?");
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_alternation_with_empty_options_only() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = |s: &str| Expression::new_literal(GraphemeCluster::from(s, &config), &config);
+        let alternation =
+            Expression::Alternation(vec![literal(""), literal("")], Rc::clone(&config));
+        assert_eq!(alternation.to_string(), "");
+    }
+
+    #[test]
+    fn ensure_correct_string_representation_of_repetition_of_empty_literal() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("", &config), &config);
+        let repetition = Expression::new_repetition(literal, Quantifier::QuestionMark, &config);
+        assert_eq!(repetition.to_string(), "");
+    }
+
+    #[test]
+    fn ensure_no_panic_when_extracting_character_set_of_empty_literal() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal = Expression::new_literal(GraphemeCluster::from("", &config), &config);
+        assert_eq!(Expression::extract_character_set(literal), BTreeSet::new());
+    }
+
+    #[test]
+    fn ensure_no_panic_in_union_with_leading_empty_grapheme_flagged_as_single_codepoint() {
+        let config = Rc::new(RegExpConfig::new());
+        let empty_grapheme = Grapheme::new(vec![], 1, 1, &config);
+        let literal1 = Expression::new_literal(
+            GraphemeCluster::from_graphemes(
+                vec![empty_grapheme, Grapheme::from("b", &config)],
+                &config,
+            ),
+            &config,
+        );
+        let literal2 = Expression::new_literal(GraphemeCluster::from("a", &config), &config);
+        assert!(literal1.is_single_codepoint());
+        assert_eq!(
+            Expression::extract_character_set(literal1.clone()),
+            btreeset!['b']
+        );
+        let union = Expression::union(&Some(literal1), &Some(literal2), &config);
+        assert_eq!(union.unwrap().to_string(), "[ab]");
+    }
 }
@@ -0,0 +1,101 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::{Expression, Quantifier};
+use crate::char::Grapheme;
+use std::collections::BTreeSet;
+
+/// The characters a non-empty match can start with, together with
+/// whether the expression matches the empty string as well.
+type FirstChars = (BTreeSet<char>, bool);
+
+impl Expression {
+    /// Computes the set of characters which a match of this expression can start with.
+    /// Returns `None` if this set is not known exactly, which is the case if a match
+    /// may start with a shorthand class such as `\d`, a wildcard or any character.
+    pub(crate) fn first_chars(&self) -> Option<BTreeSet<char>> {
+        self.first_chars_and_nullability().map(|(chars, _)| chars)
+    }
+
+    fn first_chars_and_nullability(&self) -> Option<FirstChars> {
+        match self {
+            Expression::Alternation(options, _) => {
+                let mut chars = BTreeSet::new();
+                let mut is_nullable = false;
+                for option in options {
+                    let (option_chars, is_option_nullable) =
+                        option.first_chars_and_nullability()?;
+                    chars.extend(option_chars);
+                    is_nullable |= is_option_nullable;
+                }
+                Some((chars, is_nullable))
+            }
+            Expression::AnyChar(_) => None,
+            Expression::CharacterClass(char_set, _) => Some((char_set.clone(), false)),
+            Expression::Concatenation(expr1, expr2, _) => {
+                let (mut chars, is_nullable) = expr1.first_chars_and_nullability()?;
+                if !is_nullable {
+                    return Some((chars, false));
+                }
+                let (chars2, is_nullable2) = expr2.first_chars_and_nullability()?;
+                chars.extend(chars2);
+                Some((chars, is_nullable2))
+            }
+            Expression::Literal(cluster, _) => graphemes_first_chars(cluster.graphemes()),
+            Expression::Repetition(expr, quantifier, _) => {
+                let (chars, is_nullable) = expr.first_chars_and_nullability()?;
+                let is_quantifier_nullable = matches!(
+                    quantifier,
+                    Quantifier::KleeneStar
+                        | Quantifier::QuestionMark
+                        | Quantifier::AtLeast(0)
+                        | Quantifier::Range(0, _)
+                );
+                Some((chars, is_nullable || is_quantifier_nullable))
+            }
+            Expression::WordBoundary(_) => Some((BTreeSet::new(), true)),
+        }
+    }
+}
+
+fn graphemes_first_chars(graphemes: &[Grapheme]) -> Option<FirstChars> {
+    let mut chars = BTreeSet::new();
+    for grapheme in graphemes {
+        let (grapheme_chars, is_nullable) = grapheme_first_chars(grapheme)?;
+        chars.extend(grapheme_chars);
+        if !is_nullable {
+            return Some((chars, false));
+        }
+    }
+    Some((chars, true))
+}
+
+fn grapheme_first_chars(grapheme: &Grapheme) -> Option<FirstChars> {
+    let (chars, is_nullable) = if grapheme.has_repetitions() {
+        graphemes_first_chars(&grapheme.repetitions)?
+    } else {
+        let value = grapheme.value();
+        if grapheme.is_char_class() {
+            return None;
+        } else {
+            match value.chars().next() {
+                Some(c) => (btreeset![c], false),
+                None => (BTreeSet::new(), true),
+            }
+        }
+    };
+    Some((chars, is_nullable || grapheme.minimum() == 0))
+}
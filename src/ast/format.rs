@@ -15,54 +15,77 @@
  */
 
 use crate::ast::{Expression, Quantifier};
-use crate::char::GraphemeCluster;
+use crate::char::{
+    escape_control_char, hex_digit_ranges, opposite_case, Grapheme, GraphemeCluster,
+};
 use crate::regexp::{Component, RegExpConfig};
+use crate::unicode_tables::{BLOCKS, SCRIPTS};
 use itertools::Itertools;
+use std::cmp::Reverse;
 use std::collections::BTreeSet;
 use std::fmt::{Display, Formatter, Result};
-use unic_char_range::CharRange;
+use std::rc::Rc;
 
 impl Display for Expression {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
             Expression::Alternation(options, config) => {
-                format_alternation(f, &self, options, config)
+                format_alternation(f, self, options, config)
             }
+            Expression::AnyChar(config) => format_any_char(f, config),
             Expression::CharacterClass(char_set, config) => {
                 format_character_class(f, char_set, config)
             }
             Expression::Concatenation(expr1, expr2, config) => {
-                format_concatenation(f, &self, expr1, expr2, config)
+                format_concatenation(f, self, expr1, expr2, config)
             }
             Expression::Literal(cluster, config) => format_literal(f, cluster, config),
             Expression::Repetition(expr, quantifier, config) => {
-                format_repetition(f, &self, expr, quantifier, config)
+                format_repetition(f, self, expr, quantifier, config)
             }
+            Expression::WordBoundary(config) => format_word_boundary(f, config),
         }
     }
 }
 
-fn get_codepoint_position(c: char) -> usize {
-    CharRange::all().iter().position(|it| it == c).unwrap()
+// Positions are the raw scalar values so that characters on either side
+// of the surrogate gap (U+D800 to U+DFFF) are never considered adjacent.
+fn get_codepoint_position(c: char) -> u32 {
+    c as u32
 }
 
 fn format_alternation(
     f: &mut Formatter<'_>,
     expr: &Expression,
     options: &[Expression],
-    config: &RegExpConfig,
+    config: &Rc<RegExpConfig>,
 ) -> Result {
+    // An empty option would leave a dangling pipe, such as in a|b|, which some
+    // regex engines reject. The remaining options are made optional instead.
+    if options.iter().any(|option| option.is_empty()) {
+        let mut non_empty_options = options
+            .iter()
+            .filter(|option| !option.is_empty())
+            .cloned()
+            .collect_vec();
+        let remaining = match non_empty_options.len() {
+            0 => return Ok(()),
+            1 => non_empty_options.remove(0),
+            _ => Expression::Alternation(non_empty_options, Rc::clone(config)),
+        };
+        let repetition = Expression::Repetition(
+            Box::from(remaining),
+            Quantifier::QuestionMark,
+            Rc::clone(config),
+        );
+        return write!(f, "{}", repetition);
+    }
+
     let alternation_str = options
         .iter()
         .map(|option| {
             if option.precedence() < expr.precedence() && !option.is_single_codepoint() {
-                if config.is_capturing_group_enabled() {
-                    Component::CapturedParenthesizedExpression(option.to_string())
-                        .to_repr(config.is_output_colorized)
-                } else {
-                    Component::UncapturedParenthesizedExpression(option.to_string())
-                        .to_repr(config.is_output_colorized)
-                }
+                Component::group(option.to_string(), option.is_fixed_length(), config)
             } else {
                 format!("{}", option)
             }
@@ -72,42 +95,260 @@ fn format_alternation(
     write!(f, "{}", alternation_str)
 }
 
+fn format_any_char(f: &mut Formatter<'_>, config: &RegExpConfig) -> Result {
+    write!(f, "{}", config.dot().to_repr(config.is_output_colorized))
+}
+
+/// Writes a character class in a canonical form, so that equal sets always result in
+/// the same output regardless of how they have been built. The characters are split into
+/// maximal runs of consecutive code points, which are listed in ascending order of their
+/// lowest code point. Runs of three or more characters are written as ranges, shorter runs
+/// as single characters. For instance, `{x, a, y, c, z, b, m, n}` becomes `[a-cmnx-z]`
+/// and never `[x-za-cmn]` or `[a-bcmnx-z]`.
+/// Since every code point within a range has to be contained in the set, a range
+/// like `A-z`, which spans `[`, `\`, `]`, `^`, `_` and the backtick as well,
+/// is never written for letters only.
+///
+/// If hexadecimal digits are converted, a class of exactly those is written as
+/// `[0-9a-f]`, `[0-9A-F]` or `[0-9a-fA-F]`, with lower case letters before upper case ones.
+/// If an alphabet has been provided, the class is written as a negated class of the
+/// remaining characters of the alphabet or as a dot instead, whichever is shortest.
+/// If a flavor for Unicode block names has been provided or Unicode script names are used,
+/// the blocks and scripts entirely contained in the class are written as named references
+/// in front of the remaining characters.
 fn format_character_class(
     f: &mut Formatter<'_>,
     char_set: &BTreeSet<char>,
     config: &RegExpConfig,
 ) -> Result {
-    let chars_to_escape = ['[', ']', '\\', '-', '^'];
-    let escaped_char_set = char_set
+    let expanded_char_set;
+    let char_set = if config.is_case_insensitivity_expanded() {
+        expanded_char_set = char_set
+            .iter()
+            .flat_map(|&c| std::iter::once(c).chain(opposite_case(c)))
+            .collect::<BTreeSet<char>>();
+        &expanded_char_set
+    } else {
+        char_set
+    };
+
+    if let Some(ranges) = hex_digit_ranges(char_set).filter(|_| config.is_hex_converted()) {
+        return write!(
+            f,
+            "{}{}{}",
+            Component::LeftBracket.to_repr(config.is_output_colorized),
+            ranges
+                .iter()
+                .map(|(start, end)| format!(
+                    "{}{}{}",
+                    start,
+                    Component::Hyphen.to_repr(config.is_output_colorized),
+                    end
+                ))
+                .join(""),
+            Component::RightBracket.to_repr(config.is_output_colorized)
+        );
+    }
+
+    let named_sets = find_named_char_sets(char_set, config);
+    if !named_sets.is_empty() {
+        return format_character_class_with_names(f, char_set, &named_sets, config);
+    }
+
+    if let Some(alphabet) = &config.char_class_alphabet {
+        return format_shortest_character_class(f, char_set, alphabet, config);
+    }
+
+    write!(
+        f,
+        "{}{}{}",
+        Component::LeftBracket.to_repr(config.is_output_colorized),
+        format_character_class_content(char_set, config, config.is_output_colorized),
+        Component::RightBracket.to_repr(config.is_output_colorized)
+    )
+}
+
+/// Returns the references to the Unicode blocks and scripts which are entirely contained
+/// in the character class, in ascending order of their lowest code point.
+fn find_named_char_sets(
+    char_set: &BTreeSet<char>,
+    config: &RegExpConfig,
+) -> Vec<(String, &'static [(char, char)])> {
+    let blocks = config.unicode_block_flavor.into_iter().flat_map(|flavor| {
+        BLOCKS
+            .iter()
+            .map(move |&(name, ranges)| (flavor.block_reference(name), ranges))
+    });
+    let scripts = SCRIPTS
         .iter()
-        .map(|c| {
-            if chars_to_escape.contains(&c) {
-                format!("{}{}", "\\", c)
-            } else if c == &'\n' {
-                "\\n".to_string()
-            } else if c == &'\r' {
-                "\\r".to_string()
-            } else if c == &'\t' {
-                "\\t".to_string()
-            } else {
-                c.to_string()
-            }
+        .filter(|_| config.is_unicode_script_name_used)
+        .map(|&(name, ranges)| (format!("\\p{{{}}}", name), ranges));
+
+    blocks
+        .chain(scripts)
+        .filter(|(_, ranges)| {
+            ranges.iter().all(|&(start, end)| {
+                char_set.range(start..=end).count() == end as usize - start as usize + 1
+            })
         })
-        .collect_vec();
+        .sorted_by_key(|(_, ranges)| ranges[0].0)
+        .collect_vec()
+}
+
+fn format_character_class_with_names(
+    f: &mut Formatter<'_>,
+    char_set: &BTreeSet<char>,
+    named_sets: &[(String, &[(char, char)])],
+    config: &RegExpConfig,
+) -> Result {
+    let references = named_sets
+        .iter()
+        .map(|(reference, _)| {
+            Component::CharClass(reference.clone()).to_repr(config.is_output_colorized)
+        })
+        .join("");
+    let remaining_chars = char_set
+        .iter()
+        .filter(|&&c| {
+            !named_sets.iter().any(|(_, ranges)| {
+                ranges
+                    .iter()
+                    .any(|&(start, end)| (start..=end).contains(&c))
+            })
+        })
+        .copied()
+        .collect::<BTreeSet<char>>();
+
+    if named_sets.len() == 1 && remaining_chars.is_empty() {
+        return write!(f, "{}", references);
+    }
+
+    write!(
+        f,
+        "{}{}{}{}",
+        Component::LeftBracket.to_repr(config.is_output_colorized),
+        references,
+        format_character_class_content(&remaining_chars, config, config.is_output_colorized),
+        Component::RightBracket.to_repr(config.is_output_colorized)
+    )
+}
+
+fn format_shortest_character_class(
+    f: &mut Formatter<'_>,
+    char_set: &BTreeSet<char>,
+    alphabet: &BTreeSet<char>,
+    config: &RegExpConfig,
+) -> Result {
+    // With case-insensitive matching, a negated class excludes both cases of its
+    // characters, so the opposite cases of the set must not be part of it.
+    let matched_chars = if config.is_case_insensitive_matching() {
+        char_set
+            .iter()
+            .flat_map(|&c| std::iter::once(c).chain(opposite_case(c)))
+            .collect::<BTreeSet<char>>()
+    } else {
+        char_set.clone()
+    };
+    let remaining_chars = alphabet
+        .difference(&matched_chars)
+        .copied()
+        .collect::<BTreeSet<char>>();
+
+    if remaining_chars.is_empty() {
+        let any_char = if config.is_dot_matching_newline {
+            Some(config.dot().to_repr(config.is_output_colorized))
+        } else if !alphabet.contains(&'\n') && config.is_newline_exclusion_explicit {
+            Some(Component::DotExcludingNewline.to_repr(config.is_output_colorized))
+        } else if !alphabet.contains(&'\n') {
+            Some(Component::Dot.to_repr(config.is_output_colorized))
+        } else if !config.is_posix_char_class_used {
+            Some(format!(
+                "{}{}{}",
+                Component::LeftBracket.to_repr(config.is_output_colorized),
+                "\\s\\S",
+                Component::RightBracket.to_repr(config.is_output_colorized)
+            ))
+        } else {
+            None
+        };
+        if let Some(any_char) = any_char {
+            return write!(f, "{}", any_char);
+        }
+    } else {
+        let positive_length = format_character_class_content(char_set, config, false)
+            .chars()
+            .count();
+        let negated_length = format_character_class_content(&remaining_chars, config, false)
+            .chars()
+            .count()
+            + 1;
+        if negated_length < positive_length {
+            return write!(
+                f,
+                "{}{}{}{}",
+                Component::LeftBracket.to_repr(config.is_output_colorized),
+                Component::Caret.to_repr(config.is_output_colorized),
+                format_character_class_content(
+                    &remaining_chars,
+                    config,
+                    config.is_output_colorized
+                ),
+                Component::RightBracket.to_repr(config.is_output_colorized)
+            );
+        }
+    }
+
+    write!(
+        f,
+        "{}{}{}",
+        Component::LeftBracket.to_repr(config.is_output_colorized),
+        format_character_class_content(char_set, config, config.is_output_colorized),
+        Component::RightBracket.to_repr(config.is_output_colorized)
+    )
+}
+
+fn format_character_class_content(
+    char_set: &BTreeSet<char>,
+    config: &RegExpConfig,
+    is_output_colorized: bool,
+) -> String {
+    let chars_to_escape = ['[', ']', '\\', '-', '^'];
+    let escape = |c: &char| {
+        if chars_to_escape.contains(c) {
+            format!("{}{}", "\\", c)
+        } else {
+            escape_control_char(*c, config.is_control_char_hex_escaped)
+        }
+    };
+
+    if config.is_char_class_order_preserved {
+        return char_set
+            .iter()
+            .sorted_by_key(|c| config.char_positions.get(c).unwrap_or(&usize::MAX))
+            .map(escape)
+            .join("");
+    }
+
+    if config.is_char_class_ordered_by_frequency {
+        return char_set
+            .iter()
+            .sorted_by_key(|c| Reverse(config.char_frequencies.get(c).unwrap_or(&0)))
+            .map(escape)
+            .join("");
+    }
+
+    let escaped_char_set = char_set.iter().map(escape).collect_vec();
     let char_positions = char_set
         .iter()
         .map(|&it| get_codepoint_position(it))
         .collect_vec();
 
     let mut subsets = vec![];
-    let mut subset = vec![];
+    let mut subset = escaped_char_set.iter().take(1).collect_vec();
 
-    for ((first_c, first_pos), (second_c, second_pos)) in
+    for ((_, first_pos), (second_c, second_pos)) in
         escaped_char_set.iter().zip(char_positions).tuple_windows()
     {
-        if subset.is_empty() {
-            subset.push(first_c);
-        }
         if second_pos == first_pos + 1 {
             subset.push(second_c);
         } else {
@@ -129,19 +370,13 @@ fn format_character_class(
             char_class_strs.push(format!(
                 "{}{}{}",
                 subset.first().unwrap(),
-                Component::Hyphen.to_repr(config.is_output_colorized),
+                Component::Hyphen.to_repr(is_output_colorized),
                 subset.last().unwrap()
             ));
         }
     }
 
-    write!(
-        f,
-        "{}{}{}",
-        Component::LeftBracket.to_repr(config.is_output_colorized),
-        char_class_strs.join(""),
-        Component::RightBracket.to_repr(config.is_output_colorized)
-    )
+    char_class_strs.join("")
 }
 
 fn format_concatenation(
@@ -151,17 +386,11 @@ fn format_concatenation(
     expr2: &Expression,
     config: &RegExpConfig,
 ) -> Result {
-    let expr_strs = vec![expr1, expr2]
+    let expr_strs = [expr1, expr2]
         .iter()
         .map(|&it| {
             if it.precedence() < expr.precedence() && !it.is_single_codepoint() {
-                if config.is_capturing_group_enabled() {
-                    Component::CapturedParenthesizedExpression(it.to_string())
-                        .to_repr(config.is_output_colorized)
-                } else {
-                    Component::UncapturedParenthesizedExpression(it.to_string())
-                        .to_repr(config.is_output_colorized)
-                }
+                Component::group(it.to_string(), it.is_fixed_length(), config)
             } else {
                 format!("{}", it)
             }
@@ -181,7 +410,7 @@ fn format_literal(
     cluster: &GraphemeCluster,
     config: &RegExpConfig,
 ) -> Result {
-    let literal_str = cluster
+    let escaped_graphemes = cluster
         .graphemes()
         .iter()
         .cloned()
@@ -202,7 +431,37 @@ fn format_literal(
                     config.is_astral_code_point_converted_to_surrogate,
                 );
             }
-            grapheme.to_string()
+            grapheme
+        })
+        .collect_vec();
+
+    if !config.is_literal_quoted {
+        return write!(f, "{}", escaped_graphemes.iter().join(""));
+    }
+
+    let literal_str = cluster
+        .graphemes()
+        .iter()
+        .zip(escaped_graphemes.iter())
+        .group_by(|(grapheme, escaped_grapheme)| grapheme.is_quotable(escaped_grapheme))
+        .into_iter()
+        .map(|(is_quotable, graphemes)| {
+            let (graphemes, escaped_graphemes): (Vec<&Grapheme>, Vec<&Grapheme>) =
+                graphemes.unzip();
+            let escaped_str = escaped_graphemes.iter().join("");
+            if !is_quotable {
+                return escaped_str;
+            }
+            // A literal \E would end the span early, so it is written outside of it.
+            let quoted_str = format!(
+                "\\Q{}\\E",
+                graphemes.iter().join("").replace("\\E", "\\E\\\\E\\Q")
+            );
+            if quoted_str.chars().count() < escaped_str.chars().count() {
+                quoted_str
+            } else {
+                escaped_str
+            }
         })
         .join("");
 
@@ -216,30 +475,56 @@ fn format_repetition(
     quantifier: &Quantifier,
     config: &RegExpConfig,
 ) -> Result {
-    if expr1.precedence() < expr.precedence() && !expr1.is_single_codepoint() {
-        if config.is_capturing_group_enabled() {
-            write!(
-                f,
-                "{}{}",
-                Component::CapturedParenthesizedExpression(expr1.to_string())
-                    .to_repr(config.is_output_colorized),
-                Component::Quantifier(quantifier.clone()).to_repr(config.is_output_colorized)
-            )
-        } else {
-            write!(
-                f,
-                "{}{}",
-                Component::UncapturedParenthesizedExpression(expr1.to_string())
-                    .to_repr(config.is_output_colorized),
-                Component::Quantifier(quantifier.clone()).to_repr(config.is_output_colorized)
-            )
-        }
+    // A quantifier without anything to repeat would be a syntax error.
+    if expr1.is_empty() {
+        return Ok(());
+    }
+
+    let quantifier = if config.is_quantifier_braced {
+        quantifier.to_braced()
+    } else {
+        quantifier.clone()
+    };
+
+    // Single characters and character classes are atoms which are quantified directly,
+    // whereas concatenations and literals of several characters have to be grouped.
+    // A shorthand class repeated without an upper bound, such as the wildcard \S*,
+    // is quantified directly as well.
+    // An optional repetition or a repeated optional expression is grouped because
+    // quantifiers such as *? or ?* would otherwise be read as a lazy quantifier
+    // or be rejected altogether.
+    let is_unbounded_class = quantifier.bounds().1.is_none() && expr1.is_shorthand_class();
+    let is_optional_repetition_nested = is_optional_or_kleene_star(&quantifier)
+        && matches!(expr1, Expression::Repetition(_, inner_quantifier, _)
+            if is_optional_or_kleene_star(inner_quantifier));
+    if (expr1.precedence() < expr.precedence() || is_optional_repetition_nested)
+        && !expr1.is_single_codepoint()
+        && !is_unbounded_class
+    {
+        write!(
+            f,
+            "{}{}",
+            Component::group(expr1.to_string(), expr1.is_fixed_length(), config),
+            Component::Quantifier(quantifier).to_repr(config.is_output_colorized)
+        )
     } else {
         write!(
             f,
             "{}{}",
             expr1,
-            Component::Quantifier(quantifier.clone()).to_repr(config.is_output_colorized)
+            Component::Quantifier(quantifier).to_repr(config.is_output_colorized)
         )
     }
 }
+
+fn is_optional_or_kleene_star(quantifier: &Quantifier) -> bool {
+    matches!(quantifier.bounds(), (0, Some(1)) | (0, None))
+}
+
+fn format_word_boundary(f: &mut Formatter<'_>, config: &RegExpConfig) -> Result {
+    write!(
+        f,
+        "{}",
+        Component::WordBoundary.to_repr(config.is_output_colorized)
+    )
+}
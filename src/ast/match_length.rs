@@ -0,0 +1,68 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::Expression;
+use crate::char::Grapheme;
+
+impl Expression {
+    /// Computes the number of characters of the longest string matched by this expression.
+    /// Returns `None` if there is no such string because the expression contains an
+    /// unbounded quantifier such as `*` or `+`.
+    pub(crate) fn maximum_match_length(&self) -> Option<usize> {
+        match self {
+            Expression::Alternation(options, _) => options
+                .iter()
+                .map(|it| it.maximum_match_length())
+                .try_fold(0, |max, length| length.map(|it| max.max(it))),
+            Expression::AnyChar(_) | Expression::CharacterClass(_, _) => Some(1),
+            Expression::Concatenation(expr1, expr2, _) => {
+                Some(expr1.maximum_match_length()? + expr2.maximum_match_length()?)
+            }
+            Expression::Literal(cluster, _) => graphemes_maximum_length(cluster.graphemes()),
+            Expression::Repetition(expr, quantifier, _) => {
+                repeat_length(expr.maximum_match_length()?, quantifier.bounds().1)
+            }
+            Expression::WordBoundary(_) => Some(0),
+        }
+    }
+}
+
+fn graphemes_maximum_length(graphemes: &[Grapheme]) -> Option<usize> {
+    graphemes
+        .iter()
+        .map(grapheme_maximum_length)
+        .try_fold(0, |sum, length| length.map(|it| sum + it))
+}
+
+fn grapheme_maximum_length(grapheme: &Grapheme) -> Option<usize> {
+    let length = if grapheme.has_repetitions() {
+        graphemes_maximum_length(&grapheme.repetitions)?
+    } else {
+        // Each character class is represented by a sample of a single character.
+        grapheme.sample_value().chars().count()
+    };
+    repeat_length(length, grapheme.bounds().1)
+}
+
+/// Returns the length of `maximum` repetitions of a string of the given `length`,
+/// which is unbounded if `maximum` is `None` unless the string is empty.
+fn repeat_length(length: usize, maximum: Option<u32>) -> Option<usize> {
+    match maximum {
+        Some(max) => Some(length * max as usize),
+        None if length == 0 => Some(0),
+        None => None,
+    }
+}
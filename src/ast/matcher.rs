@@ -0,0 +1,361 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::nfa::grapheme_value_transitions;
+use crate::ast::Expression;
+use crate::char::Grapheme;
+use crate::fsm::Transition;
+use crate::regexp::RegExpConfig;
+use itertools::Itertools;
+
+/// Tries to match at the given position and passes each possible end position
+/// to the continuation until it returns true.
+type Matcher<'a> = dyn Fn(&[char], usize, &mut dyn FnMut(usize) -> bool) -> bool + 'a;
+
+impl Expression {
+    /// Returns true if this expression matches the entire string `s`, as if it was anchored
+    /// at both ends. The expression is walked directly by a backtracking matcher, so no
+    /// regular expression needs to be compiled. Word boundaries are checked against
+    /// the characters surrounding the current position.
+    pub(crate) fn matches(&self, s: &str) -> bool {
+        let chars = s.chars().collect_vec();
+        self.match_at(&chars, 0, &mut |end| end == chars.len())
+    }
+
+    fn match_at(&self, chars: &[char], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+        match self {
+            Expression::Alternation(options, _) => options
+                .iter()
+                .any(|option| option.match_at(chars, pos, &mut *next)),
+            Expression::AnyChar(config) => {
+                let transition = Transition::AnyChar {
+                    is_newline_matched: config.is_dot_matching_newline,
+                };
+                match_transition(&transition, chars, pos, config, next)
+            }
+            Expression::CharacterClass(char_set, config) => {
+                let transition = Transition::CharSet(char_set.clone());
+                match_transition(&transition, chars, pos, config, next)
+            }
+            Expression::Concatenation(expr1, expr2, _) => {
+                expr1.match_at(chars, pos, &mut |middle| {
+                    expr2.match_at(chars, middle, next)
+                })
+            }
+            Expression::Literal(cluster, config) => {
+                match_graphemes(cluster.graphemes(), chars, pos, config, next)
+            }
+            Expression::Repetition(expr, quantifier, _) => {
+                let (min, max) = quantifier.bounds();
+                match_repetition(
+                    &|chars, pos, next| expr.match_at(chars, pos, next),
+                    chars,
+                    pos,
+                    (0, min, max),
+                    next,
+                )
+            }
+            Expression::WordBoundary(_) => {
+                let is_word_char = |c: &char| Transition::Word.matches(*c);
+                let is_word_char_before = pos > 0 && is_word_char(&chars[pos - 1]);
+                let is_word_char_after = chars.get(pos).is_some_and(is_word_char);
+                is_word_char_before != is_word_char_after && next(pos)
+            }
+        }
+    }
+}
+
+fn match_transition(
+    transition: &Transition,
+    chars: &[char],
+    pos: usize,
+    config: &RegExpConfig,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    let is_matched = |&c: &char| {
+        if config.is_case_insensitive_matching() {
+            transition.matches_ignoring_case(c)
+        } else {
+            transition.matches(c)
+        }
+    };
+    chars.get(pos).is_some_and(is_matched) && next(pos + 1)
+}
+
+/// Matches as many repetitions as possible first and backtracks to fewer ones.
+/// Once the minimum count is reached, repetitions matching the empty string are
+/// not tried again, so that unbounded repetitions terminate.
+fn match_repetition(
+    matcher: &Matcher,
+    chars: &[char],
+    pos: usize,
+    (count, min, max): (u32, u32, Option<u32>),
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    if max != Some(count)
+        && matcher(chars, pos, &mut |end| {
+            (end > pos || count < min)
+                && match_repetition(matcher, chars, end, (count + 1, min, max), next)
+        })
+    {
+        return true;
+    }
+    count >= min && next(pos)
+}
+
+fn match_graphemes(
+    graphemes: &[Grapheme],
+    chars: &[char],
+    pos: usize,
+    config: &RegExpConfig,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    match graphemes.split_first() {
+        Some((grapheme, rest)) => {
+            let (min, max) = grapheme.bounds();
+            match_repetition(
+                &|chars, pos, next| {
+                    if grapheme.has_repetitions() {
+                        match_graphemes(&grapheme.repetitions, chars, pos, config, next)
+                    } else {
+                        match_values(grapheme.chars(), chars, pos, config, next)
+                    }
+                },
+                chars,
+                pos,
+                (0, min, max),
+                &mut |end| match_graphemes(rest, chars, end, config, next),
+            )
+        }
+        None => next(pos),
+    }
+}
+
+fn match_values(
+    values: &[String],
+    chars: &[char],
+    pos: usize,
+    config: &RegExpConfig,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    match values.split_first() {
+        Some((value, rest)) => {
+            let next = &mut |end| match_values(rest, chars, end, config, next);
+            match_transitions(&grapheme_value_transitions(value), chars, pos, config, next)
+        }
+        None => next(pos),
+    }
+}
+
+fn match_transitions(
+    transitions: &[Transition],
+    chars: &[char],
+    pos: usize,
+    config: &RegExpConfig,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    match transitions.split_first() {
+        Some((transition, rest)) => match_transition(transition, chars, pos, config, &mut |end| {
+            match_transitions(rest, chars, end, config, next)
+        }),
+        None => next(pos),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Quantifier;
+    use crate::char::GraphemeCluster;
+    use crate::fsm::Dfa;
+    use crate::regexp::Feature;
+    use regex::Regex;
+    use std::rc::Rc;
+
+    fn expression(test_cases: &[&str], config: &Rc<RegExpConfig>) -> Expression {
+        let mut clusters = test_cases
+            .iter()
+            .map(|it| GraphemeCluster::from(it, config))
+            .collect_vec();
+        for cluster in clusters.iter_mut() {
+            if config.is_char_class_feature_enabled() {
+                cluster.convert_to_char_classes();
+            }
+            if config.is_repetition_converted() {
+                cluster.convert_repetitions();
+            }
+        }
+        Expression::from(Dfa::from(clusters, config), config, None, None)
+            .unwrap()
+            .unwrap()
+    }
+
+    fn assert_that_matcher_agrees_with_regex(expr: &Expression, regexp: &str, inputs: &[&str]) {
+        let regex = Regex::new(regexp).unwrap();
+        for input in inputs {
+            assert_eq!(
+                expr.matches(input),
+                regex.is_match(input),
+                "disagreement on {:?} for {}",
+                input,
+                regexp
+            );
+        }
+    }
+
+    #[test]
+    fn ensure_matcher_agrees_with_regex_for_alternations_and_classes() {
+        let config = Rc::new(RegExpConfig::new());
+        let expr = expression(&["abc", "abd", "xyz", "x"], &config);
+        assert_eq!(expr.to_string(), "ab[cd]|x(?:yz)?");
+        assert_that_matcher_agrees_with_regex(
+            &expr,
+            "^(?:ab[cd]|x(?:yz)?)$",
+            &[
+                "abc", "abd", "xyz", "x", "", "ab", "abe", "xy", "xyzx", "abcd", "zabc",
+            ],
+        );
+    }
+
+    #[test]
+    fn ensure_matcher_agrees_with_regex_for_shorthand_classes() {
+        let mut config = RegExpConfig::new();
+        config.conversion_features = vec![Feature::Digit, Feature::Space, Feature::Word];
+        let config = Rc::new(config);
+        let expr = expression(&["a1 ", "b2\t", "3 c"], &config);
+        assert_eq!(expr.to_string(), "\\w\\d\\s|\\d\\s\\w");
+        assert_that_matcher_agrees_with_regex(
+            &expr,
+            "^(?:\\w\\d\\s|\\d\\s\\w)$",
+            &[
+                "a1 ", "z9\n", "4 _", "a1", "ab ", "1a ", " 1a", "a1  ", "é٣ ",
+            ],
+        );
+    }
+
+    #[test]
+    fn ensure_matcher_agrees_with_regex_for_repetitions() {
+        let mut config = RegExpConfig::new();
+        config.conversion_features = vec![Feature::Repetition];
+        let config = Rc::new(config);
+        let expr = expression(&["aaa", "abababab", "cc"], &config);
+        assert_eq!(expr.to_string(), "a{3}|(?:ab){4}|c{2}");
+        assert_that_matcher_agrees_with_regex(
+            &expr,
+            "^(?:a{3}|(?:ab){4}|c{2})$",
+            &[
+                "aaa",
+                "abababab",
+                "cc",
+                "aa",
+                "aaaa",
+                "ababab",
+                "ababababab",
+                "c",
+                "ccc",
+                "",
+            ],
+        );
+    }
+
+    #[test]
+    fn ensure_matcher_agrees_with_regex_for_case_insensitivity() {
+        let mut config = RegExpConfig::new();
+        config.conversion_features = vec![Feature::CaseInsensitivity];
+        let config = Rc::new(config);
+        let expr = expression(&["abc", "xy"], &config);
+        assert_eq!(expr.to_string(), "abc|xy");
+        assert_that_matcher_agrees_with_regex(
+            &expr,
+            "(?i)^(?:abc|xy)$",
+            &["abc", "ABC", "aBc", "XY", "xY", "ab", "xyz"],
+        );
+    }
+
+    #[test]
+    fn ensure_matcher_agrees_with_regex_for_nested_repetitions() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal =
+            |s: &str| Expression::Literal(GraphemeCluster::from(s, &config), Rc::clone(&config));
+        let optional = Expression::Repetition(
+            Box::from(literal("a")),
+            Quantifier::QuestionMark,
+            Rc::clone(&config),
+        );
+        let expr = Expression::Concatenation(
+            Box::from(Expression::Repetition(
+                Box::from(Expression::Alternation(
+                    vec![optional, literal("bc")],
+                    Rc::clone(&config),
+                )),
+                Quantifier::KleeneStar,
+                Rc::clone(&config),
+            )),
+            Box::from(Expression::Repetition(
+                Box::from(Expression::CharacterClass(
+                    btreeset!['x', 'y'],
+                    Rc::clone(&config),
+                )),
+                Quantifier::Range(1, 2),
+                Rc::clone(&config),
+            )),
+            Rc::clone(&config),
+        );
+        assert_eq!(expr.to_string(), "(?:a?|bc)*[xy]{1,2}");
+        assert_that_matcher_agrees_with_regex(
+            &expr,
+            "^(?:(?:a?|bc)*[xy]{1,2})$",
+            &[
+                "x",
+                "xy",
+                "xyx",
+                "",
+                "abcax",
+                "bcbcyy",
+                "bx",
+                "aaaaaaaaaaaay",
+                "cx",
+                "ax ",
+            ],
+        );
+    }
+
+    #[test]
+    fn ensure_matcher_agrees_with_regex_for_word_boundaries_and_wildcards() {
+        let config = Rc::new(RegExpConfig::new());
+        let literal =
+            |s: &str| Expression::Literal(GraphemeCluster::from(s, &config), Rc::clone(&config));
+        let expr = Expression::Concatenation(
+            Box::from(Expression::Concatenation(
+                Box::from(Expression::Repetition(
+                    Box::from(Expression::AnyChar(Rc::clone(&config))),
+                    Quantifier::KleeneStar,
+                    Rc::clone(&config),
+                )),
+                Box::from(Expression::WordBoundary(Rc::clone(&config))),
+                Rc::clone(&config),
+            )),
+            Box::from(literal("ab")),
+            Rc::clone(&config),
+        );
+        assert_eq!(expr.to_string(), ".*\\bab");
+        assert_that_matcher_agrees_with_regex(
+            &expr,
+            "^(?:.*\\bab)$",
+            &["ab", "x ab", "xab", "-ab", "\nab", "a b", "ab ab", ""],
+        );
+    }
+}
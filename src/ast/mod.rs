@@ -14,10 +14,22 @@
  * limitations under the License.
  */
 
+mod conversions;
+mod diff;
+mod explain;
 mod expression;
+mod first_chars;
 mod format;
+mod match_length;
+// The matcher is not called by the builder itself yet, only by its own tests.
+#[allow(dead_code)]
+pub(crate) mod matcher;
+mod nfa;
 mod quantifier;
+mod required_prefix;
+mod sample;
 mod substring;
+mod warnings;
 
 pub use expression::Expression;
 pub use quantifier::Quantifier;
@@ -0,0 +1,223 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::Expression;
+use crate::char::Grapheme;
+use crate::fsm::{Nfa, State, Transition};
+use crate::regexp::RegExpConfig;
+
+impl Expression {
+    /// Converts this expression into a nondeterministic finite automaton by Thompson's
+    /// construction, so that the automaton accepts exactly the strings matched by it.
+    /// Word boundaries are zero-width assertions which cannot be expressed by transitions,
+    /// so they are replaced with epsilon transitions.
+    pub(crate) fn to_nfa(&self, config: &RegExpConfig) -> Nfa {
+        let mut nfa = Nfa::new(config.is_case_insensitive_matching());
+        let start_state = nfa.start_state();
+        let final_state = self.add_to_nfa(&mut nfa, start_state);
+        nfa.set_final_state(final_state);
+        nfa
+    }
+
+    /// Adds the transitions of this expression starting from the given state
+    /// and returns the state in which they end.
+    fn add_to_nfa(&self, nfa: &mut Nfa, start: State) -> State {
+        match self {
+            Expression::Alternation(options, _) => {
+                let end = nfa.add_state();
+                for option in options {
+                    let option_start = nfa.add_state();
+                    nfa.add_transition(start, option_start, Transition::Epsilon);
+                    let option_end = option.add_to_nfa(nfa, option_start);
+                    nfa.add_transition(option_end, end, Transition::Epsilon);
+                }
+                end
+            }
+            Expression::AnyChar(config) => add_transition(
+                nfa,
+                start,
+                Transition::AnyChar {
+                    is_newline_matched: config.is_dot_matching_newline,
+                },
+            ),
+            Expression::CharacterClass(char_set, _) => {
+                add_transition(nfa, start, Transition::CharSet(char_set.clone()))
+            }
+            Expression::Concatenation(expr1, expr2, _) => {
+                let middle = expr1.add_to_nfa(nfa, start);
+                expr2.add_to_nfa(nfa, middle)
+            }
+            Expression::Literal(cluster, _) => cluster
+                .graphemes()
+                .iter()
+                .fold(start, |state, grapheme| add_grapheme(nfa, state, grapheme)),
+            Expression::Repetition(expr, quantifier, _) => {
+                let (min, max) = quantifier.bounds();
+                add_repetition(nfa, start, min, max, &|nfa, state| {
+                    expr.add_to_nfa(nfa, state)
+                })
+            }
+            Expression::WordBoundary(_) => {
+                let end = nfa.add_state();
+                nfa.add_transition(start, end, Transition::Epsilon);
+                end
+            }
+        }
+    }
+}
+
+fn add_transition(nfa: &mut Nfa, start: State, transition: Transition) -> State {
+    let end = nfa.add_state();
+    nfa.add_transition(start, end, transition);
+    end
+}
+
+/// Adds as many copies of the transitions created by `add` as the bounds require.
+/// Optional copies may be skipped by an epsilon transition and an unbounded repetition
+/// loops back to the state before its last copy.
+fn add_repetition(
+    nfa: &mut Nfa,
+    start: State,
+    min: u32,
+    max: Option<u32>,
+    add: &dyn Fn(&mut Nfa, State) -> State,
+) -> State {
+    let mut current = start;
+
+    for _ in 0..min {
+        current = add(nfa, current);
+    }
+
+    match max {
+        Some(max) => {
+            for _ in min..max {
+                let copy_end = add(nfa, current);
+                let next = nfa.add_state();
+                nfa.add_transition(copy_end, next, Transition::Epsilon);
+                nfa.add_transition(current, next, Transition::Epsilon);
+                current = next;
+            }
+            current
+        }
+        None => {
+            let hub = nfa.add_state();
+            nfa.add_transition(current, hub, Transition::Epsilon);
+            let copy_end = add(nfa, hub);
+            nfa.add_transition(copy_end, hub, Transition::Epsilon);
+            hub
+        }
+    }
+}
+
+fn add_grapheme(nfa: &mut Nfa, start: State, grapheme: &Grapheme) -> State {
+    let (min, max) = grapheme.bounds();
+    add_repetition(nfa, start, min, max, &|nfa, state| {
+        if grapheme.has_repetitions() {
+            grapheme
+                .repetitions
+                .iter()
+                .fold(state, |state, repetition| {
+                    add_grapheme(nfa, state, repetition)
+                })
+        } else {
+            grapheme.chars().iter().fold(state, |state, value| {
+                grapheme_value_transitions(value)
+                    .into_iter()
+                    .fold(state, |state, transition| {
+                        add_transition(nfa, state, transition)
+                    })
+            })
+        }
+    })
+}
+
+/// Returns the transitions consuming the characters of a single value of a grapheme,
+/// which may contain shorthand classes such as `\d` after the conversion of single
+/// characters.
+pub(super) fn grapheme_value_transitions(value: &str) -> Vec<Transition> {
+    let mut transitions = vec![];
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        let transition = if c == '\\' {
+            match chars.next() {
+                Some('d') => Transition::Digit,
+                Some('D') => Transition::NonDigit,
+                Some('s') => Transition::Space,
+                Some('S') => Transition::NonSpace,
+                Some('w') => Transition::Word,
+                Some('W') => Transition::NonWord,
+                Some(other) => {
+                    transitions.push(Transition::Char(c));
+                    Transition::Char(other)
+                }
+                None => Transition::Char(c),
+            }
+        } else {
+            Transition::Char(c)
+        };
+        transitions.push(transition);
+    }
+
+    transitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Quantifier;
+    use crate::char::GraphemeCluster;
+    use std::rc::Rc;
+
+    #[test]
+    fn ensure_nfa_accepts_exactly_the_strings_of_a_finite_expression() {
+        let config = Rc::new(RegExpConfig::new());
+        let expr = Expression::Alternation(
+            vec![
+                Expression::Concatenation(
+                    Box::from(Expression::Literal(
+                        GraphemeCluster::from("a", &config),
+                        Rc::clone(&config),
+                    )),
+                    Box::from(Expression::CharacterClass(
+                        btreeset!['b', 'c'],
+                        Rc::clone(&config),
+                    )),
+                    Rc::clone(&config),
+                ),
+                Expression::Repetition(
+                    Box::from(Expression::Literal(
+                        GraphemeCluster::from("d", &config),
+                        Rc::clone(&config),
+                    )),
+                    Quantifier::QuestionMark,
+                    Rc::clone(&config),
+                ),
+            ],
+            Rc::clone(&config),
+        );
+        assert_eq!(expr.to_string(), "a[bc]|d?");
+
+        let nfa = expr.to_nfa(&config);
+
+        for accepted in ["ab", "ac", "d", ""] {
+            assert!(nfa.accepts(accepted), "{:?} is not accepted", accepted);
+        }
+        for rejected in ["a", "b", "abc", "ad", "dd", "bc"] {
+            assert!(!nfa.accepts(rejected), "{:?} is accepted", rejected);
+        }
+    }
+}
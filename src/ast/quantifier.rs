@@ -18,19 +18,45 @@ use std::fmt::{Display, Formatter, Result};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Quantifier {
+    AtLeast(u32),
     KleeneStar,
+    Plus,
     QuestionMark,
+    Range(u32, u32),
+}
+
+impl Quantifier {
+    /// Returns the minimum and the maximum number of repetitions,
+    /// the latter being `None` if the repetition is unbounded.
+    pub(crate) fn bounds(&self) -> (u32, Option<u32>) {
+        match self {
+            Quantifier::AtLeast(min) => (*min, None),
+            Quantifier::KleeneStar => (0, None),
+            Quantifier::Plus => (1, None),
+            Quantifier::QuestionMark => (0, Some(1)),
+            Quantifier::Range(min, max) => (*min, Some(*max)),
+        }
+    }
+
+    pub(crate) fn to_braced(&self) -> Self {
+        match self {
+            Quantifier::KleeneStar => Quantifier::AtLeast(0),
+            Quantifier::Plus => Quantifier::AtLeast(1),
+            Quantifier::QuestionMark => Quantifier::Range(0, 1),
+            other => other.clone(),
+        }
+    }
 }
 
 impl Display for Quantifier {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Quantifier::KleeneStar => '*',
-                Quantifier::QuestionMark => '?',
-            }
-        )
+        match self {
+            Quantifier::AtLeast(min) => write!(f, "{{{},}}", min),
+            Quantifier::KleeneStar => write!(f, "*"),
+            Quantifier::Plus => write!(f, "+"),
+            Quantifier::QuestionMark => write!(f, "?"),
+            Quantifier::Range(min, max) if min == max => write!(f, "{{{}}}", min),
+            Quantifier::Range(min, max) => write!(f, "{{{},{}}}", min, max),
+        }
     }
 }
@@ -0,0 +1,110 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::Expression;
+use crate::char::{is_char_class_value, opposite_case, Grapheme};
+use crate::regexp::RegExpConfig;
+
+/// The literal text every match starts with, together with whether
+/// this text is everything the expression can match.
+type RequiredPrefix = (String, bool);
+
+impl Expression {
+    /// Computes the longest literal text which every match of this expression starts with.
+    /// The leading literals are followed until the first alternation, character class
+    /// or optional part, so the text is empty if the expression starts with one of them.
+    pub(crate) fn required_prefix(&self) -> String {
+        self.required_prefix_and_completeness().0
+    }
+
+    fn required_prefix_and_completeness(&self) -> RequiredPrefix {
+        match self {
+            Expression::Alternation(_, _)
+            | Expression::AnyChar(_)
+            | Expression::CharacterClass(_, _) => (String::new(), false),
+            Expression::Concatenation(expr1, expr2, _) => {
+                let (mut prefix, is_complete) = expr1.required_prefix_and_completeness();
+                if !is_complete {
+                    return (prefix, false);
+                }
+                let (prefix2, is_complete2) = expr2.required_prefix_and_completeness();
+                prefix.push_str(&prefix2);
+                (prefix, is_complete2)
+            }
+            Expression::Literal(cluster, config) => {
+                graphemes_required_prefix(cluster.graphemes(), config)
+            }
+            Expression::Repetition(expr, quantifier, _) => {
+                let (prefix, is_complete) = expr.required_prefix_and_completeness();
+                repeat_required_prefix(prefix, is_complete, quantifier.bounds())
+            }
+            Expression::WordBoundary(_) => (String::new(), true),
+        }
+    }
+}
+
+fn graphemes_required_prefix(graphemes: &[Grapheme], config: &RegExpConfig) -> RequiredPrefix {
+    let mut prefix = String::new();
+    for grapheme in graphemes {
+        let (grapheme_prefix, is_complete) = grapheme_required_prefix(grapheme, config);
+        prefix.push_str(&grapheme_prefix);
+        if !is_complete {
+            return (prefix, false);
+        }
+    }
+    (prefix, true)
+}
+
+fn grapheme_required_prefix(grapheme: &Grapheme, config: &RegExpConfig) -> RequiredPrefix {
+    let (prefix, is_complete) = if grapheme.has_repetitions() {
+        graphemes_required_prefix(&grapheme.repetitions, config)
+    } else {
+        let mut prefix = String::new();
+        let mut is_complete = true;
+        for value in grapheme.chars() {
+            // Classes and letters matched in both cases do not stand for a single text.
+            let is_ambiguous = is_char_class_value(value)
+                || (config.is_case_insensitive_matching()
+                    && value.chars().any(|c| opposite_case(c).is_some()));
+            if is_ambiguous {
+                is_complete = false;
+                break;
+            }
+            prefix.push_str(value);
+        }
+        (prefix, is_complete)
+    };
+    repeat_required_prefix(prefix, is_complete, grapheme.bounds())
+}
+
+/// Returns the required prefix of a repetition of an expression whose required prefix
+/// is `prefix`. Only the first repetition contributes to it unless the expression
+/// matches nothing but the prefix and is repeated a fixed number of times.
+fn repeat_required_prefix(
+    prefix: String,
+    is_complete: bool,
+    (min, max): (u32, Option<u32>),
+) -> RequiredPrefix {
+    if min == 0 {
+        (String::new(), false)
+    } else if is_complete && max == Some(min) {
+        (prefix.repeat(min as usize), true)
+    } else if is_complete {
+        (prefix.repeat(min as usize), false)
+    } else {
+        (prefix, false)
+    }
+}
@@ -0,0 +1,118 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::Expression;
+use crate::char::{Grapheme, GraphemeCluster};
+use itertools::Itertools;
+
+impl Expression {
+    /// Returns up to `limit` strings matched by this expression in shortlex order,
+    /// i.e. shorter strings come first and strings of equal length are ordered
+    /// lexicographically. Character classes and shorthand classes are represented
+    /// by a single sample character each, unbounded repetitions are expanded only
+    /// as far as needed to provide `limit` strings.
+    pub(crate) fn shortest_matches(&self, limit: usize) -> Vec<String> {
+        match self {
+            Expression::Alternation(options, _) => shortlex(
+                options.iter().flat_map(|it| it.shortest_matches(limit)),
+                limit,
+            ),
+            Expression::AnyChar(_) => vec!["a".to_string()],
+            Expression::CharacterClass(char_set, _) => {
+                shortlex(char_set.iter().map(|c| c.to_string()), limit)
+            }
+            Expression::Concatenation(expr1, expr2, _) => concatenate(
+                &expr1.shortest_matches(limit),
+                &expr2.shortest_matches(limit),
+                limit,
+            ),
+            Expression::Literal(cluster, _) => cluster_matches(cluster, limit),
+            Expression::Repetition(expr, quantifier, _) => {
+                let (min, max) = quantifier.bounds();
+                repeat(&expr.shortest_matches(limit), min, max, limit)
+            }
+            Expression::WordBoundary(_) => vec![String::new()],
+        }
+    }
+}
+
+fn cluster_matches(cluster: &GraphemeCluster, limit: usize) -> Vec<String> {
+    cluster
+        .graphemes()
+        .iter()
+        .fold(vec![String::new()], |matches, grapheme| {
+            concatenate(&matches, &grapheme_matches(grapheme, limit), limit)
+        })
+}
+
+fn grapheme_matches(grapheme: &Grapheme, limit: usize) -> Vec<String> {
+    let matches = if grapheme.has_repetitions() {
+        grapheme
+            .repetitions
+            .iter()
+            .fold(vec![String::new()], |matches, repetition| {
+                concatenate(&matches, &grapheme_matches(repetition, limit), limit)
+            })
+    } else {
+        vec![grapheme.sample_value()]
+    };
+    if grapheme.is_repetition_count_exceeded() {
+        repeat(&matches, 1, None, limit)
+    } else {
+        repeat(
+            &matches,
+            grapheme.minimum(),
+            Some(grapheme.maximum()),
+            limit,
+        )
+    }
+}
+
+fn concatenate(first: &[String], second: &[String], limit: usize) -> Vec<String> {
+    shortlex(
+        first
+            .iter()
+            .cartesian_product(second.iter())
+            .map(|(a, b)| format!("{}{}", a, b)),
+        limit,
+    )
+}
+
+fn repeat(matches: &[String], min: u32, max: Option<u32>, limit: usize) -> Vec<String> {
+    // An unbounded repetition is expanded by at most `limit` additional iterations.
+    let max = max.unwrap_or_else(|| min.saturating_add(limit as u32));
+    let mut power = vec![String::new()];
+    let mut result = vec![];
+
+    for count in 0..=max {
+        if count >= min {
+            result.extend(power.iter().cloned());
+        }
+        if count < max {
+            power = concatenate(&power, matches, limit);
+        }
+    }
+
+    shortlex(result, limit)
+}
+
+fn shortlex<I: IntoIterator<Item = String>>(strings: I, limit: usize) -> Vec<String> {
+    let mut strings = strings.into_iter().collect_vec();
+    strings.sort_by(|a, b| a.chars().count().cmp(&b.chars().count()).then(a.cmp(b)));
+    strings.dedup();
+    strings.truncate(limit);
+    strings
+}
@@ -0,0 +1,125 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::{Expression, Quantifier};
+use crate::char::Grapheme;
+use itertools::Itertools;
+
+impl Expression {
+    /// Describes each construct by which this expression matches more than the test cases,
+    /// such as `introduced '.*' which matches arbitrary text`. Every description is listed
+    /// only once, in the order of appearance.
+    pub(crate) fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        self.collect_warnings(&mut warnings);
+        warnings.into_iter().unique().collect_vec()
+    }
+
+    fn collect_warnings(&self, warnings: &mut Vec<String>) {
+        match self {
+            Expression::Alternation(options, _) => {
+                for option in options {
+                    option.collect_warnings(warnings);
+                }
+            }
+            Expression::AnyChar(_) => {
+                warnings.push("introduced '.' which matches any character".to_string())
+            }
+            Expression::Concatenation(expr1, expr2, _) => {
+                expr1.collect_warnings(warnings);
+                expr2.collect_warnings(warnings);
+            }
+            Expression::Literal(cluster, _) => {
+                for grapheme in cluster.graphemes() {
+                    collect_grapheme_warnings(grapheme, warnings);
+                }
+            }
+            Expression::Repetition(expr, Quantifier::KleeneStar, _)
+                if matches!(**expr, Expression::AnyChar(_)) =>
+            {
+                warnings.push(class_warning(&self.to_string(), "arbitrary text"))
+            }
+            Expression::Repetition(expr, quantifier, _) => {
+                expr.collect_warnings(warnings);
+                if let Some(warning) = quantifier_warning(quantifier, &expr.to_string()) {
+                    warnings.push(warning);
+                }
+            }
+            Expression::CharacterClass(_, _) | Expression::WordBoundary(_) => {}
+        }
+    }
+}
+
+fn collect_grapheme_warnings(grapheme: &Grapheme, warnings: &mut Vec<String>) {
+    let value = if grapheme.has_repetitions() {
+        for repetition in grapheme.repetitions.iter() {
+            collect_grapheme_warnings(repetition, warnings);
+        }
+        grapheme
+            .repetitions
+            .iter()
+            .map(|it| it.to_string())
+            .join("")
+    } else {
+        let value = grapheme.value();
+        if let Some(warning) = shorthand_warning(&value) {
+            warnings.push(warning);
+        }
+        value
+    };
+    let (min, max) = (grapheme.minimum(), grapheme.maximum());
+    let quantifier = if grapheme.is_repetition_count_exceeded() {
+        Quantifier::Plus
+    } else {
+        Quantifier::Range(min, max)
+    };
+    if let Some(warning) = quantifier_warning(&quantifier, &value) {
+        warnings.push(warning);
+    }
+}
+
+/// Describes the shorthand classes created by the conversion features.
+fn shorthand_warning(value: &str) -> Option<String> {
+    let description = match value {
+        "\\d" => "any digit",
+        "\\D" => "any non-digit",
+        "\\s" => "any whitespace character",
+        "\\S" => "any non-whitespace character",
+        "\\w" => "any word character",
+        "\\W" => "any non-word character",
+        _ => return None,
+    };
+    Some(class_warning(value, description))
+}
+
+fn class_warning(value: &str, description: &str) -> String {
+    format!("introduced '{}' which matches {}", value, description)
+}
+
+/// Describes quantifiers which allow more repetitions than a fixed number.
+fn quantifier_warning(quantifier: &Quantifier, value: &str) -> Option<String> {
+    match quantifier {
+        Quantifier::AtLeast(_) | Quantifier::KleeneStar | Quantifier::Plus => Some(format!(
+            "introduced '{}' which repeats '{}' without an upper bound",
+            quantifier, value
+        )),
+        Quantifier::Range(min, max) if min < max => Some(format!(
+            "introduced '{}' which repeats '{}' any number of times from {} to {}",
+            quantifier, value, min, max
+        )),
+        Quantifier::QuestionMark | Quantifier::Range(_, _) => None,
+    }
+}
@@ -14,13 +14,14 @@
  * limitations under the License.
  */
 
-use crate::char::Grapheme;
+use crate::char::{Grapheme, GraphemeKind};
 use crate::regexp::RegExpConfig;
 use crate::unicode_tables::{DECIMAL_NUMBER, WHITE_SPACE, WORD};
 use itertools::Itertools;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::ops::Range;
+use std::rc::Rc;
 use unic_char_range::CharRange;
 use unic_ucd_category::GeneralCategory;
 use unicode_segmentation::UnicodeSegmentation;
@@ -28,11 +29,11 @@ use unicode_segmentation::UnicodeSegmentation;
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct GraphemeCluster {
     graphemes: Vec<Grapheme>,
-    config: RegExpConfig,
+    config: Rc<RegExpConfig>,
 }
 
 impl GraphemeCluster {
-    pub(crate) fn from(s: &str, config: &RegExpConfig) -> Self {
+    pub(crate) fn from(s: &str, config: &Rc<RegExpConfig>) -> Self {
         Self {
             graphemes: UnicodeSegmentation::graphemes(s, true)
                 .flat_map(|it| {
@@ -49,21 +50,21 @@ impl GraphemeCluster {
                     }
                 })
                 .collect_vec(),
-            config: config.clone(),
+            config: Rc::clone(config),
         }
     }
 
-    pub(crate) fn from_graphemes(graphemes: Vec<Grapheme>, config: &RegExpConfig) -> Self {
+    pub(crate) fn from_graphemes(graphemes: Vec<Grapheme>, config: &Rc<RegExpConfig>) -> Self {
         Self {
             graphemes,
-            config: config.clone(),
+            config: Rc::clone(config),
         }
     }
 
-    pub(crate) fn new(grapheme: Grapheme, config: &RegExpConfig) -> Self {
+    pub(crate) fn new(grapheme: Grapheme, config: &Rc<RegExpConfig>) -> Self {
         Self {
             graphemes: vec![grapheme],
-            config: config.clone(),
+            config: Rc::clone(config),
         }
     }
 
@@ -79,7 +80,11 @@ impl GraphemeCluster {
         let valid_alphanumeric_chars = convert_chars_to_range(WORD);
         let valid_space_chars = convert_chars_to_range(WHITE_SPACE);
 
-        for grapheme in self.graphemes.iter_mut() {
+        for grapheme in self
+            .graphemes
+            .iter_mut()
+            .filter(|it| it.kind() == GraphemeKind::Literal)
+        {
             grapheme.chars = grapheme
                 .chars
                 .iter()
@@ -115,25 +120,79 @@ impl GraphemeCluster {
         }
     }
 
+    /// Replaces each run of unescaped wildcards with a single wildcard grapheme.
+    /// A wildcard preceded by a backslash is kept as a literal and the backslash is removed.
+    pub(crate) fn convert_wildcards(&mut self, wildcard: char) {
+        let wildcard = wildcard.to_string();
+        let mut graphemes: Vec<Grapheme> = vec![];
+        let mut is_escaped = false;
+
+        for grapheme in self.graphemes.drain(..) {
+            let value = grapheme.value();
+            if is_escaped {
+                is_escaped = false;
+                if value == wildcard {
+                    graphemes.pop();
+                    graphemes.push(grapheme);
+                    continue;
+                }
+            }
+            if value == wildcard {
+                let is_previous_wildcard =
+                    matches!(graphemes.last(), Some(last) if last.kind() == GraphemeKind::Wildcard);
+                if !is_previous_wildcard {
+                    graphemes.push(Grapheme::new_wildcard(&self.config));
+                }
+            } else {
+                is_escaped = value == "\\";
+                graphemes.push(grapheme);
+            }
+        }
+
+        self.graphemes = graphemes;
+    }
+
+    /// Repeated substrings are searched among the literal graphemes only,
+    /// as runs of wildcards have been collapsed already.
     pub(crate) fn convert_repetitions(&mut self) {
-        let mut repetitions = vec![];
-        convert_repetitions(self.graphemes(), repetitions.as_mut(), &self.config);
-        if !repetitions.is_empty() {
-            self.graphemes = repetitions;
+        let mut graphemes = vec![];
+
+        for (kind, group) in &self.graphemes.iter().group_by(|it| it.kind()) {
+            let group = group.cloned().collect_vec();
+            if kind == GraphemeKind::Literal {
+                let mut repetitions = vec![];
+                convert_repetitions(&group, repetitions.as_mut(), &self.config);
+                graphemes.extend(if repetitions.is_empty() {
+                    group
+                } else {
+                    repetitions
+                });
+            } else {
+                graphemes.extend(group);
+            }
         }
+
+        self.graphemes = graphemes;
     }
 
     pub(crate) fn merge(
         first: &GraphemeCluster,
         second: &GraphemeCluster,
-        config: &RegExpConfig,
+        config: &Rc<RegExpConfig>,
     ) -> Self {
-        let mut graphemes = vec![];
-        graphemes.extend_from_slice(&first.graphemes);
-        graphemes.extend_from_slice(&second.graphemes);
+        Self::merge_all(&[first, second], config)
+    }
+
+    /// Concatenates all clusters in a single pass, which results in the same cluster
+    /// as merging them pairwise from left to right but allocates only once.
+    pub(crate) fn merge_all(clusters: &[&GraphemeCluster], config: &Rc<RegExpConfig>) -> Self {
+        let mut graphemes = Vec::with_capacity(clusters.iter().map(|it| it.size()).sum());
+        for cluster in clusters {
+            graphemes.extend_from_slice(&cluster.graphemes);
+        }
         Self {
             graphemes,
-            config: config.clone(),
+            config: Rc::clone(config),
         }
     }
 
@@ -164,7 +223,7 @@ impl GraphemeCluster {
 fn convert_repetitions(
     graphemes: &[Grapheme],
     repetitions: &mut Vec<Grapheme>,
-    config: &RegExpConfig,
+    config: &Rc<RegExpConfig>,
 ) {
     let repeated_substrings = collect_repeated_substrings(graphemes);
     let ranges_of_repetitions = create_ranges_of_repetitions(repeated_substrings);
@@ -264,7 +323,7 @@ fn replace_graphemes_with_repetitions(
     coalesced_repetitions: Vec<(Range<usize>, Vec<String>)>,
     graphemes: &[Grapheme],
     repetitions: &mut Vec<Grapheme>,
-    config: &RegExpConfig,
+    config: &Rc<RegExpConfig>,
 ) {
     if coalesced_repetitions.is_empty() {
         return;
@@ -324,3 +383,57 @@ fn convert_chars_to_range(chars: &[(char, char)]) -> Vec<CharRange> {
         .map(|&(start, end)| CharRange::closed(start, end))
         .collect_vec()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ensure_merge_preserves_order_of_graphemes() {
+        let config = Rc::new(RegExpConfig::new());
+        let first = GraphemeCluster::from("cab", &config);
+        let second = GraphemeCluster::from("zy", &config);
+        let merged = GraphemeCluster::merge(&first, &second, &config);
+        assert_eq!(
+            merged.graphemes().iter().map(|it| it.value()).collect_vec(),
+            vec!["c", "a", "b", "z", "y"]
+        );
+    }
+
+    #[test]
+    fn ensure_merge_keeps_repeated_graphemes_across_boundary() {
+        let config = Rc::new(RegExpConfig::new());
+        let first = GraphemeCluster::from("ab", &config);
+        let second = GraphemeCluster::from("ba", &config);
+        let merged = GraphemeCluster::merge(&first, &second, &config);
+        assert_eq!(
+            merged.graphemes().iter().map(|it| it.value()).collect_vec(),
+            vec!["a", "b", "b", "a"]
+        );
+        assert_eq!(merged.size(), 4);
+    }
+
+    #[test]
+    fn ensure_merge_all_equals_pairwise_merge() {
+        let config = Rc::new(RegExpConfig::new());
+        let clusters = ["ab", "", "bca", "d", "dd"]
+            .iter()
+            .map(|it| GraphemeCluster::from(it, &config))
+            .collect_vec();
+        let pairwise = clusters
+            .iter()
+            .skip(1)
+            .fold(clusters[0].clone(), |merged, it| {
+                GraphemeCluster::merge(&merged, it, &config)
+            });
+        let merged = GraphemeCluster::merge_all(&clusters.iter().collect_vec(), &config);
+        assert_eq!(merged, pairwise);
+        assert_eq!(merged.size(), 8);
+    }
+
+    #[test]
+    fn ensure_merge_all_of_no_clusters_is_empty() {
+        let config = Rc::new(RegExpConfig::new());
+        assert!(GraphemeCluster::merge_all(&[], &config).is_empty());
+    }
+}
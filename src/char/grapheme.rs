@@ -14,8 +14,10 @@
  * limitations under the License.
  */
 
+use crate::ast::Quantifier;
 use crate::regexp::{Component, RegExpConfig};
 use itertools::Itertools;
+use std::collections::BTreeSet;
 use std::fmt::{Display, Formatter, Result};
 
 const CHARS_TO_ESCAPE: [&str; 14] = [
@@ -24,12 +26,32 @@ const CHARS_TO_ESCAPE: [&str; 14] = [
 
 const CHAR_CLASSES: [&str; 6] = ["\\d", "\\s", "\\w", "\\D", "\\S", "\\W"];
 
+const CHAR_CLASS_SAMPLES: [&str; 6] = ["0", " ", "a", "a", "a", " "];
+
+const POSIX_CHAR_CLASSES: [&str; 6] = [
+    "[[:digit:]]",
+    "[[:space:]]",
+    "[[:alnum:]_]",
+    "[^[:digit:]]",
+    "[^[:space:]]",
+    "[^[:alnum:]_]",
+];
+
+/// Distinguishes graphemes standing for a wildcard
+/// from graphemes holding the characters of the test cases.
+#[derive(Clone, Copy, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum GraphemeKind {
+    Literal,
+    Wildcard,
+}
+
 #[derive(Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
 pub struct Grapheme {
     pub(crate) chars: Vec<String>,
     pub(crate) repetitions: Vec<Grapheme>,
     min: u32,
     max: u32,
+    kind: GraphemeKind,
     config: RegExpConfig,
 }
 
@@ -40,6 +62,7 @@ impl Grapheme {
             repetitions: vec![],
             min: 1,
             max: 1,
+            kind: GraphemeKind::Literal,
             config: config.clone(),
         }
     }
@@ -50,14 +73,59 @@ impl Grapheme {
             repetitions: vec![],
             min,
             max,
+            kind: GraphemeKind::Literal,
             config: config.clone(),
         }
     }
 
+    /// Creates a grapheme in place of a wildcard within a test case. It has no characters
+    /// because it is turned into an expression of its own which matches any run of them.
+    pub(crate) fn new_wildcard(config: &RegExpConfig) -> Self {
+        Self {
+            kind: GraphemeKind::Wildcard,
+            ..Self::new(vec![], 1, 1, config)
+        }
+    }
+
+    pub(crate) fn kind(&self) -> GraphemeKind {
+        self.kind
+    }
+
     pub(crate) fn value(&self) -> String {
         self.chars.join("")
     }
 
+    pub(crate) fn sample_value(&self) -> String {
+        self.chars
+            .iter()
+            .map(
+                |it| match CHAR_CLASSES.iter().position(|class| class == it) {
+                    Some(idx) => CHAR_CLASS_SAMPLES[idx],
+                    None => it,
+                },
+            )
+            .join("")
+    }
+
+    pub(crate) fn is_char_class(&self) -> bool {
+        !self.has_repetitions() && matches!(self.chars.as_slice(), [c] if is_char_class_value(c))
+    }
+
+    pub(crate) fn is_word_class(&self) -> bool {
+        !self.has_repetitions() && matches!(self.chars.as_slice(), [c] if c == "\\w" || c == "\\d")
+    }
+
+    pub(crate) fn is_non_word_char(&self) -> bool {
+        if self.has_repetitions() || self.chars.len() != 1 {
+            return false;
+        }
+        let mut chars = self.chars[0].chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => !(c.is_alphanumeric() || c == '_'),
+            _ => false,
+        }
+    }
+
     pub(crate) fn chars(&self) -> &Vec<String> {
         &self.chars
     }
@@ -82,6 +150,29 @@ impl Grapheme {
         self.max
     }
 
+    /// Returns true if the maximum count of this repetition exceeds the configured limit,
+    /// in which case it is written as `+` instead of as a counted repetition.
+    pub(crate) fn is_repetition_count_exceeded(&self) -> bool {
+        self.config.is_repetition_count_exceeded(self.max)
+    }
+
+    /// Returns the minimum and the maximum number of repetitions as they are written,
+    /// the latter being `None` if the repetition is written as `+`.
+    pub(crate) fn bounds(&self) -> (u32, Option<u32>) {
+        if self.is_repetition_count_exceeded() {
+            (1, None)
+        } else {
+            (self.min, Some(self.max))
+        }
+    }
+
+    /// Returns true if every string matched by this grapheme has the same length,
+    /// which is the case if neither it nor any of its nested repetitions is a range.
+    pub(crate) fn is_fixed_length(&self) -> bool {
+        let (min, max) = self.bounds();
+        max == Some(min) && self.repetitions.iter().all(|it| it.is_fixed_length())
+    }
+
     pub(crate) fn char_count(&self, is_non_ascii_char_escaped: bool) -> usize {
         if is_non_ascii_char_escaped {
             self.chars
@@ -112,26 +203,55 @@ impl Grapheme {
         is_non_ascii_char_escaped: bool,
         is_astral_code_point_converted_to_surrogate: bool,
     ) {
+        if self.kind != GraphemeKind::Literal {
+            return;
+        }
+
+        let is_posix_char_class_used = self.config.is_posix_char_class_used;
+        let is_control_char_hex_escaped = self.config.is_control_char_hex_escaped;
+        let is_case_insensitivity_expanded = self.config.is_case_insensitivity_expanded();
         let characters = self.chars_mut();
 
         #[allow(clippy::needless_range_loop)]
         for i in 0..characters.len() {
             let mut character = characters[i].clone();
 
+            // Graphemes containing cased letters consist of letters and combining marks only,
+            // so none of their characters needs to be escaped.
+            if is_case_insensitivity_expanded
+                && !CHAR_CLASSES.contains(&&*character)
+                && character.chars().any(|c| opposite_case(c).is_some())
+            {
+                characters[i] = character
+                    .chars()
+                    .map(|c| match opposite_case(c) {
+                        Some(other) => format!("[{}{}]", c.min(other), c.max(other)),
+                        None => c.to_string(),
+                    })
+                    .collect();
+                continue;
+            }
+
             for char_to_escape in CHARS_TO_ESCAPE.iter() {
                 character =
                     character.replace(char_to_escape, &format!("{}{}", "\\", char_to_escape));
             }
 
             character = character
-                .replace("\n", "\\n")
-                .replace("\r", "\\r")
-                .replace("\t", "\\t");
+                .chars()
+                .map(|c| escape_control_char(c, is_control_char_hex_escaped))
+                .collect();
 
             if character == "\\" {
                 character = "\\\\".to_string();
             }
 
+            if is_posix_char_class_used {
+                if let Some(idx) = CHAR_CLASSES.iter().position(|&it| it == character) {
+                    character = POSIX_CHAR_CLASSES[idx].to_string();
+                }
+            }
+
             characters[i] = character;
         }
 
@@ -140,6 +260,38 @@ impl Grapheme {
         }
     }
 
+    /// Whether this grapheme can be written within a `\Q...\E` span given its escaped form,
+    /// which is the case if both differ in escaped metacharacters only. This rules out
+    /// repetitions, shorthand classes, control characters and escaped non-ASCII characters.
+    pub(crate) fn is_quotable(&self, escaped: &Grapheme) -> bool {
+        let expected_value = self
+            .value()
+            .chars()
+            .map(|c| {
+                let c = c.to_string();
+                if c == "\\" || CHARS_TO_ESCAPE.contains(&&*c) {
+                    format!("\\{}", c)
+                } else {
+                    c
+                }
+            })
+            .join("");
+        !self.has_repetitions()
+            && self.min == 1
+            && self.max == 1
+            && escaped.value() == expected_value
+    }
+
+    /// Whether this grapheme has been expanded into a class such as `[Hh]`
+    /// by case-insensitive matching without the inline flag.
+    fn is_case_class(&self) -> bool {
+        let value = &self.chars[0];
+        self.config.is_case_insensitivity_expanded()
+            && value.starts_with('[')
+            && value.ends_with(']')
+            && value.matches('[').count() == 1
+    }
+
     fn escape(&self, c: char, use_surrogate_pairs: bool) -> String {
         if c.is_ascii() {
             c.to_string()
@@ -158,10 +310,66 @@ impl Grapheme {
     }
 }
 
+/// Returns true if `value` is a shorthand or POSIX character class
+/// rather than a literal character.
+pub fn is_char_class_value(value: &str) -> bool {
+    CHAR_CLASSES.contains(&value) || POSIX_CHAR_CLASSES.contains(&value)
+}
+
+/// Returns the ranges of the canonical class of hexadecimal digits, such as `[0-9a-f]`,
+/// if the set consists of all decimal digits and all letters from `a` to `f`
+/// in lower case, upper case or both.
+pub fn hex_digit_ranges(char_set: &BTreeSet<char>) -> Option<&'static [(char, char)]> {
+    let lowercase_letters = char_set.range('a'..='f').count();
+    let uppercase_letters = char_set.range('A'..='F').count();
+    if char_set.range('0'..='9').count() != 10
+        || char_set.len() != 10 + lowercase_letters + uppercase_letters
+    {
+        return None;
+    }
+    match (lowercase_letters, uppercase_letters) {
+        (6, 0) => Some(&[('0', '9'), ('a', 'f')]),
+        (0, 6) => Some(&[('0', '9'), ('A', 'F')]),
+        (6, 6) => Some(&[('0', '9'), ('a', 'f'), ('A', 'F')]),
+        _ => None,
+    }
+}
+
+/// Returns the counterpart of a cased letter in the opposite case,
+/// provided that it consists of a single character.
+pub fn opposite_case(c: char) -> Option<char> {
+    let other_case = if c.is_lowercase() {
+        c.to_uppercase().collect_vec()
+    } else {
+        c.to_lowercase().collect_vec()
+    };
+    match other_case[..] {
+        [other] if other != c => Some(other),
+        _ => None,
+    }
+}
+
+/// Escapes the ASCII control characters which would otherwise be inserted raw into
+/// the regular expression. Tabs, line feeds, carriage returns and line tabulations are
+/// written as `\t`, `\n`, `\r` and `\v` unless hexadecimal escapes such as `\x09`
+/// are requested.
+pub fn escape_control_char(c: char, is_control_char_hex_escaped: bool) -> String {
+    match c {
+        '\t' if !is_control_char_hex_escaped => "\\t".to_string(),
+        '\n' if !is_control_char_hex_escaped => "\\n".to_string(),
+        '\r' if !is_control_char_hex_escaped => "\\r".to_string(),
+        '\u{b}' if !is_control_char_hex_escaped => "\\v".to_string(),
+        c if c.is_ascii_control() => format!("\\x{:02X}", c as u32),
+        c => c.to_string(),
+    }
+}
+
 impl Display for Grapheme {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         let is_single_char = self.char_count(false) == 1
-            || (self.chars.len() == 1 && self.chars[0].matches('\\').count() == 1);
+            || (self.chars.len() == 1 && self.chars[0].matches('\\').count() == 1)
+            || (self.chars.len() == 1 && POSIX_CHAR_CLASSES.contains(&&*self.chars[0]))
+            || (self.chars.len() == 1 && self.is_case_class());
         let is_range = self.min < self.max;
         let is_repetition = self.min > 1;
         let mut value = if self.repetitions.is_empty() {
@@ -169,53 +377,40 @@ impl Display for Grapheme {
         } else {
             self.repetitions.iter().map(|it| it.to_string()).join("")
         };
-        value = Component::CharClass(value.clone())
-            .to_repr(self.config.is_output_colorized && CHAR_CLASSES.contains(&&*value));
-
-        if !is_range && is_repetition && is_single_char {
-            write!(
-                f,
-                "{}{}",
-                value,
-                Component::Repetition(self.min).to_repr(self.config.is_output_colorized)
-            )
-        } else if !is_range && is_repetition && !is_single_char {
-            write!(
-                f,
-                "{}{}",
-                if self.config.is_capturing_group_enabled() {
-                    Component::CapturedParenthesizedExpression(value)
-                        .to_repr(self.config.is_output_colorized)
-                } else {
-                    Component::UncapturedParenthesizedExpression(value)
-                        .to_repr(self.config.is_output_colorized)
-                },
-                Component::Repetition(self.min).to_repr(self.config.is_output_colorized)
-            )
-        } else if is_range && is_single_char {
-            write!(
-                f,
-                "{}{}",
-                value,
-                Component::RepetitionRange(self.min, self.max)
-                    .to_repr(self.config.is_output_colorized)
-            )
-        } else if is_range && !is_single_char {
-            write!(
-                f,
-                "{}{}",
-                if self.config.is_capturing_group_enabled() {
-                    Component::CapturedParenthesizedExpression(value)
-                        .to_repr(self.config.is_output_colorized)
-                } else {
-                    Component::UncapturedParenthesizedExpression(value)
-                        .to_repr(self.config.is_output_colorized)
-                },
-                Component::RepetitionRange(self.min, self.max)
-                    .to_repr(self.config.is_output_colorized)
-            )
-        } else {
-            write!(f, "{}", value)
+        value = Component::CharClass(value.clone()).to_repr(
+            self.config.is_output_colorized
+                && (CHAR_CLASSES.contains(&&*value) || POSIX_CHAR_CLASSES.contains(&&*value)),
+        );
+
+        if !is_range && !is_repetition {
+            return write!(f, "{}", value);
         }
+
+        let quantifier = if self.is_repetition_count_exceeded() {
+            let quantifier = if self.config.is_quantifier_braced {
+                Quantifier::Plus.to_braced()
+            } else {
+                Quantifier::Plus
+            };
+            Component::Quantifier(quantifier)
+        } else if is_range {
+            Component::RepetitionRange(self.min, self.max)
+        } else {
+            Component::Repetition(self.min)
+        };
+
+        let value = if is_single_char {
+            value
+        } else {
+            let is_fixed_length = self.repetitions.iter().all(|it| it.is_fixed_length());
+            Component::group(value, is_fixed_length, &self.config)
+        };
+
+        write!(
+            f,
+            "{}{}",
+            value,
+            quantifier.to_repr(self.config.is_output_colorized)
+        )
     }
 }
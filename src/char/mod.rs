@@ -18,4 +18,7 @@ mod cluster;
 mod grapheme;
 
 pub use cluster::GraphemeCluster;
-pub use grapheme::Grapheme;
+pub use grapheme::{
+    escape_control_char, hex_digit_ranges, is_char_class_value, opposite_case, Grapheme,
+    GraphemeKind,
+};
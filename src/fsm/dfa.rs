@@ -18,30 +18,48 @@ use crate::char::{Grapheme, GraphemeCluster};
 use crate::regexp::RegExpConfig;
 use itertools::Itertools;
 use petgraph::graph::NodeIndex;
+use petgraph::prelude::EdgeRef;
 use petgraph::stable_graph::{Edges, StableGraph};
 use petgraph::visit::Dfs;
 use petgraph::{Directed, Direction};
 use std::cmp::{max, min};
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
 
 type State = NodeIndex<u32>;
 type StateLabel = String;
 type EdgeLabel = Grapheme;
 
+#[derive(Clone)]
 pub struct Dfa {
     alphabet: BTreeSet<Grapheme>,
     graph: StableGraph<StateLabel, EdgeLabel>,
     initial_state: State,
     final_state_indices: HashSet<usize>,
-    config: RegExpConfig,
+    config: Rc<RegExpConfig>,
 }
 
 impl Dfa {
-    pub(crate) fn from(grapheme_clusters: Vec<GraphemeCluster>, config: &RegExpConfig) -> Self {
-        let mut dfa = Self::new(config);
-        for cluster in grapheme_clusters {
-            dfa.insert(cluster);
+    pub(crate) fn from(grapheme_clusters: Vec<GraphemeCluster>, config: &Rc<RegExpConfig>) -> Self {
+        let mut dfa = Self::new_trie(grapheme_clusters, config);
+        dfa.minimize();
+        dfa
+    }
+
+    /// Creates an automaton accepting the grapheme clusters, or their prefixes and substrings
+    /// depending on the settings, except for the excluded clusters which are taken literally.
+    pub(crate) fn from_difference(
+        grapheme_clusters: Vec<GraphemeCluster>,
+        excluded_grapheme_clusters: Vec<GraphemeCluster>,
+        config: &Rc<RegExpConfig>,
+    ) -> Self {
+        let mut excluded_dfa = Self::new(config);
+        for cluster in excluded_grapheme_clusters {
+            excluded_dfa.insert_cluster(cluster, false);
         }
+        // The difference is computed before the minimization because the
+        // automaton is still a tree then, which the minimization relies on.
+        let mut dfa = Self::new_trie(grapheme_clusters, config).difference(&excluded_dfa);
         dfa.minimize();
         dfa
     }
@@ -59,7 +77,7 @@ impl Dfa {
         states
     }
 
-    pub(crate) fn outgoing_edges(&self, state: State) -> Edges<Grapheme, Directed> {
+    pub(crate) fn outgoing_edges(&self, state: State) -> Edges<'_, Grapheme, Directed> {
         self.graph.edges_directed(state, Direction::Outgoing)
     }
 
@@ -67,7 +85,70 @@ impl Dfa {
         self.final_state_indices.contains(&state.index())
     }
 
-    fn new(config: &RegExpConfig) -> Self {
+    /// Returns true if both automata accept the same strings. The pairs of states reached
+    /// by the same strings are explored in parallel, with a missing transition leading into
+    /// an implicit sink state, until a pair is found of which only one state is final.
+    /// Such a pair is reached by a string in the symmetric difference of both languages.
+    pub(crate) fn is_equivalent(&self, other: &Dfa) -> bool {
+        let initial_pair = (Some(self.initial_state), Some(other.initial_state));
+        let mut visited_pairs = HashSet::from([initial_pair]);
+        let mut pairs = VecDeque::from([initial_pair]);
+
+        while let Some((first_state, second_state)) = pairs.pop_front() {
+            let is_first_state_final = first_state.is_some_and(|it| self.is_final_state(it));
+            let is_second_state_final = second_state.is_some_and(|it| other.is_final_state(it));
+            if is_first_state_final != is_second_state_final {
+                return false;
+            }
+
+            let labels = first_state
+                .into_iter()
+                .flat_map(|state| self.outgoing_edges(state))
+                .chain(
+                    second_state
+                        .into_iter()
+                        .flat_map(|state| other.outgoing_edges(state)),
+                )
+                .map(|edge| edge.weight())
+                .collect::<BTreeSet<&Grapheme>>();
+
+            for label in labels {
+                let pair = (
+                    first_state.and_then(|state| self.target_state(state, label)),
+                    second_state.and_then(|state| other.target_state(state, label)),
+                );
+                if visited_pairs.insert(pair) {
+                    pairs.push_back(pair);
+                }
+            }
+        }
+
+        true
+    }
+
+    fn target_state(&self, state: State, label: &Grapheme) -> Option<State> {
+        self.outgoing_edges(state)
+            .find(|edge| edge.weight() == label)
+            .map(|edge| edge.target())
+    }
+
+    fn new_trie(grapheme_clusters: Vec<GraphemeCluster>, config: &Rc<RegExpConfig>) -> Self {
+        let mut dfa = Self::new(config);
+        for cluster in grapheme_clusters {
+            if config.is_substring_matched {
+                // Inserting every suffix with all of its prefixes being accepted
+                // makes the automaton accept every substring of the cluster.
+                for i in 1..cluster.size() {
+                    let suffix = cluster.graphemes()[i..].to_vec();
+                    dfa.insert(GraphemeCluster::from_graphemes(suffix, config));
+                }
+            }
+            dfa.insert(cluster);
+        }
+        dfa
+    }
+
+    fn new(config: &Rc<RegExpConfig>) -> Self {
         let mut graph = StableGraph::new();
         let initial_state = graph.add_node("".to_string());
         Self {
@@ -80,15 +161,132 @@ impl Dfa {
     }
 
     fn insert(&mut self, cluster: GraphemeCluster) {
+        let are_prefixes_accepted =
+            self.config.is_prefix_matched || self.config.is_substring_matched;
+        self.insert_cluster(cluster, are_prefixes_accepted);
+    }
+
+    fn insert_cluster(&mut self, cluster: GraphemeCluster, are_prefixes_accepted: bool) {
         let mut current_state = self.initial_state;
 
         for grapheme in cluster.graphemes() {
             self.alphabet.insert(grapheme.clone());
             current_state = self.get_next_state(current_state, grapheme);
+            if are_prefixes_accepted {
+                self.final_state_indices.insert(current_state.index());
+            }
         }
         self.final_state_indices.insert(current_state.index());
     }
 
+    /// Returns an automaton accepting the strings which this automaton
+    /// accepts but the other one does not.
+    fn difference(&self, other: &Dfa) -> Self {
+        self.intersection(&other.complement(&self.alphabet))
+    }
+
+    /// Returns an automaton accepting all strings over the union of its own alphabet and
+    /// the given one which this automaton does not accept. Missing transitions are
+    /// completed by leading into a non-final sink state which is final in the complement.
+    fn complement(&self, alphabet: &BTreeSet<Grapheme>) -> Self {
+        let mut dfa = self.clone();
+        dfa.alphabet.extend(alphabet.iter().cloned());
+
+        let sink_state = dfa.graph.add_node("".to_string());
+        let states = dfa.graph.node_indices().collect_vec();
+
+        for state in states {
+            let labels = dfa
+                .outgoing_edges(state)
+                .map(|edge| edge.weight().clone())
+                .collect::<BTreeSet<Grapheme>>();
+            for label in dfa.alphabet.difference(&labels).cloned().collect_vec() {
+                dfa.graph.add_edge(state, sink_state, label);
+            }
+        }
+
+        dfa.final_state_indices = dfa
+            .graph
+            .node_indices()
+            .map(|state| state.index())
+            .filter(|idx| !self.final_state_indices.contains(idx))
+            .collect();
+        dfa
+    }
+
+    /// Returns an automaton accepting the strings which both automata accept,
+    /// built by the product construction from the pairs of reachable states.
+    fn intersection(&self, other: &Dfa) -> Self {
+        let mut dfa = Self::new(&self.config);
+        dfa.alphabet = self
+            .alphabet
+            .intersection(&other.alphabet)
+            .cloned()
+            .collect();
+
+        let initial_pair = (self.initial_state, other.initial_state);
+        let mut state_mappings = HashMap::new();
+        let mut pairs = VecDeque::new();
+
+        state_mappings.insert(initial_pair, dfa.initial_state);
+        pairs.push_back(initial_pair);
+
+        while let Some((first_state, second_state)) = pairs.pop_front() {
+            let source_state = state_mappings[&(first_state, second_state)];
+
+            if self.is_final_state(first_state) && other.is_final_state(second_state) {
+                dfa.final_state_indices.insert(source_state.index());
+            }
+
+            for first_edge in self.outgoing_edges(first_state) {
+                let second_edge = other
+                    .outgoing_edges(second_state)
+                    .find(|edge| edge.weight() == first_edge.weight());
+
+                if let Some(second_edge) = second_edge {
+                    let pair = (first_edge.target(), second_edge.target());
+                    let target_state = *state_mappings.entry(pair).or_insert_with(|| {
+                        pairs.push_back(pair);
+                        dfa.graph.add_node("".to_string())
+                    });
+                    dfa.graph
+                        .add_edge(source_state, target_state, first_edge.weight().clone());
+                }
+            }
+        }
+
+        dfa.remove_dead_states();
+        dfa
+    }
+
+    /// Removes all states apart from the initial one from which no final state can be reached.
+    fn remove_dead_states(&mut self) {
+        let mut live_states = self
+            .graph
+            .node_indices()
+            .filter(|state| self.is_final_state(*state))
+            .collect::<HashSet<State>>();
+        let mut states = live_states.iter().copied().collect_vec();
+
+        while let Some(state) = states.pop() {
+            for parent_state in self.graph.neighbors_directed(state, Direction::Incoming) {
+                if live_states.insert(parent_state) {
+                    states.push(parent_state);
+                }
+            }
+        }
+
+        let dead_states = self
+            .graph
+            .node_indices()
+            .filter(|state| *state != self.initial_state && !live_states.contains(state))
+            .collect_vec();
+
+        for state in dead_states {
+            self.graph.remove_node(state);
+        }
+    }
+
     fn get_next_state(&mut self, current_state: State, edge_label: &Grapheme) -> State {
         match self.find_next_state(current_state, edge_label) {
             Some(next_state) => next_state,
@@ -227,6 +425,9 @@ impl Dfa {
                 if self.initial_state == *old_state {
                     new_initial_state = Some(new_state);
                 }
+                if self.final_state_indices.contains(&old_state.index()) {
+                    final_state_indices.insert(new_state.index());
+                }
                 state_mappings.insert(*old_state, new_state);
             }
         }
@@ -245,10 +446,6 @@ impl Dfa {
                 let new_target_state = state_mappings.get(&old_target_state).unwrap();
 
                 graph.add_edge(*new_source_state, *new_target_state, grapheme.clone());
-
-                if self.final_state_indices.contains(&old_target_state.index()) {
-                    final_state_indices.insert(new_target_state.index());
-                }
             }
         }
         self.initial_state = new_initial_state.unwrap();
@@ -261,38 +458,67 @@ impl Dfa {
 mod tests {
     use super::*;
 
+    impl Dfa {
+        fn accepts(&self, s: &str) -> bool {
+            let mut state = self.initial_state;
+            for grapheme in GraphemeCluster::from(s, &self.config).graphemes() {
+                match self
+                    .outgoing_edges(state)
+                    .find(|edge| edge.weight() == grapheme)
+                {
+                    Some(edge) => state = edge.target(),
+                    None => return false,
+                }
+            }
+            self.is_final_state(state)
+        }
+    }
+
     #[test]
     fn test_state_count() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let mut dfa = Dfa::new(&config);
         assert_eq!(dfa.state_count(), 1);
 
-        dfa.insert(GraphemeCluster::from("abcd", &RegExpConfig::new()));
+        dfa.insert(GraphemeCluster::from("abcd", &Rc::new(RegExpConfig::new())));
         assert_eq!(dfa.state_count(), 5);
     }
 
     #[test]
     fn test_is_final_state() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let dfa = Dfa::from(
-            vec![GraphemeCluster::from("abcd", &RegExpConfig::new())],
+            vec![GraphemeCluster::from("abcd", &Rc::new(RegExpConfig::new()))],
             &config,
         );
 
         let intermediate_state = State::new(3);
-        assert_eq!(dfa.is_final_state(intermediate_state), false);
+        assert!(!dfa.is_final_state(intermediate_state));
 
         let final_state = State::new(4);
-        assert_eq!(dfa.is_final_state(final_state), true);
+        assert!(dfa.is_final_state(final_state));
+    }
+
+    #[test]
+    fn test_initial_state_remains_final_after_minimization() {
+        let config = Rc::new(RegExpConfig::new());
+        let dfa = Dfa::from(
+            vec![
+                GraphemeCluster::from("", &Rc::new(RegExpConfig::new())),
+                GraphemeCluster::from("abc", &Rc::new(RegExpConfig::new())),
+            ],
+            &config,
+        );
+        assert!(dfa.is_final_state(dfa.initial_state));
     }
 
     #[test]
     fn test_outgoing_edges() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let dfa = Dfa::from(
             vec![
-                GraphemeCluster::from("abcd", &RegExpConfig::new()),
-                GraphemeCluster::from("abxd", &RegExpConfig::new()),
+                GraphemeCluster::from("abcd", &Rc::new(RegExpConfig::new())),
+                GraphemeCluster::from("abxd", &Rc::new(RegExpConfig::new())),
             ],
             &config,
         );
@@ -303,14 +529,14 @@ mod tests {
         assert!(first_edge.is_some());
         assert_eq!(
             first_edge.unwrap().weight(),
-            &Grapheme::from("c", &RegExpConfig::new())
+            &Grapheme::from("c", &Rc::new(RegExpConfig::new()))
         );
 
         let second_edge = edges.next();
         assert!(second_edge.is_some());
         assert_eq!(
             second_edge.unwrap().weight(),
-            &Grapheme::from("x", &RegExpConfig::new())
+            &Grapheme::from("x", &Rc::new(RegExpConfig::new()))
         );
 
         let third_edge = edges.next();
@@ -319,22 +545,22 @@ mod tests {
 
     #[test]
     fn test_states_in_depth_first_order() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let dfa = Dfa::from(
             vec![
-                GraphemeCluster::from("abcd", &RegExpConfig::new()),
-                GraphemeCluster::from("axyz", &RegExpConfig::new()),
+                GraphemeCluster::from("abcd", &Rc::new(RegExpConfig::new())),
+                GraphemeCluster::from("axyz", &Rc::new(RegExpConfig::new())),
             ],
             &config,
         );
         let states = dfa.states_in_depth_first_order();
         assert_eq!(states.len(), 7);
 
-        let first_state = states.get(0).unwrap();
+        let first_state = states.first().unwrap();
         let mut edges = dfa.outgoing_edges(*first_state);
         assert_eq!(
             edges.next().unwrap().weight(),
-            &Grapheme::from("a", &RegExpConfig::new())
+            &Grapheme::from("a", &Rc::new(RegExpConfig::new()))
         );
         assert!(edges.next().is_none());
 
@@ -342,11 +568,11 @@ mod tests {
         edges = dfa.outgoing_edges(*second_state);
         assert_eq!(
             edges.next().unwrap().weight(),
-            &Grapheme::from("b", &RegExpConfig::new())
+            &Grapheme::from("b", &Rc::new(RegExpConfig::new()))
         );
         assert_eq!(
             edges.next().unwrap().weight(),
-            &Grapheme::from("x", &RegExpConfig::new())
+            &Grapheme::from("x", &Rc::new(RegExpConfig::new()))
         );
         assert!(edges.next().is_none());
 
@@ -354,7 +580,7 @@ mod tests {
         edges = dfa.outgoing_edges(*third_state);
         assert_eq!(
             edges.next().unwrap().weight(),
-            &Grapheme::from("y", &RegExpConfig::new())
+            &Grapheme::from("y", &Rc::new(RegExpConfig::new()))
         );
         assert!(edges.next().is_none());
 
@@ -362,7 +588,7 @@ mod tests {
         edges = dfa.outgoing_edges(*fourth_state);
         assert_eq!(
             edges.next().unwrap().weight(),
-            &Grapheme::from("z", &RegExpConfig::new())
+            &Grapheme::from("z", &Rc::new(RegExpConfig::new()))
         );
         assert!(edges.next().is_none());
 
@@ -374,7 +600,7 @@ mod tests {
         edges = dfa.outgoing_edges(*sixth_state);
         assert_eq!(
             edges.next().unwrap().weight(),
-            &Grapheme::from("c", &RegExpConfig::new())
+            &Grapheme::from("c", &Rc::new(RegExpConfig::new()))
         );
         assert!(edges.next().is_none());
 
@@ -382,23 +608,23 @@ mod tests {
         edges = dfa.outgoing_edges(*seventh_state);
         assert_eq!(
             edges.next().unwrap().weight(),
-            &Grapheme::from("d", &RegExpConfig::new())
+            &Grapheme::from("d", &Rc::new(RegExpConfig::new()))
         );
         assert!(edges.next().is_none());
     }
 
     #[test]
     fn test_minimization_algorithm() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let mut dfa = Dfa::new(&config);
         assert_eq!(dfa.graph.node_count(), 1);
         assert_eq!(dfa.graph.edge_count(), 0);
 
-        dfa.insert(GraphemeCluster::from("abcd", &RegExpConfig::new()));
+        dfa.insert(GraphemeCluster::from("abcd", &Rc::new(RegExpConfig::new())));
         assert_eq!(dfa.graph.node_count(), 5);
         assert_eq!(dfa.graph.edge_count(), 4);
 
-        dfa.insert(GraphemeCluster::from("abxd", &RegExpConfig::new()));
+        dfa.insert(GraphemeCluster::from("abxd", &Rc::new(RegExpConfig::new())));
         assert_eq!(dfa.graph.node_count(), 7);
         assert_eq!(dfa.graph.edge_count(), 6);
 
@@ -407,13 +633,107 @@ mod tests {
         assert_eq!(dfa.graph.edge_count(), 5);
     }
 
+    #[test]
+    fn test_complement() {
+        let config = Rc::new(RegExpConfig::new());
+        let dfa = Dfa::new_trie(vec![GraphemeCluster::from("ab", &config)], &config);
+        let complement = dfa.complement(&dfa.alphabet);
+
+        assert_eq!(complement.state_count(), dfa.state_count() + 1);
+        assert!(!complement.accepts("ab"));
+        assert!(complement.accepts(""));
+        assert!(complement.accepts("a"));
+        assert!(complement.accepts("ba"));
+        assert!(complement.accepts("abab"));
+    }
+
+    #[test]
+    fn test_intersection() {
+        let config = Rc::new(RegExpConfig::new());
+        let first_dfa = Dfa::new_trie(
+            vec![
+                GraphemeCluster::from("ab", &config),
+                GraphemeCluster::from("cd", &config),
+            ],
+            &config,
+        );
+        let second_dfa = Dfa::new_trie(
+            vec![
+                GraphemeCluster::from("cd", &config),
+                GraphemeCluster::from("ef", &config),
+            ],
+            &config,
+        );
+        let intersection = first_dfa.intersection(&second_dfa);
+
+        assert_eq!(intersection.state_count(), 3);
+        assert!(intersection.accepts("cd"));
+        assert!(!intersection.accepts("ab"));
+        assert!(!intersection.accepts("ef"));
+    }
+
+    #[test]
+    fn test_dfa_from_difference() {
+        let mut config = RegExpConfig::new();
+        config.is_prefix_matched = true;
+        let config = Rc::new(config);
+        let dfa = Dfa::from_difference(
+            vec![GraphemeCluster::from("abc", &config)],
+            vec![
+                GraphemeCluster::from("ab", &config),
+                GraphemeCluster::from("xyz", &config),
+            ],
+            &config,
+        );
+
+        assert!(dfa.accepts("a"));
+        assert!(!dfa.accepts("ab"));
+        assert!(dfa.accepts("abc"));
+        assert!(!dfa.accepts("xyz"));
+    }
+
+    #[test]
+    fn test_is_equivalent() {
+        let config = Rc::new(RegExpConfig::new());
+        let dfa = |test_cases: &[&str]| {
+            Dfa::from(
+                test_cases
+                    .iter()
+                    .map(|it| GraphemeCluster::from(it, &config))
+                    .collect_vec(),
+                &config,
+            )
+        };
+
+        assert!(dfa(&["ab", "cd"]).is_equivalent(&dfa(&["cd", "ab", "ab"])));
+        assert!(dfa(&["", "a"]).is_equivalent(&dfa(&["a", ""])));
+        assert!(!dfa(&["ab", "cd"]).is_equivalent(&dfa(&["ab", "ce"])));
+        assert!(!dfa(&["ab"]).is_equivalent(&dfa(&["ab", "abc"])));
+        assert!(!dfa(&["ab", ""]).is_equivalent(&dfa(&["ab"])));
+    }
+
+    #[test]
+    fn test_is_equivalent_regardless_of_minimization() {
+        let config = Rc::new(RegExpConfig::new());
+        let test_cases = vec![
+            GraphemeCluster::from("abcd", &config),
+            GraphemeCluster::from("abxd", &config),
+        ];
+        let trie = Dfa::new_trie(test_cases.clone(), &config);
+        let dfa = Dfa::from(test_cases, &config);
+
+        assert!(trie.state_count() > dfa.state_count());
+        assert!(trie.is_equivalent(&dfa));
+        assert!(dfa.is_equivalent(&trie));
+    }
+
     #[test]
     fn test_dfa_constructor() {
-        let config = RegExpConfig::new();
+        let config = Rc::new(RegExpConfig::new());
         let dfa = Dfa::from(
             vec![
-                GraphemeCluster::from("abcd", &RegExpConfig::new()),
-                GraphemeCluster::from("abxd", &RegExpConfig::new()),
+                GraphemeCluster::from("abcd", &Rc::new(RegExpConfig::new())),
+                GraphemeCluster::from("abxd", &Rc::new(RegExpConfig::new())),
             ],
             &config,
         );
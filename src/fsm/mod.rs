@@ -15,5 +15,8 @@
  */
 
 mod dfa;
+mod nfa;
 
 pub use dfa::Dfa;
+pub(crate) use nfa::State;
+pub use nfa::{Nfa, Transition};
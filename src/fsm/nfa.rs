@@ -0,0 +1,198 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::char::opposite_case;
+use crate::unicode_tables::{DECIMAL_NUMBER, WHITE_SPACE, WORD};
+use petgraph::graph::NodeIndex;
+use petgraph::stable_graph::StableGraph;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use petgraph::Direction;
+use std::collections::{BTreeSet, HashSet};
+
+pub(crate) type State = NodeIndex<u32>;
+
+/// This enum specifies the labels of the transitions of an [`Nfa`](./struct.Nfa.html).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Transition {
+    /// Passes to the next state without consuming any character.
+    Epsilon,
+
+    /// Consumes the given character.
+    Char(char),
+
+    /// Consumes any of the given characters, as written by a character class such as `[a-c]`.
+    CharSet(BTreeSet<char>),
+
+    /// Consumes any Unicode decimal digit, as written by `\d`.
+    Digit,
+
+    /// Consumes any character which is not a Unicode decimal digit, as written by `\D`.
+    NonDigit,
+
+    /// Consumes any Unicode whitespace character, as written by `\s`.
+    Space,
+
+    /// Consumes any character which is not a Unicode whitespace character, as written by `\S`.
+    NonSpace,
+
+    /// Consumes any Unicode word character, as written by `\w`.
+    Word,
+
+    /// Consumes any character which is not a Unicode word character, as written by `\W`.
+    NonWord,
+
+    /// Consumes any character, including a line break only if `is_newline_matched` is set.
+    AnyChar { is_newline_matched: bool },
+}
+
+impl Transition {
+    /// Returns true if this transition consumes the given character.
+    /// Epsilon transitions do not consume any character.
+    pub fn matches(&self, c: char) -> bool {
+        match self {
+            Transition::Epsilon => false,
+            Transition::Char(other) => c == *other,
+            Transition::CharSet(chars) => chars.contains(&c),
+            Transition::Digit => is_contained(c, DECIMAL_NUMBER),
+            Transition::NonDigit => !is_contained(c, DECIMAL_NUMBER),
+            Transition::Space => is_contained(c, WHITE_SPACE),
+            Transition::NonSpace => !is_contained(c, WHITE_SPACE),
+            Transition::Word => is_contained(c, WORD),
+            Transition::NonWord => !is_contained(c, WORD),
+            Transition::AnyChar { is_newline_matched } => *is_newline_matched || c != '\n',
+        }
+    }
+
+    /// Returns true if this transition consumes the given character
+    /// or its counterpart in the opposite case.
+    pub(crate) fn matches_ignoring_case(&self, c: char) -> bool {
+        self.matches(c) || opposite_case(c).is_some_and(|other| self.matches(other))
+    }
+}
+
+/// A nondeterministic finite automaton with epsilon transitions which accepts
+/// exactly the strings matched by a generated regular expression.
+/// It is built by [`RegExpBuilder.build_nfa`](./struct.RegExpBuilder.html#method.build_nfa)
+/// and is useful for composing the expression with other automata or for testing.
+///
+/// States are identified by indices. The automaton has a single initial and a single
+/// final state, as resulting from Thompson's construction.
+#[derive(Clone, Debug)]
+pub struct Nfa {
+    graph: StableGraph<(), Transition>,
+    initial_state: State,
+    final_state: State,
+    is_case_insensitive: bool,
+}
+
+impl Nfa {
+    pub(crate) fn new(is_case_insensitive: bool) -> Self {
+        let mut graph = StableGraph::new();
+        let initial_state = graph.add_node(());
+        Self {
+            graph,
+            initial_state,
+            final_state: initial_state,
+            is_case_insensitive,
+        }
+    }
+
+    /// Returns the number of states of this automaton.
+    pub fn state_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// Returns the index of the initial state.
+    pub fn initial_state(&self) -> usize {
+        self.initial_state.index()
+    }
+
+    /// Returns the index of the final state.
+    pub fn final_state(&self) -> usize {
+        self.final_state.index()
+    }
+
+    /// Returns all transitions of this automaton as triples of
+    /// the source state, the transition label and the target state.
+    pub fn transitions(&self) -> Vec<(usize, &Transition, usize)> {
+        (&self.graph)
+            .edge_references()
+            .map(|edge| (edge.source().index(), edge.weight(), edge.target().index()))
+            .collect()
+    }
+
+    /// Returns true if this automaton accepts the entire string `s`.
+    /// For case-insensitive expressions, both cases of each letter are accepted.
+    pub fn accepts(&self, s: &str) -> bool {
+        let mut states = self.epsilon_closure(vec![self.initial_state]);
+
+        for c in s.chars() {
+            let next_states = states
+                .iter()
+                .flat_map(|&state| self.graph.edges_directed(state, Direction::Outgoing))
+                .filter(|edge| {
+                    if self.is_case_insensitive {
+                        edge.weight().matches_ignoring_case(c)
+                    } else {
+                        edge.weight().matches(c)
+                    }
+                })
+                .map(|edge| edge.target())
+                .collect();
+            states = self.epsilon_closure(next_states);
+            if states.is_empty() {
+                return false;
+            }
+        }
+
+        states.contains(&self.final_state)
+    }
+
+    pub(crate) fn add_state(&mut self) -> State {
+        self.graph.add_node(())
+    }
+
+    pub(crate) fn add_transition(&mut self, source: State, target: State, transition: Transition) {
+        self.graph.add_edge(source, target, transition);
+    }
+
+    pub(crate) fn set_final_state(&mut self, state: State) {
+        self.final_state = state;
+    }
+
+    pub(crate) fn start_state(&self) -> State {
+        self.initial_state
+    }
+
+    fn epsilon_closure(&self, mut states: Vec<State>) -> HashSet<State> {
+        let mut closure = states.iter().copied().collect::<HashSet<State>>();
+
+        while let Some(state) = states.pop() {
+            for edge in self.graph.edges_directed(state, Direction::Outgoing) {
+                if edge.weight() == &Transition::Epsilon && closure.insert(edge.target()) {
+                    states.push(edge.target());
+                }
+            }
+        }
+        closure
+    }
+}
+
+fn is_contained(c: char, ranges: &[(char, char)]) -> bool {
+    ranges
+        .iter()
+        .any(|&(start, end)| (start..=end).contains(&c))
+}
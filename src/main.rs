@@ -151,12 +151,23 @@ struct Cli {
     )]
     is_astral_code_point_converted_to_surrogate: bool,
 
+    #[structopt(
+        name = "posix-classes",
+        long,
+        help = "Replaces shorthand character classes such as \\d with POSIX bracket expressions",
+        long_help = "Replaces shorthand character classes such as \\d with POSIX bracket expressions.\n\n\
+                     \\d becomes [[:digit:]], \\s becomes [[:space:]], \\w becomes [[:alnum:]_],\n\
+                     their negations become [^[:digit:]], [^[:space:]] and [^[:alnum:]_].",
+        display_order = 10
+    )]
+    is_posix_char_class_used: bool,
+
     #[structopt(
         name = "ignore-case",
         short,
         long,
         help = "Performs case-insensitive matching, letters match both upper and lower case",
-        display_order = 10
+        display_order = 11
     )]
     is_case_ignored: bool,
 
@@ -165,7 +176,7 @@ struct Cli {
         short = "g",
         long,
         help = "Replaces non-capturing groups by capturing ones",
-        display_order = 11
+        display_order = 12
     )]
     is_group_captured: bool,
 
@@ -174,7 +185,7 @@ struct Cli {
         short = "x",
         long,
         help = "Produces a nicer looking regular expression in verbose mode",
-        display_order = 12
+        display_order = 13
     )]
     is_verbose_mode_enabled: bool,
 
@@ -183,7 +194,7 @@ struct Cli {
         short,
         long,
         help = "Provides syntax highlighting for the resulting regular expression",
-        display_order = 13
+        display_order = 14
     )]
     is_output_colorized: bool,
 
@@ -237,7 +248,7 @@ fn obtain_input(cli: &Cli) -> Result<Vec<String>, Error> {
     if !cli.input.is_empty() {
         Ok(cli.input.clone())
     } else if let Some(file_path) = &cli.file_path {
-        match std::fs::read_to_string(&file_path) {
+        match std::fs::read_to_string(file_path) {
             Ok(file_content) => Ok(file_content.lines().map(|it| it.to_string()).collect_vec()),
             Err(error) => Err(error),
         }
@@ -301,6 +312,10 @@ fn handle_input(cli: &Cli, input: Result<Vec<String>, Error>) {
                 );
             }
 
+            if cli.is_posix_char_class_used {
+                builder.with_posix_char_classes();
+            }
+
             if cli.is_verbose_mode_enabled {
                 builder.with_verbose_mode();
             }
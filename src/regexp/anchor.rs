@@ -0,0 +1,32 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// This enum specifies the anchors which can be placed at the start and at the end
+/// of the generated regular expression using method
+/// [`RegExpBuilder.with_anchors`](./struct.RegExpBuilder.html#method.with_anchors).
+#[derive(Clone, Copy, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum Anchor {
+    /// No anchor is placed, so the expression may match anywhere within a string.
+    None,
+
+    /// The anchor `^` is placed at the start or the anchor `$` is placed at the end,
+    /// so the expression must match at the start or at the end of a string respectively.
+    StringBoundary,
+
+    /// The word boundary `\b` is placed, so the expression must match
+    /// at the start or at the end of a word respectively.
+    WordBoundary,
+}
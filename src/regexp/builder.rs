@@ -14,23 +14,61 @@
  * limitations under the License.
  */
 
+use crate::fsm::{Dfa, Nfa};
+use crate::regexp::diagnostics::permutation_diagnostics;
 use crate::regexp::feature::Feature;
-use crate::regexp::{RegExp, RegExpConfig};
+use crate::regexp::{
+    Anchor, Error, Flavor, GroupMarker, LeadingZeros, OutputWrapper, Preset, ProgressEvent, RegExp,
+    RegExpConfig,
+};
 use itertools::Itertools;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
 use std::io::ErrorKind;
 use std::path::PathBuf;
+use std::time::Duration;
+
+type Preprocessor = Box<dyn Fn(&str) -> String + Send + Sync>;
+type ProgressCallback = Box<dyn Fn(ProgressEvent) + Send + Sync>;
+type InputFilter = Box<dyn Fn(&str) -> bool + Send + Sync>;
 
 /// This struct builds regular expressions from user-provided test cases.
 pub struct RegExpBuilder {
     test_cases: Vec<String>,
     config: RegExpConfig,
+    preprocessor: Option<Preprocessor>,
+    progress_callback: Option<ProgressCallback>,
+    input_filter: Option<InputFilter>,
+    weights: HashMap<String, u64>,
+    negative_test_cases: Vec<String>,
 }
 
 impl RegExpBuilder {
+    /// Creates a `RegExpBuilder` without any test cases.
+    ///
+    /// Test cases can then be added one by one with
+    /// [`add_test_case`](./struct.RegExpBuilder.html#method.add_test_case).
+    /// This is useful for interactive use where test cases become available successively.
+    pub fn new() -> Self {
+        Self {
+            test_cases: vec![],
+            config: RegExpConfig::new(),
+            preprocessor: None,
+            progress_callback: None,
+            input_filter: None,
+            weights: HashMap::new(),
+            negative_test_cases: vec![],
+        }
+    }
+
     /// Specifies the test cases to build the regular expression from.
     ///
     /// The test cases need not be sorted because `RegExpBuilder` sorts them internally.
     ///
+    /// The empty string is a valid test case. If all test cases are empty strings,
+    /// the regular expression only matches the empty string, which is `^$`
+    /// with the default anchors and the empty regular expression without any anchors.
+    ///
     /// ⚠ Panics if `test_cases` is empty.
     pub fn from<T: Clone + Into<String>>(test_cases: &[T]) -> Self {
         if test_cases.is_empty() {
@@ -39,6 +77,38 @@ impl RegExpBuilder {
         Self {
             test_cases: test_cases.iter().cloned().map(|it| it.into()).collect_vec(),
             config: RegExpConfig::new(),
+            preprocessor: None,
+            progress_callback: None,
+            input_filter: None,
+            weights: HashMap::new(),
+            negative_test_cases: vec![],
+        }
+    }
+
+    /// Specifies the test cases to build the regular expression from, each of them
+    /// together with a weight such as its number of occurrences in some data set.
+    /// Weights of duplicate test cases are summed up.
+    ///
+    /// Together with [`with_minimum_weight`](./struct.RegExpBuilder.html#method.with_minimum_weight),
+    /// rare test cases can be excluded so that the regular expression is not overfitted to noise.
+    ///
+    /// ⚠ Panics if `test_cases` is empty.
+    pub fn from_weighted<T: Clone + Into<String>>(test_cases: &[(T, u64)]) -> Self {
+        if test_cases.is_empty() {
+            panic!("No test cases have been provided for regular expression generation");
+        }
+        let mut weights = HashMap::new();
+        for (test_case, weight) in test_cases.iter().cloned() {
+            *weights.entry(test_case.into()).or_insert(0) += weight;
+        }
+        Self {
+            test_cases: weights.keys().cloned().collect_vec(),
+            config: RegExpConfig::new(),
+            preprocessor: None,
+            progress_callback: None,
+            input_filter: None,
+            weights,
+            negative_test_cases: vec![],
         }
     }
 
@@ -60,6 +130,11 @@ impl RegExpBuilder {
             Ok(file_content) => Self {
                 test_cases: file_content.lines().map(|it| it.to_string()).collect_vec(),
                 config: RegExpConfig::new(),
+                preprocessor: None,
+                progress_callback: None,
+                input_filter: None,
+                weights: HashMap::new(),
+                negative_test_cases: vec![],
             },
             Err(error) => match error.kind() {
                 ErrorKind::NotFound => panic!("The specified file could not be found"),
@@ -74,10 +149,46 @@ impl RegExpBuilder {
         }
     }
 
+    /// Adds a single test case to the ones the regular expression is built from.
+    ///
+    /// The regular expression is not generated before
+    /// [`build`](./struct.RegExpBuilder.html#method.build) is called,
+    /// so adding many test cases successively is cheap.
+    pub fn add_test_case<T: Into<String>>(&mut self, test_case: T) -> &mut Self {
+        self.test_cases.push(test_case.into());
+        self
+    }
+
+    /// Specifies test cases which the regular expression must not match although
+    /// it would otherwise. They are removed from the automaton built from the test cases
+    /// by computing the difference of both automata, before the automaton is converted
+    /// into an expression. With [`with_prefix_matching`](./struct.RegExpBuilder.html#method.with_prefix_matching),
+    /// for instance, the test case `abc` together with the negative test case `ab`
+    /// results in `^a(?:bc)?$` instead of `^a(?:bc?)?$`.
+    ///
+    /// Negative test cases are compared literally with the test cases, so strings matched
+    /// only because of a conversion feature, such as `3` being matched by `\d`, are not excluded.
+    /// They do not have any effect together with
+    /// [`with_positional_inference`](./struct.RegExpBuilder.html#method.with_positional_inference).
+    ///
+    /// If a test case is given as negative test case as well, the regular expression cannot
+    /// be built and [`Error::ContradictingTestCase`](./enum.Error.html#variant.ContradictingTestCase)
+    /// is returned by [`try_build`](./struct.RegExpBuilder.html#method.try_build).
+    pub fn with_negative_test_cases<T: Clone + Into<String>>(
+        &mut self,
+        test_cases: &[T],
+    ) -> &mut Self {
+        self.negative_test_cases = test_cases.iter().cloned().map(|it| it.into()).collect_vec();
+        self
+    }
+
     /// Tells `RegExpBuilder` which conversions should be performed during
     /// regular expression generation. The available conversion features
     /// are listed in the [`Feature`](./enum.Feature.html#variants) enum.
     ///
+    /// Only the given features are applied. For instance, `&[Feature::Digit, Feature::Word]`
+    /// converts digits and word characters but leaves whitespace as it is.
+    ///
     /// ⚠ Panics if `features` is empty.
     pub fn with_conversion_of(&mut self, features: &[Feature]) -> &mut Self {
         if features.is_empty() {
@@ -87,6 +198,16 @@ impl RegExpBuilder {
         self
     }
 
+    /// Applies the settings of the given [`Preset`](./enum.Preset.html) at once.
+    /// The variants of the enum list exactly which settings each preset changes.
+    ///
+    /// Settings given before this method may be overwritten by the preset,
+    /// so call it first and adjust individual settings afterwards.
+    pub fn with_preset(&mut self, preset: Preset) -> &mut Self {
+        self.config.apply_preset(preset);
+        self
+    }
+
     /// Specifies the minimum quantity of substring repetitions to be converted if
     /// [`Feature::Repetition`](./enum.Feature.html#variant.Repetition)
     /// is set as one of the features in method
@@ -119,6 +240,465 @@ impl RegExpBuilder {
         self
     }
 
+    /// Specifies the minimum number of characters a character class must contain
+    /// in order to be built from single characters. Below this size, the single characters
+    /// are kept as alternatives, for instance `a|b` for the test cases `a` and `b`
+    /// with a minimum size of 3, whereas `a`, `b` and `c` still result in `[a-c]`.
+    ///
+    /// If the size is not explicitly set with this method, a default value of 2 will be used.
+    ///
+    /// ⚠ Panics if `size` is less than 2.
+    pub fn with_minimum_class_size(&mut self, size: usize) -> &mut Self {
+        if size < 2 {
+            panic!("Minimum class size must not be less than two");
+        }
+        self.config.minimum_class_size = size;
+        self
+    }
+
+    /// Specifies the maximum count any counted repetition in the resulting
+    /// regular expression may have, such as the 3 in `a{3}` or the 5 in `a{2,5}`.
+    ///
+    /// A repetition whose maximum count exceeds this limit is written with the quantifier `+`
+    /// instead, for instance `a+` rather than `a{10000}`. This keeps the output readable
+    /// and quick to compile, but the regular expression then matches more than the test cases.
+    ///
+    /// ⚠ Panics if `count` is zero.
+    pub fn with_maximum_repetition_count(&mut self, count: u32) -> &mut Self {
+        if count == 0 {
+            panic!("Maximum repetition count must not be zero");
+        }
+        self.config.maximum_repetition_count = Some(count);
+        self
+    }
+
+    /// Specifies the maximum number of branches any alternation in the resulting
+    /// regular expression may have.
+    ///
+    /// If this limit is exceeded, the exact regular expression is discarded in favor of
+    /// a much broader one: a character class of all first characters of the test cases
+    /// followed by `.*`, for instance `^[abc].*$`. This keeps the output short for very
+    /// diverse test cases, but the regular expression then matches much more than the
+    /// test cases. Note that `.` does not match line breaks.
+    /// Use [`with_error_instead_of_alternation_fallback`](./struct.RegExpBuilder.html#method.with_error_instead_of_alternation_fallback)
+    /// if an exact regular expression or none at all is preferable.
+    ///
+    /// ⚠ Panics if `limit` is zero.
+    pub fn with_maximum_alternation_branches(&mut self, limit: u32) -> &mut Self {
+        if limit == 0 {
+            panic!("Maximum number of alternation branches must not be zero");
+        }
+        self.config.maximum_alternation_branches = Some(limit);
+        self
+    }
+
+    /// Tells the builder to return an
+    /// [`Error::TooManyAlternationBranches`](./enum.Error.html#variant.TooManyAlternationBranches)
+    /// from [`try_build`](./struct.RegExpBuilder.html#method.try_build) instead of falling back
+    /// to the broader regular expression if the limit set with
+    /// [`with_maximum_alternation_branches`](./struct.RegExpBuilder.html#method.with_maximum_alternation_branches)
+    /// is exceeded. Without such a limit, this setting has no effect.
+    pub fn with_error_instead_of_alternation_fallback(&mut self) -> &mut Self {
+        self.config.is_alternation_fallback_replaced_by_error = true;
+        self
+    }
+
+    /// Specifies the maximum number of states the automaton built from the test cases
+    /// may have. Converting the automaton into a regular expression is the most expensive
+    /// step of the generation, so this limit protects against excessive runtime and memory
+    /// consumption for very large or adversarial test cases.
+    ///
+    /// If this limit is exceeded, [`try_build`](./struct.RegExpBuilder.html#method.try_build)
+    /// returns an [`Error::TooManyStates`](./enum.Error.html#variant.TooManyStates)
+    /// without converting the automaton.
+    ///
+    /// ⚠ Panics if `limit` is zero.
+    pub fn with_maximum_dfa_states(&mut self, limit: usize) -> &mut Self {
+        if limit == 0 {
+            panic!("Maximum number of automaton states must not be zero");
+        }
+        self.config.maximum_dfa_states = Some(limit);
+        self
+    }
+
+    /// Specifies how deeply groups and quantified expressions may be nested within the
+    /// generated regular expression. For instance, the test cases `a`, `ab` and `abc`
+    /// produce `^a(?:bc?)?$` whose nesting depth is 2. Pathological test cases can produce
+    /// expressions nested so deeply that rendering them risks overflowing the stack.
+    ///
+    /// If this limit is exceeded, [`try_build`](./struct.RegExpBuilder.html#method.try_build)
+    /// returns an [`Error::NestingTooDeep`](./enum.Error.html#variant.NestingTooDeep).
+    /// The generation is aborted as soon as an intermediate expression exceeds the limit,
+    /// before the expression becomes too deep to be processed.
+    ///
+    /// ⚠ Panics if `limit` is zero.
+    pub fn with_maximum_nesting_depth(&mut self, limit: usize) -> &mut Self {
+        if limit == 0 {
+            panic!("Maximum nesting depth must not be zero");
+        }
+        self.config.maximum_nesting_depth = Some(limit);
+        self
+    }
+
+    /// Specifies how many subexpressions, such as literals, groups and quantified expressions,
+    /// the generated regular expression may consist of. Each literal counts once per grapheme,
+    /// so the literal `abc` counts as three subexpressions. While the automaton is converted,
+    /// intermediate expressions are combined and copied over and over again, so pathological
+    /// test cases can make the conversion exhaust the available memory.
+    ///
+    /// If this limit is exceeded, [`try_build`](./struct.RegExpBuilder.html#method.try_build)
+    /// returns an [`Error::ExpressionTooLarge`](./enum.Error.html#variant.ExpressionTooLarge).
+    /// Like the nesting depth, the size is checked for each intermediate expression,
+    /// so the generation is aborted before the complete expression has been built.
+    ///
+    /// ⚠ Panics if `limit` is zero.
+    pub fn with_maximum_expression_size(&mut self, limit: usize) -> &mut Self {
+        if limit == 0 {
+            panic!("Maximum expression size must not be zero");
+        }
+        self.config.maximum_expression_size = Some(limit);
+        self
+    }
+
+    /// Specifies how much time the conversion of the automaton built from the test cases
+    /// into a regular expression may take. Unlike the other limits, exceeding this budget
+    /// is not an error: the conversion is cancelled and the plain alternation of the test
+    /// cases is returned instead, for instance `^(?:b|abc|abd)$` rather than `^(?:ab[cd]|b)$`.
+    /// This still matches exactly the test cases but is not optimized in any way.
+    ///
+    /// The time needed to build the automaton counts towards the budget as well,
+    /// but only the conversion itself can be cancelled.
+    ///
+    /// ⚠ Panics if `budget` is zero.
+    pub fn with_time_budget(&mut self, budget: Duration) -> &mut Self {
+        if budget.is_zero() {
+            panic!("Time budget must not be zero");
+        }
+        self.config.time_budget = Some(budget);
+        self
+    }
+
+    /// Specifies the minimum number of graphemes a common prefix or common suffix
+    /// of alternatives must have in order to be factored out. For instance, with a minimum
+    /// length of 2, the test cases `abcd`, `bd` and `d` result in `^(?:abcd|bd|d)$`
+    /// instead of `^(?:abc|b)?d$` because the common suffix `d` is too short.
+    ///
+    /// If the length is not explicitly set with this method, a default value of 1 will be used.
+    /// Common prefixes merged by the underlying automaton are affected as well, so the test
+    /// cases `abc` and `ade` result in `^(?:abc|ade)$` with a minimum length of 2.
+    ///
+    /// ⚠ Panics if `length` is zero.
+    pub fn with_minimum_common_substring_length(&mut self, length: usize) -> &mut Self {
+        if length == 0 {
+            panic!("Minimum common substring length must not be zero");
+        }
+        self.config.minimum_common_substring_length = length;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to split the test cases into buckets of similar length and
+    /// to generate a separate branch for each bucket, joined by a top-level alternation.
+    /// A bucket covers `width` consecutive lengths counted in characters, so a width of 5
+    /// puts lengths 0 to 4 into the first bucket, lengths 5 to 9 into the second one and so on.
+    ///
+    /// For test cases of wildly varying lengths, this results in a longer but more readable
+    /// expression. In verbose mode, each branch is annotated with the lengths it covers.
+    ///
+    /// ⚠ Panics if `width` is zero.
+    pub fn with_length_buckets(&mut self, width: usize) -> &mut Self {
+        if width == 0 {
+            panic!("Width of length buckets must not be zero");
+        }
+        self.config.length_bucket_width = Some(width);
+        self
+    }
+
+    /// Specifies whether common prefixes and common suffixes of alternatives
+    /// are factored out, as in `^a(?:b|cd)$` and `^(?:b|cd)a$` respectively.
+    /// Both are factored out by default.
+    ///
+    /// Note that prefixes and suffixes shared by the test cases are often merged already
+    /// by the minimization of the underlying automaton, so turning off the factoring does
+    /// not necessarily produce a plain alternation of the test cases.
+    pub fn with_factoring_of_common_affixes(
+        &mut self,
+        prefixes: bool,
+        suffixes: bool,
+    ) -> &mut Self {
+        self.config.is_common_prefix_factored = prefixes;
+        self.config.is_common_suffix_factored = suffixes;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to infer a character class for each position if all test cases
+    /// consist of the same number of graphemes, and to fold consecutive equal classes into
+    /// counted repetitions. This produces templates for structured data such as phone numbers.
+    /// For instance, the test cases `555-123-4567` and `555-987-6543` result in
+    /// `^\d{3}-\d{3}-\d{4}$` if [`Feature::Digit`](./enum.Feature.html#variant.Digit)
+    /// is converted as well.
+    ///
+    /// Be aware that this generalizes the resulting expression, as it matches every combination
+    /// of the graphemes found at each position, such as `ab` for the test cases `aa` and `bb`.
+    /// Graphemes which are equal for all test cases are kept as they are. If the test cases
+    /// differ in length or if the graphemes at some position cannot be combined into a single
+    /// character class, the regular expression is generated as usual.
+    /// This setting cannot be combined with
+    /// [`with_prefix_matching`](./struct.RegExpBuilder.html#method.with_prefix_matching) and
+    /// [`with_substring_matching`](./struct.RegExpBuilder.html#method.with_substring_matching).
+    pub fn with_positional_inference(&mut self) -> &mut Self {
+        self.config.is_positional_inference_enabled = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to keep test cases which are prefixes of other test cases
+    /// as separate alternatives instead of making the remaining suffix optional.
+    /// The test cases `ab` and `abc` then result in `^(?:abc|ab)$` rather than `^abc?$`.
+    pub fn with_distinct_prefix_branches(&mut self) -> &mut Self {
+        self.config.is_prefix_branch_kept_distinct = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to replace all dash-like characters in the test cases,
+    /// such as the en dash `–` or the em dash `—`, with the character `replacement`
+    /// before the regular expression is generated. This way, test cases differing in
+    /// their dashes only do not lead to separate branches in the resulting expression.
+    ///
+    /// Note that the resulting expression does not match the original dashes anymore.
+    /// If this is desired, use
+    /// [`with_extension_of_dash_classes`](./struct.RegExpBuilder.html#method.with_extension_of_dash_classes)
+    /// instead.
+    pub fn with_replacement_of_dashes(&mut self, replacement: char) -> &mut Self {
+        self.config.dash_replacement = Some(replacement);
+        self
+    }
+
+    /// Tells `RegExpBuilder` to extend a character class containing at least two different
+    /// dash-like characters, such as the hyphen-minus `-` and the en dash `–`, with all other
+    /// dash-like characters. As soon as the test cases use dashes interchangeably at some
+    /// position, any dash is matched there, for instance `a[\-‐-―−﹘﹣－]b`.
+    pub fn with_extension_of_dash_classes(&mut self) -> &mut Self {
+        self.config.is_dash_class_extended = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to tolerate any of the given `separators` between two adjacent
+    /// characters of the test cases. An optional separator, such as `[ \-]?` for a space
+    /// and a hyphen, is inserted between every two characters of the resulting expression,
+    /// so that the test case `123` produces `^1[ \-]?2[ \-]?3$` which matches `1 2 3`
+    /// and `1-2-3` as well.
+    ///
+    /// Be aware that this widens the resulting expression considerably. Any combination of
+    /// separators is matched, so `12-3` and `1 2-3` are matched as well. Repeated substrings
+    /// converted with [`Feature::Repetition`](./enum.Feature.html#variant.Repetition)
+    /// are kept as they are without any separators between their repetitions.
+    ///
+    /// ⚠ Panics if `separators` is empty.
+    pub fn with_separators(&mut self, separators: &[char]) -> &mut Self {
+        if separators.is_empty() {
+            panic!("No separators have been provided for regular expression generation");
+        }
+        self.config.separators = Some(separators.iter().copied().collect());
+        self
+    }
+
+    /// Tells `RegExpBuilder` to interpret the character `wildcard` within the test cases
+    /// as a placeholder for any run of characters, similar to a glob pattern.
+    /// Each wildcard is written as `.*`, or as `\S*` if `is_whitespace_excluded` is set,
+    /// while the rest of the test cases is treated literally. The test case `ab*c` with
+    /// the wildcard `*` results in `^ab.*c$`, for instance. Consecutive wildcards are
+    /// written as a single one.
+    ///
+    /// In order to match the wildcard character itself, it has to be preceded by
+    /// a backslash in the test cases, as in `ab\*c` which results in `^ab\*c$`.
+    /// The backslash is not part of the match then. Note that `.` does not match line breaks.
+    ///
+    /// ⚠ Panics if `wildcard` is a backslash.
+    pub fn with_wildcard(&mut self, wildcard: char, is_whitespace_excluded: bool) -> &mut Self {
+        if wildcard == '\\' {
+            panic!("The backslash cannot be used as wildcard because it escapes the wildcard");
+        }
+        self.config.wildcard = Some(wildcard);
+        self.config.is_wildcard_whitespace_excluded = is_whitespace_excluded;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to insert word boundaries `\b` at transitions between
+    /// shorthand character classes `\w` or `\d` and literal non-word characters,
+    /// for instance `^\w{3}\b\-\b\d{2}$` instead of `^\w{3}\-\d{2}$`.
+    ///
+    /// This takes effect only in combination with [`Feature::Word`](./enum.Feature.html#variant.Word)
+    /// or [`Feature::Digit`](./enum.Feature.html#variant.Digit). Transitions involving
+    /// other expressions, such as alternations or optional parts, are left untouched.
+    pub fn with_inner_word_boundaries(&mut self) -> &mut Self {
+        self.config.is_word_boundary_inserted = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to collapse adjacent alternations which are equal to each other
+    /// into a counted repetition, for instance `^(?:ab|cd){2}$` instead of `^(?:ab|cd)(?:ab|cd)$`.
+    ///
+    /// Alternations are left untouched in combination with
+    /// [`Feature::CapturingGroup`](./enum.Feature.html#variant.CapturingGroup) because
+    /// collapsing them would merge several capturing groups into one.
+    pub fn with_collapsed_alternation_repeats(&mut self) -> &mut Self {
+        self.config.is_alternation_repeat_collapsed = true;
+        self
+    }
+
+    /// Specifies the minimum weight a test case passed to
+    /// [`from_weighted`](./struct.RegExpBuilder.html#method.from_weighted)
+    /// must have in order to be considered for regular expression generation.
+    /// Test cases with a smaller weight are excluded. Test cases without a weight
+    /// are always considered. The default value is 0, so that nothing is excluded.
+    pub fn with_minimum_weight(&mut self, weight: u64) -> &mut Self {
+        self.config.minimum_weight = weight;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to generate a regular expression which matches every non-empty
+    /// prefix of the test cases instead of the test cases only. For the test case `abc`, the
+    /// resulting expression `^a(?:bc?)?$` matches `a`, `ab` and `abc`.
+    /// This is useful for validating partial input such as in autocompletion.
+    ///
+    /// Substrings converted with [`Feature::Repetition`](./enum.Feature.html#variant.Repetition)
+    /// are treated as a unit, so prefixes ending within such a substring are not matched.
+    pub fn with_prefix_matching(&mut self) -> &mut Self {
+        self.config.is_prefix_matched = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to generate a regular expression which matches every non-empty
+    /// contiguous substring of the test cases instead of the test cases only. For the test
+    /// case `abc`, the resulting expression matches `a`, `b`, `c`, `ab`, `bc` and `abc`.
+    /// The expression is still anchored, so strings merely containing a substring of the
+    /// test cases are not matched.
+    ///
+    /// Be aware that this generalizes the resulting expression heavily and that its size
+    /// grows quickly with the length of the test cases. Substrings converted with
+    /// [`Feature::Repetition`](./enum.Feature.html#variant.Repetition) are treated as a unit.
+    pub fn with_substring_matching(&mut self) -> &mut Self {
+        self.config.is_substring_matched = true;
+        self
+    }
+
+    /// Specifies a function which transforms each test case before the regular expression
+    /// is generated from it. This can be used to apply custom preprocessing such as
+    /// stripping prefixes or normalizing the test cases.
+    ///
+    /// The function is applied to each test case exactly once per call of
+    /// [`build`](./struct.RegExpBuilder.html#method.build), before duplicates are removed.
+    /// Test cases which are equal after preprocessing are merged into a single one, so
+    /// a function normalizing Unicode strings lets the composed and the decomposed form
+    /// of `é` result in one branch only. Their weights are summed up in this case.
+    /// The generated regular expression is deterministic as long as the function is pure.
+    pub fn with_preprocessor<F>(&mut self, preprocessor: F) -> &mut Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.preprocessor = Some(Box::new(preprocessor));
+        self
+    }
+
+    /// Tells `RegExpBuilder` to treat each test case as a line of delimiter-separated values,
+    /// such as read from a CSV or TSV file with [`from_file`](./struct.RegExpBuilder.html#method.from_file),
+    /// and to take only the field at the zero-based column `index` as the actual test case.
+    /// For instance, with the delimiter `,` and the index 1, the line `1,abc,x` results in
+    /// the test case `abc`. Lines with fewer columns are skipped.
+    ///
+    /// Fields enclosed in double quotes may contain the delimiter, and two consecutive
+    /// double quotes within them stand for a single one, as in `"a,""b"""` for `a,"b"`.
+    /// Line breaks within quoted fields are not supported because each line is a separate
+    /// test case. Whitespace around the delimiter is significant and kept as part of the field,
+    /// so a column padded with spaces has to be trimmed with
+    /// [`with_preprocessor`](./struct.RegExpBuilder.html#method.with_preprocessor).
+    ///
+    /// The column is selected before any other processing of the test cases, so the
+    /// [`with_input_filter`](./struct.RegExpBuilder.html#method.with_input_filter) predicate
+    /// and the preprocessor are applied to the field only. Negative test cases are taken
+    /// as they are.
+    ///
+    /// ⚠ Panics if `delimiter` is a double quote.
+    pub fn with_column(&mut self, delimiter: char, index: usize) -> &mut Self {
+        if delimiter == '"' {
+            panic!("Column delimiter must not be a double quote");
+        }
+        self.config.column = Some((delimiter, index));
+        self
+    }
+
+    /// Tells `RegExpBuilder` to treat the parts of test cases enclosed in the markers `open`
+    /// and `close` as optional. For instance, with the markers `[` and `]`, the test case
+    /// `colo[u]r` stands for both `color` and `colour` and results in `^colou?r$`.
+    /// Markers may be nested, as in `a[b[c]]` for `a`, `ab` and `abc`.
+    ///
+    /// A marker preceded by a backslash is taken literally, as in `colo\[u\]r` for `colo[u]r`,
+    /// and so is a backslash preceded by another backslash. All other backslashes are kept.
+    /// A test case with optional parts is not expanded into all of the strings it stands for
+    /// but turned into an expression of its own, which is merged with the expression built
+    /// from the other test cases. The markers are resolved after the
+    /// [`with_preprocessor`](./struct.RegExpBuilder.html#method.with_preprocessor) function
+    /// has been applied. Negative test cases are taken as they are.
+    ///
+    /// As the alternation fallback and the length buckets are computed from the strings
+    /// of the test cases, this setting cannot be combined with
+    /// [`with_length_buckets`](./struct.RegExpBuilder.html#method.with_length_buckets) or with
+    /// [`with_maximum_alternation_branches`](./struct.RegExpBuilder.html#method.with_maximum_alternation_branches)
+    /// unless the fallback is replaced by an error.
+    ///
+    /// If a test case contains unbalanced markers, the regular expression cannot be built and
+    /// [`Error::UnbalancedOptionalMarkers`](./enum.Error.html#variant.UnbalancedOptionalMarkers)
+    /// is returned by [`try_build`](./struct.RegExpBuilder.html#method.try_build).
+    ///
+    /// ⚠ Panics if `open` and `close` are equal or if either of them is a backslash.
+    pub fn with_optional_markers(&mut self, open: char, close: char) -> &mut Self {
+        if open == close {
+            panic!("The opening and closing optional markers must differ from each other");
+        }
+        if open == '\\' || close == '\\' {
+            panic!(
+                "The backslash cannot be used as optional marker because it escapes the markers"
+            );
+        }
+        self.config.optional_markers = Some((open, close));
+        self
+    }
+
+    /// Specifies a predicate which each test case has to fulfill in order to be considered
+    /// for regular expression generation, such as a maximum length or a required format.
+    /// Test cases failing the predicate are excluded as if they had not been provided,
+    /// so the generated regular expression does not match them unless other test cases
+    /// generalize to them.
+    ///
+    /// The predicate is applied to the original test cases before they are passed to the
+    /// [`with_preprocessor`](./struct.RegExpBuilder.html#method.with_preprocessor) function
+    /// and before duplicates are removed. The number of excluded test cases is reported as
+    /// [`ProgressEvent::TestCasesFiltered`](./enum.ProgressEvent.html#variant.TestCasesFiltered)
+    /// to the callback given to [`with_progress_callback`](./struct.RegExpBuilder.html#method.with_progress_callback).
+    ///
+    /// ⚠ Panics when building if all test cases fail the predicate.
+    pub fn with_input_filter<F>(&mut self, input_filter: F) -> &mut Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.input_filter = Some(Box::new(input_filter));
+        self
+    }
+
+    /// Specifies a function which is called with a [`ProgressEvent`](./enum.ProgressEvent.html)
+    /// whenever a phase of the generation has been completed. This can be used to give feedback
+    /// to users while a regular expression is built from a large number of test cases.
+    ///
+    /// Besides the test case ingestion, the construction of the automaton and the rendering,
+    /// the elimination of each of the automaton's states is reported, which is usually
+    /// the phase taking longest.
+    pub fn with_progress_callback<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(ProgressEvent) + Send + Sync + 'static,
+    {
+        self.progress_callback = Some(Box::new(callback));
+        self
+    }
+
     /// Tells `RegExpBuilder` to convert non-ASCII characters to unicode escape sequences.
     /// The parameter `use_surrogate_pairs` specifies whether to convert astral code planes
     /// (range `U+010000` to `U+10FFFF`) to surrogate pairs.
@@ -128,11 +708,354 @@ impl RegExpBuilder {
         self
     }
 
+    /// Tells `RegExpBuilder` to write all ASCII control characters as hexadecimal
+    /// escape sequences, such as `\x09` for a tab and `\x0A` for a line feed, both
+    /// in literals and in character classes. By default, tabs, line feeds, carriage returns
+    /// and line tabulations are written as `\t`, `\n`, `\r` and `\v`, and only the
+    /// remaining control characters are written as hexadecimal escape sequences.
+    pub fn with_hex_escaping_of_control_chars(&mut self) -> &mut Self {
+        self.config.is_control_char_hex_escaped = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to render the shorthand character classes created by the
+    /// conversion features as POSIX bracket expressions, for regular expression engines
+    /// such as POSIX ERE which do not support shorthands like `\d`.
+    ///
+    /// The following replacements are made:
+    /// - `\d` becomes `[[:digit:]]`, `\D` becomes `[^[:digit:]]`
+    /// - `\s` becomes `[[:space:]]`, `\S` becomes `[^[:space:]]`
+    /// - `\w` becomes `[[:alnum:]_]`, `\W` becomes `[^[:alnum:]_]`
+    ///
+    /// Note that whether the POSIX classes match non-ASCII characters
+    /// depends on the locale of the respective regular expression engine.
+    pub fn with_posix_char_classes(&mut self) -> &mut Self {
+        self.config.is_posix_char_class_used = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to write the Unicode blocks entirely contained in a character
+    /// class as named block references in the syntax of the given [`Flavor`](./enum.Flavor.html#variants).
+    /// The supported blocks are Basic Latin, Latin-1 Supplement, Latin Extended-A,
+    /// Latin Extended-B, Greek and Cyrillic. Characters outside of complete blocks
+    /// are listed in the class as usual.
+    ///
+    /// For instance, a class of all characters from `\u{0}` to `\u{7f}` is written as
+    /// `\p{IsBasicLatin}` with `Flavor::DotNet` and as `\p{InBasicLatin}` with `Flavor::Java`.
+    /// Adding `é` results in `[\p{IsBasicLatin}é]`. Be aware that the `regex` crate does
+    /// not support named blocks, so the resulting expression cannot be used with it.
+    pub fn with_unicode_block_names(&mut self, flavor: Flavor) -> &mut Self {
+        self.config.unicode_block_flavor = Some(flavor);
+        self
+    }
+
+    /// Tells `RegExpBuilder` to write the Unicode scripts entirely contained in a character
+    /// class as named script references such as `\p{Latin}`, which are understood by the
+    /// `regex` crate, PCRE and Perl among others. The supported scripts are Latin, Greek
+    /// and Cyrillic. Characters outside of complete scripts are listed in the class as usual.
+    ///
+    /// For instance, a class of all Latin and Greek characters is written as
+    /// `[\p{Latin}\p{Greek}]` instead of listing several hundred ranges.
+    pub fn with_unicode_script_names(&mut self) -> &mut Self {
+        self.config.is_unicode_script_name_used = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to express case-insensitive matching by character classes
+    /// instead of by the inline flag `(?i)`, for regular expression engines which do not
+    /// support inline flags. Every cased letter is expanded into a class of both cases,
+    /// so the test case `hello` results in `^[Hh][Ee][Ll][Ll][Oo]$`, and character classes
+    /// contain both cases of their letters, as in `[A-Ca-c]`.
+    ///
+    /// This setting only takes effect together with
+    /// [`Feature::CaseInsensitivity`](./enum.Feature.html#variant.CaseInsensitivity).
+    pub fn with_case_insensitivity_as_char_classes(&mut self) -> &mut Self {
+        self.config.is_case_expanded_into_char_classes = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to write the dot, which matches any character, as `(?s:.)`
+    /// so that it matches line breaks as well. Otherwise, the dot created by
+    /// [`with_wildcard`](./struct.RegExpBuilder.html#method.with_wildcard) or by exceeding
+    /// the limit of [`with_maximum_alternation_branches`](./struct.RegExpBuilder.html#method.with_maximum_alternation_branches)
+    /// does not match test cases spanning multiple lines. For instance, the wildcard in
+    /// `a*b` results in `^a(?s:.)*b$`, which matches `a\nb` too.
+    ///
+    /// The inline flag `s` is not supported by POSIX regular expressions, so this setting
+    /// cannot be combined with [`with_posix_char_classes`](./struct.RegExpBuilder.html#method.with_posix_char_classes).
+    pub fn with_dot_matching_newlines(&mut self) -> &mut Self {
+        self.config.is_dot_matching_newline = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to write the inline flag `s` in front of the expression instead
+    /// of applying it to each dot separately, provided that
+    /// [`with_dot_matching_newlines`](./struct.RegExpBuilder.html#method.with_dot_matching_newlines)
+    /// is set. For instance, the wildcard in `a*b` then results in `(?s)^a.*b$` instead of
+    /// `^a(?s:.)*b$`. Without the latter setting, this setting has no effect.
+    ///
+    /// The flag is combined with all other inline flags into a single group in the canonical
+    /// order `imsx`, such as `(?ims)` together with case-insensitive and multiline matching.
+    pub fn with_leading_dot_all_flag(&mut self) -> &mut Self {
+        self.config.is_dot_all_flag_leading = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to write the inline flag `m` in front of the expression,
+    /// so that the anchors `^` and `$` match at the start and at the end of each line
+    /// instead of the whole string only. For instance, the test cases `a` and `b`
+    /// result in `(?m)^[ab]$`, which matches both lines of `a\nb`.
+    ///
+    /// The flag is combined with all other inline flags into a single group in the canonical
+    /// order `imsx`, such as `(?im)` together with case-insensitive matching. Inline flags
+    /// are not supported by POSIX regular expressions, so this setting cannot be combined with
+    /// [`with_posix_char_classes`](./struct.RegExpBuilder.html#method.with_posix_char_classes).
+    pub fn with_multiline_matching(&mut self) -> &mut Self {
+        self.config.is_multiline_matching = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to write the dot, which matches any character except line breaks,
+    /// as the character class `[^\n]` instead. Both match exactly the same, but the class
+    /// makes it obvious that line breaks are excluded. For instance, the wildcard in `a*b`
+    /// results in `^a[^\n]*b$` rather than `^a.*b$`.
+    ///
+    /// This setting cannot be combined with
+    /// [`with_dot_matching_newlines`](./struct.RegExpBuilder.html#method.with_dot_matching_newlines)
+    /// which makes the dot match line breaks, nor with
+    /// [`with_posix_char_classes`](./struct.RegExpBuilder.html#method.with_posix_char_classes)
+    /// because POSIX bracket expressions do not support escape sequences.
+    pub fn with_explicit_newline_exclusion(&mut self) -> &mut Self {
+        self.config.is_newline_exclusion_explicit = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to wrap each branch of the top-level alternation in a named
+    /// capturing group, such as in `^(?:(?P<b0>abc)|(?P<b1>de)|(?P<b2>f))$`.
+    /// The branches are named `b0`, `b1` and so on from left to right, so the name of the
+    /// participating group tells which branch has matched. This is useful for debugging.
+    ///
+    /// If the expression is not an alternation at the top level, this setting has no effect.
+    /// Named groups are not supported by POSIX regular expressions, so this setting cannot
+    /// be combined with [`with_posix_char_classes`](./struct.RegExpBuilder.html#method.with_posix_char_classes).
+    pub fn with_named_branches(&mut self) -> &mut Self {
+        self.config.is_branch_named = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to list the characters of character classes in the order
+    /// in which they first appear in the test cases instead of sorting them.
+    /// For instance, the test cases `c`, `a` and `b` produce `^[cab]$` instead of `^[a-c]$`.
+    /// This makes it easier to trace the characters back to the test cases.
+    ///
+    /// Ranges such as `a-c` are not formed in this mode, so character classes may get longer.
+    /// Characters not appearing in the test cases, such as the separators given to
+    /// [`with_separators`](./struct.RegExpBuilder.html#method.with_separators),
+    /// are listed after all others in sorted order.
+    pub fn with_char_classes_in_order_of_appearance(&mut self) -> &mut Self {
+        self.config.is_char_class_order_preserved = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to list the characters of character classes in descending
+    /// order of how often they appear in the test cases instead of sorting them.
+    /// For instance, the test cases `xa`, `xb`, `xc`, `cc` and `ac` produce
+    /// `^(?:[ca]c|x[cab])$` instead of `^(?:[ac]c|x[a-c])$` because `c` appears most often.
+    ///
+    /// Ranges such as `a-c` are not formed in this mode, so character classes may get longer.
+    /// Characters appearing equally often are listed in sorted order. This setting cannot be
+    /// combined with [`with_char_classes_in_order_of_appearance`](./struct.RegExpBuilder.html#method.with_char_classes_in_order_of_appearance).
+    pub fn with_char_classes_in_order_of_frequency(&mut self) -> &mut Self {
+        self.config.is_char_class_ordered_by_frequency = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` that the strings to be matched consist of characters of the
+    /// given `alphabet` only, so that each character class can be written in its shortest
+    /// valid form. Besides the class listing its characters, this is either a negated class
+    /// of the remaining characters of the alphabet or a dot if the class contains the entire
+    /// alphabet. With the alphabet `acegik`, for instance, the class `[acegi]` is written
+    /// as `[^k]`, whereas `[ac]` is kept as it is.
+    ///
+    /// If the alphabet contains a line break, a dot is written as `[\s\S]` unless
+    /// [`with_dot_matching_newlines`](./struct.RegExpBuilder.html#method.with_dot_matching_newlines)
+    /// is set. Be aware that negated classes and dots match characters outside the alphabet as well.
+    ///
+    /// ⚠ Panics if `alphabet` is empty.
+    pub fn with_shortest_char_classes(&mut self, alphabet: &[char]) -> &mut Self {
+        if alphabet.is_empty() {
+            panic!("No alphabet has been provided for choosing the shortest character classes");
+        }
+        self.config.char_class_alphabet = Some(alphabet.iter().copied().collect());
+        self
+    }
+
+    /// Tells `RegExpBuilder` to render the quantifiers `?` and `*` in brace notation,
+    /// that is `{0,1}` and `{0,}` respectively. The matched language does not change.
+    pub fn with_braced_quantifiers(&mut self) -> &mut Self {
+        self.config.is_quantifier_braced = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` to wrap runs of literal characters in `\Q...\E` instead of
+    /// escaping each metacharacter with a backslash, wherever this is shorter. For instance,
+    /// the test case `(a)[b]{c}` results in `^\Q(a)[b]{c}\E$` instead of `^\(a\)\[b\]\{c\}$`.
+    /// A literal `\E` within such a run is written outside of it as `\E\\E\Q`.
+    ///
+    /// Literal quoting is supported by PCRE, Java and Perl but not by the Rust regex crate
+    /// or by POSIX regular expressions, so this setting cannot be combined with
+    /// [`with_posix_char_classes`](./struct.RegExpBuilder.html#method.with_posix_char_classes).
+    /// It cannot be combined with [`with_verbose_mode`](./struct.RegExpBuilder.html#method.with_verbose_mode)
+    /// either because whitespace is rearranged in verbose mode.
+    pub fn with_literal_quoting(&mut self) -> &mut Self {
+        self.config.is_literal_quoted = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` which form to prefer for an expression which is both optional
+    /// and repeatable. By default, both quantifiers are kept in a repetition of an optional
+    /// expression, as in `(?:(?:ab)?)*`. With this setting, the redundant quantifier is
+    /// dropped and only the Kleene star remains, as in `(?:ab)*`. The matched language
+    /// does not change. Optional repetitions such as `(?:(?:ab)*)?`, which include the
+    /// wildcards created by [`with_wildcard`](./struct.RegExpBuilder.html#method.with_wildcard),
+    /// are always written as `(?:ab)*` regardless of this setting.
+    pub fn with_kleene_star_preferred(&mut self) -> &mut Self {
+        self.config.is_kleene_star_preferred = true;
+        self
+    }
+
+    /// Tells `RegExpBuilder` not to introduce any repetitions, so that the resulting
+    /// regular expression matches the test cases by spelling them out, even if it gets longer.
+    /// Neither counted repetitions such as `{2}` nor unbounded quantifiers such as `*` and `+`
+    /// are written then, only `?` for optional parts. This takes precedence over converting
+    /// [`Feature::Repetition`](./enum.Feature.html#variant.Repetition), which may have been
+    /// selected by a preset, for instance.
+    ///
+    /// Wildcards and the broad expression written when the maximum number of alternation
+    /// branches is exceeded consist of unbounded quantifiers, so this setting cannot be combined
+    /// with [`with_wildcard`](./struct.RegExpBuilder.html#method.with_wildcard) or
+    /// [`with_maximum_alternation_branches`](./struct.RegExpBuilder.html#method.with_maximum_alternation_branches).
+    pub fn with_disabled_repetitions(&mut self) -> &mut Self {
+        self.config.is_repetition_disabled = true;
+        self
+    }
+
     pub fn with_verbose_mode(&mut self) -> &mut Self {
         self.config.is_verbose_mode_enabled = true;
         self
     }
 
+    /// Specifies the anchors to place at the start and at the end of the resulting
+    /// regular expression. By default, the expression is surrounded by `^` and `$`,
+    /// so that substrings not being part of the test cases are not matched accidentally.
+    /// The available anchors are listed in the [`Anchor`](./enum.Anchor.html#variants) enum.
+    ///
+    /// For instance, `with_anchors(Anchor::WordBoundary, Anchor::None)` produces
+    /// `\b(?:bc|a)` for the test cases `a` and `bc`, which matches words starting
+    /// with one of the test cases.
+    pub fn with_anchors(&mut self, start: Anchor, end: Anchor) -> &mut Self {
+        self.config.start_anchor = start;
+        self.config.end_anchor = end;
+        self
+    }
+
+    /// Specifies the string literal in which the resulting regular expression is wrapped,
+    /// so that it can be pasted into source code without escaping it by hand.
+    /// The available wrappers are listed in the [`OutputWrapper`](./enum.OutputWrapper.html#variants) enum.
+    ///
+    /// For instance, `with_output_wrapper(OutputWrapper::RustRaw)` produces
+    /// `r#"^"a"$"#` for the test case `"a"`.
+    pub fn with_output_wrapper(&mut self, wrapper: OutputWrapper) -> &mut Self {
+        self.config.output_wrapper = wrapper;
+        self
+    }
+
+    /// Specifies the syntax of the groups which enclose subexpressions, such as alternations
+    /// within concatenations or quantified concatenations. The available syntaxes are listed in
+    /// the [`GroupMarker`](./enum.GroupMarker.html#variants) enum. All groups of the expression
+    /// are written in the same syntax, apart from the named groups created by
+    /// [`with_named_branches`](./struct.RegExpBuilder.html#method.with_named_branches).
+    ///
+    /// For instance, `with_group_marker(GroupMarker::Atomic)` produces `^a(?>bc)?$`
+    /// instead of `^a(?:bc)?$` for the test cases `a` and `abc`.
+    pub fn with_group_marker(&mut self, marker: GroupMarker) -> &mut Self {
+        self.config.group_marker = marker;
+        self
+    }
+
+    /// Specifies how leading zeros of numeric test cases such as `007` are treated.
+    /// By default, they are kept, so `7` and `007` are distinct test cases resulting
+    /// in `^(?:00)?7$`. The available treatments are listed in the
+    /// [`LeadingZeros`](./enum.LeadingZeros.html#variants) enum.
+    ///
+    /// For instance, `with_leading_zeros(LeadingZeros::Removed)` produces `^7$` and
+    /// `with_leading_zeros(LeadingZeros::Optional)` produces `^0*7$` for the test cases
+    /// `7` and `007`. Negative test cases are treated the same way.
+    pub fn with_leading_zeros(&mut self, leading_zeros: LeadingZeros) -> &mut Self {
+        self.config.leading_zeros = leading_zeros;
+        self
+    }
+
+    /// Specifies whether the test cases are the complete set of strings to be matched
+    /// or merely examples of them, which determines whether anchors are placed.
+    ///
+    /// If the test cases are complete, which is assumed by default, the expression is surrounded
+    /// by `^` and `$` because it must not match anything else, not even as part of a longer string.
+    /// If they are just examples, the expression is meant to find similar strings within some text,
+    /// so no anchors are placed. For instance, the test cases `a` and `bc` result in
+    /// `^(?:bc|a)$` and `bc|a` respectively.
+    ///
+    /// This is a shorthand for [`with_anchors`](./struct.RegExpBuilder.html#method.with_anchors)
+    /// with [`Anchor::StringBoundary`](./enum.Anchor.html#variant.StringBoundary) or
+    /// [`Anchor::None`](./enum.Anchor.html#variant.None) at both ends, whichever is called last wins.
+    pub fn with_complete_input_set(&mut self, is_complete: bool) -> &mut Self {
+        let anchor = if is_complete {
+            Anchor::StringBoundary
+        } else {
+            Anchor::None
+        };
+        self.with_anchors(anchor, anchor)
+    }
+
+    /// Tells `RegExpBuilder` to document the test cases in a leading comment such as
+    /// `# generated from: a, b, c` right after the verbose mode flag. At most five test cases
+    /// are listed, followed by an ellipsis if there are more of them. Special characters
+    /// such as line feeds are escaped so that the comment cannot end prematurely.
+    ///
+    /// This setting only takes effect together with
+    /// [`with_verbose_mode`](./struct.RegExpBuilder.html#method.with_verbose_mode)
+    /// because comments are not supported otherwise.
+    pub fn with_test_cases_as_comment(&mut self) -> &mut Self {
+        self.config.is_test_case_comment_added = true;
+        self
+    }
+
+    /// Specifies the text which is written in place of each pipe separating the options
+    /// of an alternation, so that long alternations can be laid out for documentation.
+    /// The separator must consist of a single pipe surrounded by whitespace and comments,
+    /// all of which are ignored in verbose mode. Its lines are indented like the pipe.
+    ///
+    /// For instance, the separator `"\n| # or\n"` puts an empty line before and after each
+    /// pipe and annotates it with a comment.
+    ///
+    /// This setting only takes effect together with
+    /// [`with_verbose_mode`](./struct.RegExpBuilder.html#method.with_verbose_mode),
+    /// the compact expression always separates the options by a plain pipe.
+    ///
+    /// ⚠ Panics if `separator` does not consist of a single pipe surrounded by whitespace
+    /// and comments.
+    pub fn with_alternation_separator(&mut self, separator: &str) -> &mut Self {
+        let significant_chars = separator
+            .lines()
+            .map(|line| line.split('#').next().unwrap().trim())
+            .collect::<String>();
+        if significant_chars != "|" {
+            panic!("The alternation separator must consist of a single pipe surrounded by whitespace and comments");
+        }
+        self.config.alternation_separator = Some(separator.to_string());
+        self
+    }
+
     /// Tells `RegExpBuilder` to provide syntax highlighting for the resulting regular expression.
     ///
     /// ⚠ This method may only be used if the resulting regular expression is meant to
@@ -143,10 +1066,535 @@ impl RegExpBuilder {
         self
     }
 
+    /// Returns the settings given so far, for instance in order to escape strings
+    /// with [`escape`](./fn.escape.html) in the same way as the literals of the regular expression.
+    pub fn config(&self) -> &RegExpConfig {
+        &self.config
+    }
+
     /// Builds the actual regular expression using the previously given settings.
-    /// Every generated regular expression is surrounded by the anchors `^` and `$`
+    /// By default, every generated regular expression is surrounded by the anchors `^` and `$`
     /// so that substrings not being part of the test cases are not matched accidentally.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been
+    /// specified or if the automaton has too many states. Use
+    /// [`try_build`](./struct.RegExpBuilder.html#method.try_build) to handle
+    /// these cases without panicking.
     pub fn build(&mut self) -> String {
-        RegExp::from(&mut self.test_cases, &self.config).to_string()
+        match self.try_build() {
+            Ok(regexp) => regexp,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// but validates the previously given settings first. If any of them cannot be combined
+    /// with each other, an [`Error::ConflictingOptions`](./enum.Error.html#variant.ConflictingOptions)
+    /// naming both of them is returned instead of a regular expression. If the automaton built
+    /// from the test cases exceeds the limit set with
+    /// [`with_maximum_dfa_states`](./struct.RegExpBuilder.html#method.with_maximum_dfa_states),
+    /// an [`Error::TooManyStates`](./enum.Error.html#variant.TooManyStates) is returned.
+    /// If no test cases have been provided or none of them is left after filtering them,
+    /// an [`Error::NoTestCases`](./enum.Error.html#variant.NoTestCases) is returned.
+    pub fn try_build(&mut self) -> Result<String, Error> {
+        let regexp = self.regexp()?.to_string();
+        if let Some(callback) = &self.progress_callback {
+            callback(ProgressEvent::RenderingDone);
+        }
+        Ok(regexp)
+    }
+
+    /// Builds the actual regular expression in two forms and returns them together, the form
+    /// for searching first and the form for full matching second. The search form has no anchors,
+    /// so it matches the test cases anywhere within a string, which is what methods like
+    /// `Regex::is_match` or `Regex::find` look for. The full match form is surrounded by `^`
+    /// and `$`, so it matches the test cases only if they make up the entire string.
+    /// For instance, the test cases `a` and `bc` result in `bc|a` and `^(?:bc|a)$`.
+    ///
+    /// The anchors specified with [`with_anchors`](./struct.RegExpBuilder.html#method.with_anchors)
+    /// are replaced for both forms, all other settings are kept.
+    /// The progress callback is not invoked.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if a limit is exceeded.
+    pub fn build_for_search_and_full_match(&mut self) -> (String, String) {
+        let mut search_config = self.config.clone();
+        search_config.start_anchor = Anchor::None;
+        search_config.end_anchor = Anchor::None;
+
+        let mut full_match_config = self.config.clone();
+        full_match_config.start_anchor = Anchor::StringBoundary;
+        full_match_config.end_anchor = Anchor::StringBoundary;
+
+        match (
+            self.regexp_with_config(&search_config, None),
+            self.regexp_with_config(&full_match_config, None),
+        ) {
+            (Ok(search_regexp), Ok(full_match_regexp)) => {
+                (search_regexp.to_string(), full_match_regexp.to_string())
+            }
+            (Err(error), _) | (_, Err(error)) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// but returns up to `count` of the shortest strings it matches instead of the expression itself.
+    /// Shorter strings come first, strings of equal length are sorted lexicographically.
+    ///
+    /// Each character class is represented by a single sample character, so the returned strings
+    /// are representative matches rather than the complete language of the expression.
+    /// This is useful for generating input data for tests or fuzzing.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if the automaton has too many states.
+    pub fn build_shortest_matches(&mut self, count: usize) -> Vec<String> {
+        match self.regexp() {
+            Ok(regexp) => regexp.shortest_matches(count),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// but returns the number of characters of the longest string it matches instead of the
+    /// expression itself. This is useful for sizing buffers. For instance, the test cases `a`
+    /// and `abc` result in `^a(?:bc)?$`, whose longest match has 3 characters.
+    ///
+    /// Each character class, shorthand class or dot matches a single character. If the expression
+    /// contains an unbounded quantifier such as `*` or `+`, its matches can be arbitrarily long
+    /// and `None` is returned.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if a limit is exceeded.
+    pub fn build_maximum_match_length(&mut self) -> Option<usize> {
+        match self.regexp() {
+            Ok(regexp) => regexp.maximum_match_length(),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// but returns a description of it in plain English instead of the expression itself,
+    /// for instance `matches "ab", then either a digit or "c"` for the test cases `abc` and `ab1`
+    /// with digit conversion enabled. The whole input has to be matched, just as with the
+    /// anchors of the regular expression.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if the automaton has too many states.
+    pub fn build_explanation(&mut self) -> String {
+        match self.regexp() {
+            Ok(regexp) => regexp.explain(),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the regular expressions of this builder and of `other` like
+    /// [`build`](./struct.RegExpBuilder.html#method.build) and describes how the latter
+    /// differs structurally from the former. This is useful for reviewing how changed
+    /// test cases or settings affect the generated expression.
+    ///
+    /// Each difference is described by a separate string, such as
+    /// `added 'c' to character class [ab]` for the test cases `a` and `b` compared
+    /// to `a`, `b` and `c`. Added or removed branches of alternations and changed quantifiers
+    /// are reported as well. Subexpressions whose structure differs are reported as replaced.
+    /// If both expressions are equal, the returned vector is empty.
+    ///
+    /// ⚠ Panics if no test cases have been provided to either of the builders, if conflicting
+    /// options have been specified or if a limit is exceeded.
+    pub fn build_diff(&mut self, other: &mut RegExpBuilder) -> Vec<String> {
+        match (self.regexp(), other.regexp()) {
+            (Ok(regexp), Ok(other_regexp)) => regexp.diff(&other_regexp),
+            (Err(error), _) | (_, Err(error)) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the automata of this builder and of `other`, from which the regular expressions
+    /// would be built like with [`build`](./struct.RegExpBuilder.html#method.build), and returns
+    /// true if both accept the same strings. This answers whether two sets of test cases are
+    /// equivalent under the settings of the respective builder. For instance, the test cases
+    /// `1` and `2` are equivalent to `3` with [`Feature::Digit`](./enum.Feature.html#variant.Digit)
+    /// because all of them are converted to `\d`, whereas they are not equivalent without it.
+    ///
+    /// Only the automata are compared, so settings which rework the expression afterwards,
+    /// such as [`with_maximum_alternation_branches`](./struct.RegExpBuilder.html#method.with_maximum_alternation_branches),
+    /// [`with_positional_inference`](./struct.RegExpBuilder.html#method.with_positional_inference)
+    /// or [`with_length_buckets`](./struct.RegExpBuilder.html#method.with_length_buckets), are not
+    /// taken into account. Neither are test cases with parts enclosed in the markers given by
+    /// [`with_optional_markers`](./struct.RegExpBuilder.html#method.with_optional_markers),
+    /// which are turned into expressions without an automaton. Both builders have to use the same settings because characters
+    /// built with different settings are never considered equal.
+    ///
+    /// If no test cases have been provided to either of the builders or if conflicting
+    /// options have been specified, the respective [`Error`](./enum.Error.html) is returned.
+    pub fn is_equivalent_to(&self, other: &RegExpBuilder) -> Result<bool, Error> {
+        Ok(self.dfa()?.is_equivalent(&other.dfa()?))
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// but returns the set of characters which its matches can start with instead of the
+    /// expression itself. This is useful for search indexes which skip positions that cannot
+    /// start a match. For instance, the test cases `ab` and `b` result in `^a?b$`,
+    /// whose matches start with either `a` or `b`.
+    ///
+    /// The empty string, if matched, does not contribute any character. For case-insensitive
+    /// expressions, both cases of each letter are contained. If a match may start with
+    /// a shorthand class such as `\d`, a wildcard or any character, the set is too large
+    /// to be listed and `None` is returned.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if a limit is exceeded.
+    pub fn build_first_chars(&mut self) -> Option<BTreeSet<char>> {
+        match self.regexp() {
+            Ok(regexp) => regexp.first_chars(),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// and returns it together with the longest literal text which all of its matches start with.
+    /// Scanning for this text is much cheaper than running the regular expression, so it can
+    /// serve as a prefilter which skips positions that cannot start a match. For instance,
+    /// the test cases `abcd` and `abce` result in `^abc[de]$` and the required prefix `abc`.
+    ///
+    /// The prefix ends at the first alternation, character class or optional part of the
+    /// expression, so it is empty if the expression starts with one of them. For case-insensitive
+    /// expressions, it ends before the first cased letter.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if a limit is exceeded.
+    pub fn build_with_required_prefix(&mut self) -> (String, String) {
+        match self.regexp() {
+            Ok(regexp) => (regexp.to_string(), regexp.required_prefix()),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// but returns a nondeterministic finite automaton accepting exactly the strings which
+    /// the expression matches as a whole, instead of the expression itself. The automaton
+    /// is built from the expression by Thompson's construction and is useful for composing
+    /// it with other automata or for checking which strings it matches without a regex engine.
+    ///
+    /// Anchors are not part of the automaton, which always accepts entire strings only.
+    /// Word boundaries inserted by [`with_inner_word_boundaries`](./struct.RegExpBuilder.html#method.with_inner_word_boundaries)
+    /// cannot be expressed by transitions and are ignored.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if a limit is exceeded.
+    pub fn build_nfa(&mut self) -> Nfa {
+        match self.regexp() {
+            Ok(regexp) => regexp.nfa(),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// but returns the branches of its top-level alternation instead of the expression itself,
+    /// such as `abc`, `xy` and `d` for `^(?:abc|xy|d)$`. This is useful for presenting the
+    /// alternatives separately, for instance in a user interface.
+    ///
+    /// The branches are written without anchors and flags. If the expression is not an
+    /// alternation at the top level, it is returned as the only branch, such as `a(?:bc)?`
+    /// for `^a(?:bc)?$`. Branches matching single characters are merged into character
+    /// classes during the generation, so they appear as a single branch such as `[ab]`.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if a limit is exceeded.
+    pub fn build_alternatives(&mut self) -> Vec<String> {
+        match self.regexp() {
+            Ok(regexp) => regexp.alternatives(),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// and returns it together with the conversion features which have actually changed it.
+    /// This is useful for logging or auditing which of the features passed to
+    /// [`with_conversion_of`](./struct.RegExpBuilder.html#method.with_conversion_of) took effect.
+    ///
+    /// A feature is reported if the expression contains what it produces, for instance
+    /// [`Feature::Digit`](./enum.Feature.html#variant.Digit) if it contains `\d`,
+    /// [`Feature::Repetition`](./enum.Feature.html#variant.Repetition) if it contains
+    /// a counted repetition such as `{2}`,
+    /// [`Feature::CaseInsensitivity`](./enum.Feature.html#variant.CaseInsensitivity)
+    /// if it contains a cased letter and
+    /// [`Feature::CapturingGroup`](./enum.Feature.html#variant.CapturingGroup)
+    /// if it contains a group. For instance, the test cases `1` and `22` with digit
+    /// conversion result in `^\d(?:\d)?$` and `Feature::Digit` only.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if a limit is exceeded.
+    pub fn build_with_applied_conversions(&mut self) -> (String, BTreeSet<Feature>) {
+        match self.regexp() {
+            Ok(regexp) => (regexp.to_string(), regexp.applied_conversions()),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// but returns warnings about the ways in which it matches more than the test cases
+    /// instead of the expression itself. These are informational only and help to decide
+    /// whether the trade-off of a shorter expression is acceptable.
+    ///
+    /// Each warning is a separate string, such as `introduced '\d' which matches any digit`
+    /// for digit conversion or `introduced '{1,3}' which repeats 'a' any number of times
+    /// from 1 to 3` for repetition conversion. Unbounded quantifiers and wildcards are
+    /// reported as well. If the expression matches exactly the test cases,
+    /// the returned vector is empty.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if a limit is exceeded.
+    pub fn build_warnings(&mut self) -> Vec<String> {
+        match self.regexp() {
+            Ok(regexp) => regexp.warnings(),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Returns hints about test cases for which the generated regular expression is bound
+    /// to be unwieldy, without building the expression itself. The expression is not
+    /// affected by them, they only suggest to solve the problem at hand differently.
+    ///
+    /// Currently, this concerns test cases which are permutations of the same characters,
+    /// such as `abc`, `acb`, `bac`, `bca`, `cab` and `cba`. Regular expressions cannot count
+    /// characters, so each permutation has to be spelled out in a large alternation.
+    /// A hint is returned for each group of at least six permutations, such as
+    /// `6 test cases such as 'abc', 'acb', 'bac' are permutations of the same characters, ...`.
+    /// If there is nothing to point out, the returned vector is empty.
+    ///
+    /// ⚠ Panics if no test cases have been provided or if conflicting options have been specified.
+    pub fn build_diagnostics(&mut self) -> Vec<String> {
+        match self.test_cases_with_config(&self.config, None) {
+            Ok((test_cases, _)) => permutation_diagnostics(&test_cases),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds the actual regular expression like [`build`](./struct.RegExpBuilder.html#method.build)
+    /// but returns the flags which have to be passed to the regular expression engine of the
+    /// target language selected with [`with_output_wrapper`](./struct.RegExpBuilder.html#method.with_output_wrapper)
+    /// instead of the expression itself.
+    ///
+    /// Currently, this only concerns JavaScript, which requires the `u` flag as in
+    /// `new RegExp(regexp, "u")` as soon as the expression contains unicode escape sequences
+    /// such as `\u{e9}` or astral characters such as `💩`. Without it, escape sequences
+    /// with braces are not recognized and astral characters are treated as two separate
+    /// code units. For all other output wrappers, the returned set is empty.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if a limit is exceeded.
+    pub fn build_required_flags(&mut self) -> BTreeSet<char> {
+        match self.regexp() {
+            Ok(regexp) => regexp.required_flags(),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Builds alternative regular expressions for the same test cases and returns
+    /// up to `count` distinct ones, the shortest first. This is useful for exploring
+    /// which expression reads best.
+    ///
+    /// The alternatives are produced by varying the settings which do not change
+    /// the matched language: the factoring of common prefixes and suffixes
+    /// (see [`with_factoring_of_common_affixes`](./struct.RegExpBuilder.html#method.with_factoring_of_common_affixes)),
+    /// the minimum length of factored affixes (see
+    /// [`with_minimum_common_substring_length`](./struct.RegExpBuilder.html#method.with_minimum_common_substring_length))
+    /// and the preference of the Kleene star (see
+    /// [`with_kleene_star_preferred`](./struct.RegExpBuilder.html#method.with_kleene_star_preferred)).
+    /// All other settings are kept. Expressions of equal length are listed in the order
+    /// in which they have been built, starting with the one [`build`](./struct.RegExpBuilder.html#method.build)
+    /// returns. The progress callback is not invoked for the alternatives.
+    ///
+    /// ⚠ Panics if no test cases have been provided, if conflicting options have been specified
+    /// or if a limit is exceeded.
+    pub fn build_candidates(&mut self, count: usize) -> Vec<String> {
+        let mut configs = vec![self.config.clone()];
+        for (prefixes, suffixes) in [(true, true), (true, false), (false, true), (false, false)] {
+            for is_kleene_star_preferred in [false, true] {
+                for minimum_common_substring_length in [1, 2] {
+                    let mut config = self.config.clone();
+                    config.is_common_prefix_factored = prefixes;
+                    config.is_common_suffix_factored = suffixes;
+                    config.is_kleene_star_preferred = is_kleene_star_preferred;
+                    config.minimum_common_substring_length = minimum_common_substring_length;
+                    configs.push(config);
+                }
+            }
+        }
+
+        let mut candidates = vec![];
+        for config in configs {
+            match self.regexp_with_config(&config, None) {
+                Ok(regexp) => candidates.push(regexp.to_string()),
+                Err(error) => panic!("{}", error),
+            }
+        }
+        candidates
+            .into_iter()
+            .unique()
+            .sorted_by_key(|it| it.chars().count())
+            .take(count)
+            .collect_vec()
+    }
+
+    fn regexp(&mut self) -> Result<RegExp, Error> {
+        self.regexp_with_config(
+            &self.config,
+            self.progress_callback
+                .as_deref()
+                .map(|callback| callback as &dyn Fn(ProgressEvent)),
+        )
+    }
+
+    fn regexp_with_config(
+        &self,
+        config: &RegExpConfig,
+        progress: Option<&dyn Fn(ProgressEvent)>,
+    ) -> Result<RegExp, Error> {
+        let (mut test_cases, mut negative_test_cases) =
+            self.test_cases_with_config(config, progress)?;
+        RegExp::from(&mut test_cases, &mut negative_test_cases, config, progress)
+    }
+
+    fn dfa(&self) -> Result<Dfa, Error> {
+        let (mut test_cases, mut negative_test_cases) =
+            self.test_cases_with_config(&self.config, None)?;
+        RegExp::dfa(&mut test_cases, &mut negative_test_cases, &self.config)
+    }
+
+    /// Returns the test cases and the negative test cases to build the expression from,
+    /// after the input filter, the minimum weight and the preprocessor have been applied.
+    #[allow(clippy::type_complexity)]
+    fn test_cases_with_config(
+        &self,
+        config: &RegExpConfig,
+        progress: Option<&dyn Fn(ProgressEvent)>,
+    ) -> Result<(Vec<Cow<'_, str>>, Vec<Cow<'_, str>>), Error> {
+        if self.test_cases.is_empty() {
+            return Err(Error::NoTestCases { requirement: None });
+        }
+        config.validate()?;
+        let selected_test_cases = self
+            .test_cases
+            .iter()
+            .filter_map(|it| match config.column {
+                Some((delimiter, index)) => {
+                    select_column(it, delimiter, index).map(|field| (it, field))
+                }
+                None => Some((it, Cow::Borrowed(it.as_str()))),
+            })
+            .collect_vec();
+        if selected_test_cases.is_empty() {
+            return Err(Error::NoTestCases {
+                requirement: Some("containing the selected column"),
+            });
+        }
+        let (accepted_test_cases, rejected_test_cases): (Vec<_>, Vec<_>) = selected_test_cases
+            .into_iter()
+            .partition(|(_, field)| match &self.input_filter {
+                Some(input_filter) => input_filter(field),
+                None => true,
+            });
+        if let (Some(progress), Some(_)) = (progress, &self.input_filter) {
+            progress(ProgressEvent::TestCasesFiltered {
+                excluded_count: rejected_test_cases.len(),
+            });
+        }
+        if accepted_test_cases.is_empty() {
+            return Err(Error::NoTestCases {
+                requirement: Some("passing the input filter"),
+            });
+        }
+        let preprocessed_test_cases = accepted_test_cases
+            .into_iter()
+            .map(|(it, field)| {
+                let field = match &self.preprocessor {
+                    Some(preprocessor) => Cow::Owned(preprocessor(&field)),
+                    None => field,
+                };
+                (field, self.weights.get(it).copied())
+            })
+            .collect_vec();
+        // Test cases which are equal after preprocessing, such as differently normalized
+        // forms of the same string, are a single test case whose weights add up.
+        let mut summed_weights = HashMap::<&str, u64>::new();
+        for (field, weight) in preprocessed_test_cases.iter() {
+            if let Some(weight) = weight {
+                *summed_weights.entry(field).or_insert(0) += weight;
+            }
+        }
+        let test_cases = preprocessed_test_cases
+            .iter()
+            .filter(|(field, weight)| {
+                weight.is_none() || summed_weights[field.as_ref()] >= config.minimum_weight
+            })
+            .map(|(field, _)| field.clone())
+            .collect_vec();
+        if test_cases.is_empty() {
+            return Err(Error::NoTestCases {
+                requirement: Some("with the minimum weight"),
+            });
+        }
+        let negative_test_cases = self
+            .negative_test_cases
+            .iter()
+            .map(|it| match &self.preprocessor {
+                Some(preprocessor) => Cow::Owned(preprocessor(it)),
+                None => Cow::Borrowed(it.as_str()),
+            })
+            .collect_vec();
+        Ok((test_cases, negative_test_cases))
+    }
+}
+
+/// Returns the field at the zero-based `index` of a line of delimiter-separated values,
+/// or `None` if the line has fewer fields.
+fn select_column(line: &str, delimiter: char, index: usize) -> Option<Cow<'_, str>> {
+    let mut rest = line;
+    for _ in 0..index {
+        rest = split_field(rest, delimiter).1?;
+    }
+    Some(split_field(rest, delimiter).0)
+}
+
+/// Splits off the first field of a line of delimiter-separated values and returns it
+/// together with the rest of the line after the delimiter, if there is any. Characters
+/// following the closing quote of a quoted field are appended to the field.
+fn split_field(line: &str, delimiter: char) -> (Cow<'_, str>, Option<&str>) {
+    if let Some(quoted_line) = line.strip_prefix('"') {
+        let mut field = String::new();
+        let mut chars = quoted_line.char_indices().peekable();
+
+        while let Some((idx, c)) = chars.next() {
+            if c != '"' {
+                field.push(c);
+            } else if chars.next_if(|&(_, next)| next == '"').is_some() {
+                field.push('"');
+            } else {
+                let rest = &quoted_line[idx + 1..];
+                return match rest.split_once(delimiter) {
+                    Some((tail, rest)) => {
+                        field.push_str(tail);
+                        (Cow::Owned(field), Some(rest))
+                    }
+                    None => {
+                        field.push_str(rest);
+                        (Cow::Owned(field), None)
+                    }
+                };
+            }
+        }
+        // An unclosed quote extends the field to the end of the line.
+        return (Cow::Owned(field), None);
+    }
+
+    match line.split_once(delimiter) {
+        Some((field, rest)) => (Cow::Borrowed(field), Some(rest)),
+        None => (Cow::Borrowed(line), None),
+    }
+}
+
+impl Default for RegExpBuilder {
+    fn default() -> Self {
+        Self::new()
     }
 }
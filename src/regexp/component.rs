@@ -15,19 +15,26 @@
  */
 
 use crate::ast::Quantifier;
+use crate::regexp::{GroupMarker, RegExpConfig};
 use std::fmt::{Display, Formatter, Result};
 
 pub enum Component {
     Asterisk,
+    AtomicLeftParenthesis,
+    AtomicParenthesizedExpression(String),
     CapturedLeftParenthesis,
     CapturedParenthesizedExpression(String),
     Caret,
     CharClass(String),
     DollarSign,
+    Dot,
+    DotMatchingNewline,
+    DotExcludingNewline,
     Hyphen,
-    IgnoreCaseFlag,
-    IgnoreCaseAndVerboseModeFlag,
+    InlineFlags(String),
     LeftBracket,
+    NamedLeftParenthesis(String),
+    NamedParenthesizedExpression(String, String),
     Pipe,
     Quantifier(Quantifier),
     QuestionMark,
@@ -37,10 +44,30 @@ pub enum Component {
     RightParenthesis,
     UncapturedLeftParenthesis,
     UncapturedParenthesizedExpression(String),
-    VerboseModeFlag,
+    WordBoundary,
 }
 
 impl Component {
+    /// Returns `expr` enclosed in a group of the syntax selected by `config`.
+    /// Every expression which has to be grouped is written by this function,
+    /// so that all groups of the regular expression share the same syntax.
+    ///
+    /// An atomic group is only written if `expr` matches strings of a single length
+    /// (`is_fixed_length`). Any other expression, such as an alternation, is written as
+    /// a non-capturing group instead, because an atomic group would keep the first
+    /// alternative that matches even if the remainder of the expression then fails.
+    pub(crate) fn group(expr: String, is_fixed_length: bool, config: &RegExpConfig) -> String {
+        let group = match config.group_marker() {
+            GroupMarker::NonCapturing => Component::UncapturedParenthesizedExpression(expr),
+            GroupMarker::Atomic if is_fixed_length => {
+                Component::AtomicParenthesizedExpression(expr)
+            }
+            GroupMarker::Atomic => Component::UncapturedParenthesizedExpression(expr),
+            GroupMarker::Plain => Component::CapturedParenthesizedExpression(expr),
+        };
+        group.to_repr(config.is_output_colorized)
+    }
+
     pub fn to_repr(&self, is_output_colorized: bool) -> String {
         match is_output_colorized {
             true => self.to_colored_string(false),
@@ -51,6 +78,15 @@ impl Component {
     pub fn to_colored_string(&self, is_escaped: bool) -> String {
         match self {
             Component::Asterisk => Self::purple_bold(&self.to_string(), is_escaped),
+            Component::AtomicLeftParenthesis => Self::green_bold(&self.to_string(), is_escaped),
+            Component::AtomicParenthesizedExpression(expr) => {
+                format!(
+                    "{}{}{}",
+                    Component::AtomicLeftParenthesis.to_colored_string(is_escaped),
+                    expr,
+                    Component::RightParenthesis.to_colored_string(is_escaped)
+                )
+            }
             Component::CapturedLeftParenthesis => Self::green_bold(&self.to_string(), is_escaped),
             Component::CapturedParenthesizedExpression(expr) => {
                 format!(
@@ -63,14 +99,27 @@ impl Component {
             Component::Caret => Self::yellow_bold(&self.to_string(), is_escaped),
             Component::CharClass(value) => Self::black_on_bright_yellow(value, is_escaped),
             Component::DollarSign => Self::yellow_bold(&self.to_string(), is_escaped),
-            Component::Hyphen => Self::cyan_bold(&self.to_string(), is_escaped),
-            Component::IgnoreCaseFlag => {
-                Self::bright_yellow_on_black(&self.to_string(), is_escaped)
+            Component::Dot => Self::black_on_bright_yellow(&self.to_string(), is_escaped),
+            Component::DotMatchingNewline => {
+                Self::black_on_bright_yellow(&self.to_string(), is_escaped)
+            }
+            Component::DotExcludingNewline => {
+                Self::black_on_bright_yellow(&self.to_string(), is_escaped)
             }
-            Component::IgnoreCaseAndVerboseModeFlag => {
+            Component::Hyphen => Self::cyan_bold(&self.to_string(), is_escaped),
+            Component::InlineFlags(_) => {
                 Self::bright_yellow_on_black(&self.to_string(), is_escaped)
             }
             Component::LeftBracket => Self::cyan_bold(&self.to_string(), is_escaped),
+            Component::NamedLeftParenthesis(_) => Self::green_bold(&self.to_string(), is_escaped),
+            Component::NamedParenthesizedExpression(name, expr) => {
+                format!(
+                    "{}{}{}",
+                    Component::NamedLeftParenthesis(name.clone()).to_colored_string(is_escaped),
+                    expr,
+                    Component::RightParenthesis.to_colored_string(is_escaped)
+                )
+            }
             Component::Pipe => Self::red_bold(&self.to_string(), is_escaped),
             Component::Quantifier(_) => Self::purple_bold(&self.to_string(), is_escaped),
             Component::QuestionMark => Self::purple_bold(&self.to_string(), is_escaped),
@@ -89,9 +138,7 @@ impl Component {
                     Component::RightParenthesis.to_colored_string(is_escaped)
                 )
             }
-            Component::VerboseModeFlag => {
-                Self::bright_yellow_on_black(&self.to_string(), is_escaped)
-            }
+            Component::WordBoundary => Self::yellow_bold(&self.to_string(), is_escaped),
         }
     }
 
@@ -143,20 +190,36 @@ impl Display for Component {
             "{}",
             match self {
                 Component::Asterisk => "*".to_string(),
+                Component::AtomicLeftParenthesis => "(?>".to_string(),
+                Component::AtomicParenthesizedExpression(expr) => format!(
+                    "{}{}{}",
+                    Component::AtomicLeftParenthesis,
+                    expr,
+                    Component::RightParenthesis
+                ),
                 Component::CapturedLeftParenthesis => "(".to_string(),
                 Component::CapturedParenthesizedExpression(expr) => format!(
                     "{}{}{}",
                     Component::CapturedLeftParenthesis,
-                    expr.to_string(),
+                    expr,
                     Component::RightParenthesis
                 ),
                 Component::Caret => "^".to_string(),
                 Component::CharClass(value) => value.clone(),
                 Component::DollarSign => "$".to_string(),
+                Component::Dot => ".".to_string(),
+                Component::DotMatchingNewline => "(?s:.)".to_string(),
+                Component::DotExcludingNewline => "[^\\n]".to_string(),
                 Component::Hyphen => "-".to_string(),
-                Component::IgnoreCaseFlag => "(?i)".to_string(),
-                Component::IgnoreCaseAndVerboseModeFlag => "(?ix)".to_string(),
+                Component::InlineFlags(flags) => format!("(?{})", flags),
                 Component::LeftBracket => "[".to_string(),
+                Component::NamedLeftParenthesis(name) => format!("(?P<{}>", name),
+                Component::NamedParenthesizedExpression(name, expr) => format!(
+                    "{}{}{}",
+                    Component::NamedLeftParenthesis(name.clone()),
+                    expr,
+                    Component::RightParenthesis
+                ),
                 Component::Pipe => "|".to_string(),
                 Component::Quantifier(quantifier) => quantifier.to_string(),
                 Component::QuestionMark => "?".to_string(),
@@ -181,7 +244,7 @@ impl Display for Component {
                     expr,
                     Component::RightParenthesis
                 ),
-                Component::VerboseModeFlag => "(?x)".to_string(),
+                Component::WordBoundary => "\\b".to_string(),
             }
         )
     }
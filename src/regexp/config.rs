@@ -14,32 +14,330 @@
  * limitations under the License.
  */
 
-use crate::regexp::Feature;
+use crate::regexp::{
+    Anchor, Component, Error, Feature, Flavor, GroupMarker, LeadingZeros, OutputWrapper, Preset,
+};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
 
+/// This struct holds the settings with which regular expressions are built.
+/// It is configured by [`RegExpBuilder`](./struct.RegExpBuilder.html) and can be passed
+/// to [`escape`](./fn.escape.html) in order to escape strings in the same way.
 #[derive(Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
 pub struct RegExpConfig {
     pub(crate) conversion_features: Vec<Feature>,
+    pub(crate) start_anchor: Anchor,
+    pub(crate) end_anchor: Anchor,
     pub(crate) minimum_repetitions: u32,
     pub(crate) minimum_substring_length: u32,
+    pub(crate) minimum_class_size: usize,
+    pub(crate) minimum_common_substring_length: usize,
+    pub(crate) length_bucket_width: Option<usize>,
+    pub(crate) maximum_repetition_count: Option<u32>,
+    pub(crate) maximum_alternation_branches: Option<u32>,
+    pub(crate) is_alternation_fallback_replaced_by_error: bool,
+    pub(crate) maximum_dfa_states: Option<usize>,
+    pub(crate) maximum_nesting_depth: Option<usize>,
+    pub(crate) maximum_expression_size: Option<usize>,
+    pub(crate) time_budget: Option<Duration>,
+    pub(crate) minimum_weight: u64,
+    pub(crate) is_common_prefix_factored: bool,
+    pub(crate) is_common_suffix_factored: bool,
+    pub(crate) is_prefix_branch_kept_distinct: bool,
+    pub(crate) is_positional_inference_enabled: bool,
+    pub(crate) dash_replacement: Option<char>,
+    pub(crate) column: Option<(char, usize)>,
+    pub(crate) optional_markers: Option<(char, char)>,
+    pub(crate) is_dash_class_extended: bool,
+    pub(crate) separators: Option<BTreeSet<char>>,
+    pub(crate) wildcard: Option<char>,
+    pub(crate) is_wildcard_whitespace_excluded: bool,
+    pub(crate) is_word_boundary_inserted: bool,
+    pub(crate) is_alternation_repeat_collapsed: bool,
+    pub(crate) is_prefix_matched: bool,
+    pub(crate) is_substring_matched: bool,
     pub(crate) is_non_ascii_char_escaped: bool,
     pub(crate) is_astral_code_point_converted_to_surrogate: bool,
+    pub(crate) is_posix_char_class_used: bool,
+    pub(crate) unicode_block_flavor: Option<Flavor>,
+    pub(crate) is_unicode_script_name_used: bool,
+    pub(crate) is_dot_matching_newline: bool,
+    pub(crate) is_dot_all_flag_leading: bool,
+    pub(crate) is_multiline_matching: bool,
+    pub(crate) is_newline_exclusion_explicit: bool,
+    pub(crate) is_case_expanded_into_char_classes: bool,
+    pub(crate) is_char_class_order_preserved: bool,
+    pub(crate) char_class_alphabet: Option<BTreeSet<char>>,
+    pub(crate) char_positions: BTreeMap<char, usize>,
+    pub(crate) is_char_class_ordered_by_frequency: bool,
+    pub(crate) char_frequencies: BTreeMap<char, usize>,
+    pub(crate) is_control_char_hex_escaped: bool,
+    pub(crate) is_quantifier_braced: bool,
+    pub(crate) is_literal_quoted: bool,
+    pub(crate) is_kleene_star_preferred: bool,
+    pub(crate) is_repetition_disabled: bool,
     pub(crate) is_verbose_mode_enabled: bool,
+    pub(crate) is_test_case_comment_added: bool,
+    pub(crate) alternation_separator: Option<String>,
+    pub(crate) is_branch_named: bool,
     pub(crate) is_output_colorized: bool,
+    pub(crate) output_wrapper: OutputWrapper,
+    pub(crate) group_marker: GroupMarker,
+    pub(crate) leading_zeros: LeadingZeros,
 }
 
 impl RegExpConfig {
     pub(crate) fn new() -> Self {
         Self {
             conversion_features: vec![],
+            start_anchor: Anchor::StringBoundary,
+            end_anchor: Anchor::StringBoundary,
             minimum_repetitions: 1,
             minimum_substring_length: 1,
+            minimum_class_size: 2,
+            minimum_common_substring_length: 1,
+            length_bucket_width: None,
+            maximum_repetition_count: None,
+            maximum_alternation_branches: None,
+            is_alternation_fallback_replaced_by_error: false,
+            maximum_dfa_states: None,
+            maximum_nesting_depth: None,
+            maximum_expression_size: None,
+            time_budget: None,
+            minimum_weight: 0,
+            is_common_prefix_factored: true,
+            is_common_suffix_factored: true,
+            is_prefix_branch_kept_distinct: false,
+            is_positional_inference_enabled: false,
+            dash_replacement: None,
+            column: None,
+            optional_markers: None,
+            is_dash_class_extended: false,
+            separators: None,
+            wildcard: None,
+            is_wildcard_whitespace_excluded: false,
+            is_word_boundary_inserted: false,
+            is_alternation_repeat_collapsed: false,
+            is_prefix_matched: false,
+            is_substring_matched: false,
             is_non_ascii_char_escaped: false,
             is_astral_code_point_converted_to_surrogate: false,
+            is_posix_char_class_used: false,
+            unicode_block_flavor: None,
+            is_unicode_script_name_used: false,
+            is_dot_matching_newline: false,
+            is_dot_all_flag_leading: false,
+            is_multiline_matching: false,
+            is_newline_exclusion_explicit: false,
+            is_case_expanded_into_char_classes: false,
+            is_char_class_order_preserved: false,
+            char_class_alphabet: None,
+            char_positions: BTreeMap::new(),
+            is_char_class_ordered_by_frequency: false,
+            char_frequencies: BTreeMap::new(),
+            is_control_char_hex_escaped: false,
+            is_quantifier_braced: false,
+            is_literal_quoted: false,
+            is_kleene_star_preferred: false,
+            is_repetition_disabled: false,
             is_verbose_mode_enabled: false,
+            is_test_case_comment_added: false,
+            alternation_separator: None,
+            is_branch_named: false,
             is_output_colorized: false,
+            output_wrapper: OutputWrapper::None,
+            group_marker: GroupMarker::NonCapturing,
+            leading_zeros: LeadingZeros::Kept,
         }
     }
 
+    /// Creates the default settings with the given [`Preset`](./enum.Preset.html) applied.
+    /// The settings can be passed to [`escape`](./fn.escape.html), for instance.
+    pub fn preset(preset: Preset) -> Self {
+        let mut config = Self::new();
+        config.apply_preset(preset);
+        config
+    }
+
+    pub(crate) fn apply_preset(&mut self, preset: Preset) {
+        match preset {
+            Preset::Readable => {
+                self.conversion_features = vec![Feature::Repetition];
+                self.minimum_substring_length = 2;
+                self.is_kleene_star_preferred = true;
+            }
+            Preset::Compact => {
+                self.conversion_features = vec![
+                    Feature::Digit,
+                    Feature::Space,
+                    Feature::Word,
+                    Feature::Repetition,
+                ];
+                self.minimum_substring_length = 1;
+                self.is_kleene_star_preferred = true;
+            }
+            Preset::Portable => {
+                self.conversion_features = vec![];
+                self.is_non_ascii_char_escaped = true;
+                self.is_astral_code_point_converted_to_surrogate = true;
+                self.is_control_char_hex_escaped = true;
+            }
+            Preset::Verbose => {
+                self.conversion_features = vec![Feature::Repetition];
+                self.is_verbose_mode_enabled = true;
+                self.is_test_case_comment_added = true;
+            }
+        }
+    }
+
+    /// Checks all pairs of mutually exclusive settings in one place
+    /// and returns an error for the first conflicting pair found.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        let conflicting_options = [
+            (
+                "with_replacement_of_dashes",
+                "with_extension_of_dash_classes",
+                self.dash_replacement.is_some() && self.is_dash_class_extended,
+            ),
+            (
+                "with_separators",
+                "with_inner_word_boundaries",
+                self.separators.is_some() && self.is_word_boundary_inserted,
+            ),
+            (
+                "with_posix_char_classes",
+                "with_inner_word_boundaries",
+                self.is_posix_char_class_used && self.is_word_boundary_inserted,
+            ),
+            (
+                "with_posix_char_classes",
+                "with_anchors",
+                self.is_posix_char_class_used
+                    && (self.start_anchor == Anchor::WordBoundary
+                        || self.end_anchor == Anchor::WordBoundary),
+            ),
+            (
+                "with_positional_inference",
+                "with_prefix_matching",
+                self.is_positional_inference_enabled && self.is_prefix_matched,
+            ),
+            (
+                "with_positional_inference",
+                "with_substring_matching",
+                self.is_positional_inference_enabled && self.is_substring_matched,
+            ),
+            (
+                "with_posix_char_classes",
+                "with_unicode_block_names",
+                self.is_posix_char_class_used && self.unicode_block_flavor.is_some(),
+            ),
+            (
+                "with_posix_char_classes",
+                "with_unicode_script_names",
+                self.is_posix_char_class_used && self.is_unicode_script_name_used,
+            ),
+            (
+                "with_posix_char_classes",
+                "with_dot_matching_newlines",
+                self.is_posix_char_class_used && self.is_dot_matching_newline,
+            ),
+            (
+                "with_posix_char_classes",
+                "with_multiline_matching",
+                self.is_posix_char_class_used && self.is_multiline_matching,
+            ),
+            (
+                "with_dot_matching_newlines",
+                "with_explicit_newline_exclusion",
+                self.is_dot_matching_newline && self.is_newline_exclusion_explicit,
+            ),
+            (
+                "with_posix_char_classes",
+                "with_explicit_newline_exclusion",
+                self.is_posix_char_class_used && self.is_newline_exclusion_explicit,
+            ),
+            (
+                "with_posix_char_classes",
+                "with_named_branches",
+                self.is_posix_char_class_used && self.is_branch_named,
+            ),
+            (
+                "with_positional_inference",
+                "with_length_buckets",
+                self.is_positional_inference_enabled && self.length_bucket_width.is_some(),
+            ),
+            (
+                "with_posix_char_classes",
+                "with_literal_quoting",
+                self.is_posix_char_class_used && self.is_literal_quoted,
+            ),
+            (
+                "with_verbose_mode",
+                "with_literal_quoting",
+                self.is_verbose_mode_enabled && self.is_literal_quoted,
+            ),
+            (
+                "with_wildcard",
+                "with_disabled_repetitions",
+                self.wildcard.is_some() && self.is_repetition_disabled,
+            ),
+            (
+                "with_maximum_alternation_branches",
+                "with_disabled_repetitions",
+                self.maximum_alternation_branches.is_some()
+                    && !self.is_alternation_fallback_replaced_by_error
+                    && self.is_repetition_disabled,
+            ),
+            (
+                "with_char_classes_in_order_of_appearance",
+                "with_char_classes_in_order_of_frequency",
+                self.is_char_class_order_preserved && self.is_char_class_ordered_by_frequency,
+            ),
+            (
+                "with_optional_markers",
+                "with_length_buckets",
+                self.optional_markers.is_some() && self.length_bucket_width.is_some(),
+            ),
+            (
+                "with_optional_markers",
+                "with_maximum_alternation_branches",
+                self.optional_markers.is_some()
+                    && self.maximum_alternation_branches.is_some()
+                    && !self.is_alternation_fallback_replaced_by_error,
+            ),
+        ];
+
+        match conflicting_options
+            .iter()
+            .find(|(_, _, is_conflicting)| *is_conflicting)
+        {
+            Some(&(a, b, _)) => Err(Error::ConflictingOptions { a, b }),
+            None => Ok(()),
+        }
+    }
+
+    /// Records the position at which each character first appears in the test cases,
+    /// so that character classes can be rendered in order of appearance.
+    pub(crate) fn with_char_positions(&self, test_cases: &[Cow<str>]) -> Self {
+        let mut config = self.clone();
+        for c in test_cases.iter().flat_map(|it| it.chars()) {
+            let position = config.char_positions.len();
+            config.char_positions.entry(c).or_insert(position);
+        }
+        config
+    }
+
+    /// Counts how often each character appears in the test cases,
+    /// so that character classes can be rendered in order of frequency.
+    pub(crate) fn with_char_frequencies(&self, test_cases: &[Cow<str>]) -> Self {
+        let mut config = self.clone();
+        for c in test_cases.iter().flat_map(|it| it.chars()) {
+            *config.char_frequencies.entry(c).or_insert(0) += 1;
+        }
+        config
+    }
+
     pub(crate) fn is_digit_converted(&self) -> bool {
         self.conversion_features.contains(&Feature::Digit)
     }
@@ -64,8 +362,19 @@ impl RegExpConfig {
         self.conversion_features.contains(&Feature::NonWord)
     }
 
+    pub(crate) fn is_hex_converted(&self) -> bool {
+        self.conversion_features.contains(&Feature::Hex)
+    }
+
     pub(crate) fn is_repetition_converted(&self) -> bool {
-        self.conversion_features.contains(&Feature::Repetition)
+        !self.is_repetition_disabled && self.conversion_features.contains(&Feature::Repetition)
+    }
+
+    pub(crate) fn is_repetition_count_exceeded(&self, count: u32) -> bool {
+        match self.maximum_repetition_count {
+            Some(limit) => count > limit,
+            None => false,
+        }
     }
 
     pub(crate) fn is_case_insensitive_matching(&self) -> bool {
@@ -73,11 +382,122 @@ impl RegExpConfig {
             .contains(&Feature::CaseInsensitivity)
     }
 
+    /// Whether case-insensitivity is expressed by character classes such as `[Hh]`
+    /// instead of by the inline flag `(?i)`.
+    pub(crate) fn is_case_insensitivity_expanded(&self) -> bool {
+        self.is_case_insensitive_matching() && self.is_case_expanded_into_char_classes
+    }
+
+    /// Returns the inline flags which are written in front of the expression,
+    /// all of them in a single group in the canonical order `imsx`.
+    pub(crate) fn inline_flags(&self) -> String {
+        [
+            (
+                'i',
+                self.is_case_insensitive_matching() && !self.is_case_insensitivity_expanded(),
+            ),
+            ('m', self.is_multiline_matching),
+            (
+                's',
+                self.is_dot_matching_newline && self.is_dot_all_flag_leading,
+            ),
+            ('x', self.is_verbose_mode_enabled),
+        ]
+        .iter()
+        .filter(|(_, is_enabled)| *is_enabled)
+        .map(|(flag, _)| flag)
+        .collect()
+    }
+
+    /// Returns the dot matching any character. If it has to match line breaks
+    /// and the inline flag `s` is not written in front of the expression,
+    /// the flag is applied to the dot alone.
+    pub(crate) fn dot(&self) -> Component {
+        if self.is_dot_matching_newline && !self.is_dot_all_flag_leading {
+            Component::DotMatchingNewline
+        } else if self.is_newline_exclusion_explicit {
+            Component::DotExcludingNewline
+        } else {
+            Component::Dot
+        }
+    }
+
     pub(crate) fn is_capturing_group_enabled(&self) -> bool {
-        self.conversion_features.contains(&Feature::CapturingGroup)
+        self.group_marker() == GroupMarker::Plain
+    }
+
+    /// Returns the syntax of the groups enclosing subexpressions.
+    /// Converting capturing groups takes precedence over the selected group marker.
+    pub(crate) fn group_marker(&self) -> GroupMarker {
+        if self.conversion_features.contains(&Feature::CapturingGroup) {
+            GroupMarker::Plain
+        } else {
+            self.group_marker
+        }
     }
 
     pub(crate) fn is_char_class_feature_enabled(&self) -> bool {
         self.conversion_features.iter().any(|it| it.is_char_class())
     }
 }
+
+impl Default for RegExpConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ensure_correct_settings_of_readable_preset() {
+        let config = RegExpConfig::preset(Preset::Readable);
+        assert_eq!(config.conversion_features, vec![Feature::Repetition]);
+        assert_eq!(config.minimum_substring_length, 2);
+        assert!(config.is_kleene_star_preferred);
+    }
+
+    #[test]
+    fn ensure_correct_settings_of_compact_preset() {
+        let config = RegExpConfig::preset(Preset::Compact);
+        assert_eq!(
+            config.conversion_features,
+            vec![
+                Feature::Digit,
+                Feature::Space,
+                Feature::Word,
+                Feature::Repetition
+            ]
+        );
+        assert_eq!(config.minimum_substring_length, 1);
+        assert!(config.is_kleene_star_preferred);
+    }
+
+    #[test]
+    fn ensure_correct_settings_of_portable_preset() {
+        let config = RegExpConfig::preset(Preset::Portable);
+        assert!(config.conversion_features.is_empty());
+        assert!(config.is_non_ascii_char_escaped);
+        assert!(config.is_astral_code_point_converted_to_surrogate);
+        assert!(config.is_control_char_hex_escaped);
+    }
+
+    #[test]
+    fn ensure_correct_settings_of_verbose_preset() {
+        let config = RegExpConfig::preset(Preset::Verbose);
+        assert_eq!(config.conversion_features, vec![Feature::Repetition]);
+        assert!(config.is_verbose_mode_enabled);
+        assert!(config.is_test_case_comment_added);
+    }
+
+    #[test]
+    fn ensure_presets_leave_remaining_settings_untouched() {
+        let config = RegExpConfig::preset(Preset::Readable);
+        assert_eq!(config.start_anchor, Anchor::StringBoundary);
+        assert_eq!(config.end_anchor, Anchor::StringBoundary);
+        assert!(!config.is_verbose_mode_enabled);
+        assert_eq!(config.output_wrapper, OutputWrapper::None);
+    }
+}
@@ -0,0 +1,63 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use itertools::Itertools;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// The number of permutations of three distinct characters, below which an alternation
+/// of permutations is still short enough to be readable.
+const MINIMUM_PERMUTATION_COUNT: usize = 6;
+
+/// Describes each group of test cases which are permutations of the same characters, such as
+/// `abc`, `acb` and `bac`. Regular expressions cannot count characters, so they have to be
+/// spelled out in an alternation which grows with the factorial of their length.
+/// The groups are listed in the order of appearance of their first test case.
+pub(crate) fn permutation_diagnostics(test_cases: &[Cow<str>]) -> Vec<String> {
+    let mut groups = HashMap::<Vec<char>, Vec<&str>>::new();
+    let mut keys = vec![];
+
+    for test_case in test_cases.iter().map(|it| it.as_ref()).unique() {
+        let key = test_case.chars().sorted().collect_vec();
+        // A test case consisting of a single repeated character has no other permutations.
+        if key.iter().all_equal() {
+            continue;
+        }
+        let group = groups.entry(key.clone()).or_default();
+        if group.is_empty() {
+            keys.push(key);
+        }
+        group.push(test_case);
+    }
+
+    keys.iter()
+        .map(|key| &groups[key])
+        .filter(|group| group.len() >= MINIMUM_PERMUTATION_COUNT)
+        .map(|group| {
+            format!(
+                "{} test cases such as {} are permutations of the same characters, \
+                 so the expression will be a large alternation of them; consider matching \
+                 the characters with a character class and checking their counts separately",
+                group.len(),
+                group
+                    .iter()
+                    .take(3)
+                    .map(|it| format!("'{}'", it))
+                    .join(", ")
+            )
+        })
+        .collect_vec()
+}
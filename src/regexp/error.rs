@@ -0,0 +1,124 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::{Display, Formatter, Result};
+
+/// This enum specifies the errors which can occur when the settings of
+/// [`RegExpBuilder`](./struct.RegExpBuilder.html) are validated or while
+/// the regular expression is generated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// Two settings have been enabled which cannot be combined with each other.
+    /// Both fields hold the names of the respective methods of
+    /// [`RegExpBuilder`](./struct.RegExpBuilder.html).
+    ConflictingOptions { a: &'static str, b: &'static str },
+
+    /// An alternation of the generated expression has more branches than allowed by
+    /// [`RegExpBuilder.with_maximum_alternation_branches`](./struct.RegExpBuilder.html#method.with_maximum_alternation_branches)
+    /// and falling back to a broader expression has been disabled with
+    /// [`RegExpBuilder.with_error_instead_of_alternation_fallback`](./struct.RegExpBuilder.html#method.with_error_instead_of_alternation_fallback).
+    TooManyAlternationBranches { branch_count: usize, limit: usize },
+
+    /// The automaton built from the test cases has more states than allowed by
+    /// [`RegExpBuilder.with_maximum_dfa_states`](./struct.RegExpBuilder.html#method.with_maximum_dfa_states).
+    TooManyStates { state_count: usize, limit: usize },
+
+    /// The generated expression has more nested groups and quantified expressions than allowed by
+    /// [`RegExpBuilder.with_maximum_nesting_depth`](./struct.RegExpBuilder.html#method.with_maximum_nesting_depth).
+    /// The field `depth` holds the depth at which the generation has been aborted,
+    /// which may be lower than the depth of the complete expression.
+    NestingTooDeep { depth: usize, limit: usize },
+
+    /// The generated expression consists of more subexpressions than allowed by
+    /// [`RegExpBuilder.with_maximum_expression_size`](./struct.RegExpBuilder.html#method.with_maximum_expression_size).
+    /// The field `size` holds the size of the intermediate expression at which the generation
+    /// has been aborted, which may differ from the size of the complete expression.
+    ExpressionTooLarge { size: usize, limit: usize },
+
+    /// A test case has been passed to
+    /// [`RegExpBuilder.with_negative_test_cases`](./struct.RegExpBuilder.html#method.with_negative_test_cases)
+    /// which is a regular test case as well, so it cannot be excluded from matching.
+    ContradictingTestCase { test_case: String },
+
+    /// A test case contains an opening marker without a matching closing marker or vice versa,
+    /// so the optional parts given by
+    /// [`RegExpBuilder.with_optional_markers`](./struct.RegExpBuilder.html#method.with_optional_markers)
+    /// cannot be determined.
+    UnbalancedOptionalMarkers { test_case: String },
+
+    /// No test cases are left to build the regular expression from. The field `requirement`
+    /// describes the condition which none of the provided test cases has met, such as
+    /// `"passing the input filter"`, or is `None` if no test cases have been provided at all.
+    NoTestCases { requirement: Option<&'static str> },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Error::ConflictingOptions { a, b } => write!(
+                f,
+                "The options '{}' and '{}' cannot be combined with each other",
+                a, b
+            ),
+            Error::TooManyAlternationBranches {
+                branch_count,
+                limit,
+            } => write!(
+                f,
+                "The generated expression has an alternation of {} branches which exceeds the maximum of {} branches",
+                branch_count, limit
+            ),
+            Error::TooManyStates { state_count, limit } => write!(
+                f,
+                "The automaton built from the test cases has {} states which exceeds the maximum of {} states",
+                state_count, limit
+            ),
+            Error::NestingTooDeep { depth, limit } => write!(
+                f,
+                "The generated expression has a nesting depth of {} which exceeds the maximum of {}",
+                depth, limit
+            ),
+            Error::ExpressionTooLarge { size, limit } => write!(
+                f,
+                "The generated expression consists of {} subexpressions which exceeds the maximum of {}",
+                size, limit
+            ),
+            Error::ContradictingTestCase { test_case } => write!(
+                f,
+                "The test case '{}' has been provided as both positive and negative test case",
+                test_case
+            ),
+            Error::UnbalancedOptionalMarkers { test_case } => write!(
+                f,
+                "The test case '{}' contains unbalanced optional markers",
+                test_case
+            ),
+            Error::NoTestCases { requirement } => match requirement {
+                Some(requirement) => write!(
+                    f,
+                    "No test cases {} have been provided for regular expression generation",
+                    requirement
+                ),
+                None => write!(
+                    f,
+                    "No test cases have been provided for regular expression generation"
+                ),
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {}
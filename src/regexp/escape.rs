@@ -0,0 +1,39 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ast::Expression;
+use crate::char::GraphemeCluster;
+use crate::regexp::RegExpConfig;
+use std::rc::Rc;
+
+/// Escapes all regular expression metacharacters in `s` so that the returned string
+/// matches `s` literally when used as a regular expression or as part of one.
+///
+/// The escaping rules are exactly the same as the ones applied to literals in the
+/// regular expressions built by [`RegExpBuilder`](./struct.RegExpBuilder.html).
+/// A builder's settings, such as the escaping of non-ASCII characters, can be taken over
+/// by passing the config returned from
+/// [`RegExpBuilder.config`](./struct.RegExpBuilder.html#method.config).
+///
+/// ```
+/// use grex::{escape, RegExpConfig};
+///
+/// assert_eq!(escape("1+1=2?", &RegExpConfig::default()), "1\\+1=2\\?");
+/// ```
+pub fn escape(s: &str, config: &RegExpConfig) -> String {
+    let config = &Rc::new(config.clone());
+    Expression::new_literal(GraphemeCluster::from(s, config), config).to_string()
+}
@@ -75,6 +75,16 @@ pub enum Feature {
     /// Non-words which are also non-space characters are converted to `\W`.
     NonWord,
 
+    /// This feature writes character classes which consist of exactly the ASCII hexadecimal
+    /// digits as `[0-9a-f]`, `[0-9A-F]` or `[0-9a-fA-F]`, depending on whether their letters
+    /// are in lower case, upper case or both. Adjacent classes of this kind are written
+    /// as a counted repetition, so that hexadecimal tokens of uniform length result in
+    /// expressions like `[0-9a-f]{8}`.
+    ///
+    /// Only classes built from the test cases are affected, so test cases such as `cafe`
+    /// which merely look like hexadecimal numbers are left unchanged.
+    Hex,
+
     /// This feature detects repeated non-overlapping substrings and
     /// converts them to `{min,max}` quantifier notation.
     Repetition,
@@ -0,0 +1,37 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// This enum specifies the regular expression engine whose syntax is used for named
+/// references to Unicode blocks. It is selected with method
+/// [`RegExpBuilder.with_unicode_block_names`](./struct.RegExpBuilder.html#method.with_unicode_block_names).
+#[derive(Clone, Copy, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum Flavor {
+    /// The syntax of .NET, which prefixes block names with `Is`, as in `\p{IsBasicLatin}`.
+    DotNet,
+
+    /// The syntax of Java, which prefixes block names with `In`, as in `\p{InBasicLatin}`.
+    Java,
+}
+
+impl Flavor {
+    pub(crate) fn block_reference(&self, block_name: &str) -> String {
+        let prefix = match self {
+            Flavor::DotNet => "Is",
+            Flavor::Java => "In",
+        };
+        format!("\\p{{{}{}}}", prefix, block_name)
+    }
+}
@@ -0,0 +1,38 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// This enum specifies the syntax of the groups which enclose subexpressions, such as
+/// alternations within concatenations or quantified concatenations. It is selected with
+/// method [`RegExpBuilder.with_group_marker`](./struct.RegExpBuilder.html#method.with_group_marker).
+#[derive(Clone, Copy, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum GroupMarker {
+    /// Groups are written as non-capturing groups such as `(?:ab)`.
+    /// This is supported by most regular expression engines and is the default.
+    NonCapturing,
+
+    /// Groups are written as atomic groups such as `(?>ab)`, which do not backtrack
+    /// into the group once it has matched. This is supported by engines such as PCRE,
+    /// Java and .NET, but not by Rust's regex crate. Only groups whose content matches
+    /// strings of a single length are made atomic, as backtracking into them cannot
+    /// lead to another match. Groups containing alternations or ranges of repetitions
+    /// remain non-capturing groups such as `(?:ab|c)`.
+    Atomic,
+
+    /// Groups are written as plain parentheses such as `(ab)`, which capture the matched
+    /// text. This is the same as converting
+    /// [`Feature::CapturingGroup`](./enum.Feature.html#variant.CapturingGroup).
+    Plain,
+}
@@ -0,0 +1,35 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// This enum specifies how leading zeros of numeric test cases are treated, that is of test
+/// cases which consist of ASCII digits only. It is selected with method
+/// [`RegExpBuilder.with_leading_zeros`](./struct.RegExpBuilder.html#method.with_leading_zeros).
+#[derive(Clone, Copy, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum LeadingZeros {
+    /// Leading zeros are kept as they are, so `7` and `007` are distinct test cases.
+    /// This is the default.
+    Kept,
+
+    /// Leading zeros are removed before the expression is built, so `007` is treated
+    /// as `7` and the expression does not match `007` anymore. A number consisting
+    /// of zeros only is reduced to a single zero.
+    Removed,
+
+    /// Leading zeros are removed before the expression is built and any number of them
+    /// is allowed in front of the expression instead, so `7` and `007` result in `^0*7$`.
+    /// This only takes effect if all test cases are numeric, otherwise they are kept.
+    Optional,
+}
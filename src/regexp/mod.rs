@@ -14,24 +14,45 @@
  * limitations under the License.
  */
 
+mod anchor;
 mod builder;
 mod component;
 mod config;
+mod diagnostics;
+mod error;
+mod escape;
 mod feature;
+mod flavor;
+mod group_marker;
+mod leading_zeros;
+mod output_wrapper;
+mod preset;
+mod progress;
 
 #[allow(clippy::module_inception)]
 mod regexp;
 
+pub use anchor::Anchor;
 pub use builder::RegExpBuilder;
 pub use component::Component;
 pub use config::RegExpConfig;
+pub use error::Error;
+pub use escape::escape;
 pub use feature::Feature;
+pub use flavor::Flavor;
+pub use group_marker::GroupMarker;
+pub use leading_zeros::LeadingZeros;
+pub use output_wrapper::OutputWrapper;
+pub use preset::Preset;
+pub use progress::ProgressEvent;
+pub(crate) use regexp::MarkedPart;
 pub use regexp::RegExp;
 
 #[cfg(test)]
 mod tests {
     use crate::regexp::Feature;
     use crate::regexp::RegExpBuilder;
+    use std::time::Duration;
 
     #[test]
     #[should_panic(expected = "No test cases have been provided for regular expression generation")]
@@ -39,6 +60,43 @@ mod tests {
         RegExpBuilder::from(&Vec::<String>::new());
     }
 
+    #[test]
+    #[should_panic(expected = "No test cases have been provided for regular expression generation")]
+    fn regexp_builder_panics_on_build_without_added_test_cases() {
+        RegExpBuilder::new().build();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "No test cases with the minimum weight have been provided for regular expression generation"
+    )]
+    fn regexp_builder_panics_if_all_test_cases_are_below_minimum_weight() {
+        RegExpBuilder::from_weighted(&[("abc", 1), ("def", 2)])
+            .with_minimum_weight(3)
+            .build();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "No test cases passing the input filter have been provided for regular expression generation"
+    )]
+    fn regexp_builder_panics_if_all_test_cases_fail_input_filter() {
+        RegExpBuilder::from(&["abc", "def"])
+            .with_input_filter(|it| it.len() > 3)
+            .build();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The options 'with_replacement_of_dashes' and 'with_extension_of_dash_classes' cannot be combined with each other"
+    )]
+    fn regexp_builder_panics_on_build_with_conflicting_options() {
+        RegExpBuilder::from(&["a-b"])
+            .with_replacement_of_dashes('-')
+            .with_extension_of_dash_classes()
+            .build();
+    }
+
     #[test]
     #[should_panic(
         expected = "No conversion features have been provided for regular expression generation"
@@ -47,21 +105,147 @@ mod tests {
         RegExpBuilder::from(&["abc"]).with_conversion_of(&Vec::<Feature>::new());
     }
 
+    #[test]
+    #[should_panic(expected = "No separators have been provided for regular expression generation")]
+    fn regexp_builder_panics_without_separators() {
+        RegExpBuilder::from(&["abc"]).with_separators(&[]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "No alphabet has been provided for choosing the shortest character classes"
+    )]
+    fn regexp_builder_panics_without_alphabet() {
+        RegExpBuilder::from(&["abc"]).with_shortest_char_classes(&[]);
+    }
+
     #[test]
     #[should_panic(expected = "The specified file could not be found")]
     fn regexp_builder_panics_if_file_does_not_exist() {
         RegExpBuilder::from_file("/path/to/non-existing/file");
     }
 
+    #[test]
+    #[should_panic(expected = "No test cases have been provided for regular expression generation")]
+    fn regexp_builder_panics_if_test_cases_are_empty() {
+        RegExpBuilder::from(&Vec::<String>::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "No test cases have been provided for regular expression generation")]
+    fn regexp_builder_panics_if_no_test_case_has_been_added() {
+        RegExpBuilder::new().build();
+    }
+
     #[test]
     #[should_panic(expected = "Quantity of minimum repetitions must not be zero")]
     fn regexp_builder_panics_if_minimum_repetitions_is_less_than_two() {
         RegExpBuilder::from(&["abc"]).with_minimum_repetitions(0);
     }
 
+    #[test]
+    #[should_panic(
+        expected = "The backslash cannot be used as wildcard because it escapes the wildcard"
+    )]
+    fn regexp_builder_panics_if_wildcard_is_backslash() {
+        RegExpBuilder::from(&["abc"]).with_wildcard('\\', false);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minimum class size must not be less than two")]
+    fn regexp_builder_panics_if_minimum_class_size_is_less_than_two() {
+        RegExpBuilder::from(&["abc"]).with_minimum_class_size(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Maximum repetition count must not be zero")]
+    fn regexp_builder_panics_if_maximum_repetition_count_is_zero() {
+        RegExpBuilder::from(&["abc"]).with_maximum_repetition_count(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Maximum number of automaton states must not be zero")]
+    fn regexp_builder_panics_if_maximum_dfa_states_is_zero() {
+        RegExpBuilder::from(&["abc"]).with_maximum_dfa_states(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Maximum nesting depth must not be zero")]
+    fn regexp_builder_panics_if_maximum_nesting_depth_is_zero() {
+        RegExpBuilder::from(&["abc"]).with_maximum_nesting_depth(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Maximum expression size must not be zero")]
+    fn regexp_builder_panics_if_maximum_expression_size_is_zero() {
+        RegExpBuilder::from(&["abc"]).with_maximum_expression_size(0);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The alternation separator must consist of a single pipe surrounded by whitespace and comments"
+    )]
+    fn regexp_builder_panics_if_alternation_separator_has_no_pipe() {
+        RegExpBuilder::from(&["abc"]).with_alternation_separator("# or");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The alternation separator must consist of a single pipe surrounded by whitespace and comments"
+    )]
+    fn regexp_builder_panics_if_alternation_separator_has_other_chars() {
+        RegExpBuilder::from(&["abc"]).with_alternation_separator("||");
+    }
+
+    #[test]
+    #[should_panic(expected = "Time budget must not be zero")]
+    fn regexp_builder_panics_if_time_budget_is_zero() {
+        RegExpBuilder::from(&["abc"]).with_time_budget(Duration::ZERO);
+    }
+
+    #[test]
+    #[should_panic(expected = "Maximum number of alternation branches must not be zero")]
+    fn regexp_builder_panics_if_maximum_alternation_branches_is_zero() {
+        RegExpBuilder::from(&["abc"]).with_maximum_alternation_branches(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minimum common substring length must not be zero")]
+    fn regexp_builder_panics_if_minimum_common_substring_length_is_zero() {
+        RegExpBuilder::from(&["abc"]).with_minimum_common_substring_length(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Width of length buckets must not be zero")]
+    fn regexp_builder_panics_if_width_of_length_buckets_is_zero() {
+        RegExpBuilder::from(&["abc"]).with_length_buckets(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Column delimiter must not be a double quote")]
+    fn regexp_builder_panics_if_column_delimiter_is_double_quote() {
+        RegExpBuilder::from(&["a,b"]).with_column('"', 1);
+    }
+
     #[test]
     #[should_panic(expected = "Minimum substring length must not be zero")]
     fn regexp_builder_panics_if_minimum_substring_length_is_zero() {
         RegExpBuilder::from(&["abc"]).with_minimum_substring_length(0);
     }
+
+    #[test]
+    #[should_panic(
+        expected = "The opening and closing optional markers must differ from each other"
+    )]
+    fn regexp_builder_panics_if_optional_markers_are_equal() {
+        RegExpBuilder::from(&["colo|u|r"]).with_optional_markers('|', '|');
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The backslash cannot be used as optional marker because it escapes the markers"
+    )]
+    fn regexp_builder_panics_if_optional_marker_is_backslash() {
+        RegExpBuilder::from(&["colo[u]r"]).with_optional_markers('[', '\\');
+    }
 }
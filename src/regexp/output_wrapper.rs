@@ -0,0 +1,126 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeSet;
+
+/// This enum specifies the string literals in which the generated regular expression
+/// can be wrapped using method
+/// [`RegExpBuilder.with_output_wrapper`](./struct.RegExpBuilder.html#method.with_output_wrapper),
+/// so that it can be pasted into source code or shell commands directly.
+#[derive(Clone, Copy, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum OutputWrapper {
+    /// The regular expression is not wrapped.
+    None,
+
+    /// The regular expression is wrapped in a Rust raw string literal such as `r"^a\d$"`.
+    /// If it contains double quotes, as many hashes are added as needed to delimit it,
+    /// such as in `r#"^"a"$"#`.
+    RustRaw,
+
+    /// The regular expression is wrapped in a Python raw string literal such as `r"^a\d$"`.
+    /// Double quotes within it are escaped with a backslash, which the regular expression
+    /// engine treats as a literal double quote. Multi-line expressions created in verbose
+    /// mode are wrapped in triple quotes.
+    PythonRaw,
+
+    /// The regular expression is wrapped in a JavaScript string literal such as `"^a\\d$"`,
+    /// to be passed to the `RegExp` constructor. Backslashes, double quotes
+    /// and control characters are escaped.
+    JavaScript,
+
+    /// The regular expression is wrapped in a Java string literal such as `"^a\\d$"`,
+    /// to be passed to `Pattern.compile`. Backslashes, double quotes and control characters
+    /// are escaped. Control characters are escaped as octal sequences because unicode
+    /// escape sequences are already resolved before Java source code is parsed.
+    Java,
+
+    /// The regular expression is encoded as a JSON string such as `"^a\\d$"`.
+    /// Backslashes, double quotes and control characters are escaped.
+    Json,
+
+    /// The regular expression is wrapped in single quotes for POSIX shells, such as
+    /// `'^a\$$'`, so that the shell passes it on unchanged. Within single quotes, no
+    /// character is special except the single quote itself, which is written as `'\''`.
+    PosixShell,
+}
+
+impl OutputWrapper {
+    pub(crate) fn wrap(&self, regexp: &str) -> String {
+        match self {
+            OutputWrapper::None => regexp.to_string(),
+            OutputWrapper::RustRaw => {
+                let hashes = "#".repeat(Self::count_required_hashes(regexp));
+                format!("r{}\"{}\"{}", hashes, regexp, hashes)
+            }
+            OutputWrapper::PythonRaw => {
+                let quotes = if regexp.contains('\n') {
+                    "\"\"\""
+                } else {
+                    "\""
+                };
+                format!("r{}{}{}", quotes, regexp.replace('"', "\\\""), quotes)
+            }
+            OutputWrapper::JavaScript | OutputWrapper::Java | OutputWrapper::Json => {
+                format!("\"{}\"", self.escape(regexp))
+            }
+            // The single quoted string is closed, followed by an escaped single quote,
+            // and opened again.
+            OutputWrapper::PosixShell => format!("'{}'", regexp.replace('\'', "'\\''")),
+        }
+    }
+
+    /// JavaScript only understands unicode escape sequences with braces and treats astral
+    /// characters as single characters in unicode mode, which is enabled by the `u` flag.
+    /// The other target languages do not need any flags beyond the inline ones.
+    pub(crate) fn required_flags(&self, regexp: &str) -> BTreeSet<char> {
+        let mut flags = BTreeSet::new();
+        if self == &OutputWrapper::JavaScript
+            && (regexp.contains("\\u{") || regexp.chars().any(|c| c > '\u{ffff}'))
+        {
+            flags.insert('u');
+        }
+        flags
+    }
+
+    fn escape(&self, regexp: &str) -> String {
+        regexp
+            .chars()
+            .map(|c| match c {
+                '\\' => "\\\\".to_string(),
+                '"' => "\\\"".to_string(),
+                '\n' => "\\n".to_string(),
+                '\r' => "\\r".to_string(),
+                '\t' => "\\t".to_string(),
+                c if c.is_control() && self == &OutputWrapper::Java => {
+                    format!("\\{:03o}", c as u32)
+                }
+                c if c.is_control() => format!("\\u{:04x}", c as u32),
+                c => c.to_string(),
+            })
+            .collect()
+    }
+
+    /// A Rust raw string literal ends at the first double quote followed by as many
+    /// hashes as it has been opened with, so it needs one more hash than the longest
+    /// sequence of hashes following a double quote within the regular expression.
+    fn count_required_hashes(regexp: &str) -> usize {
+        regexp
+            .match_indices('"')
+            .map(|(idx, _)| regexp[idx + 1..].chars().take_while(|&c| c == '#').count() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
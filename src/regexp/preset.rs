@@ -0,0 +1,53 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// This enum specifies named bundles of settings which can be applied at once using
+/// [`RegExpConfig::preset`](./struct.RegExpConfig.html#method.preset) or
+/// [`RegExpBuilder.with_preset`](./struct.RegExpBuilder.html#method.with_preset).
+///
+/// Each preset only sets the settings listed for it. All other settings keep their
+/// current values and can still be changed afterwards with the respective builder methods.
+#[derive(Clone, Copy, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum Preset {
+    /// Produces expressions which are easy to read for humans. It sets:
+    /// - the conversion features to [`Feature::Repetition`](./enum.Feature.html#variant.Repetition) only
+    /// - the minimum substring length to 2, so that single repeated characters are written out
+    /// - the preference of the Kleene star over redundant optional repetitions
+    Readable,
+
+    /// Produces expressions which are as short as possible. It sets:
+    /// - the conversion features to [`Feature::Digit`](./enum.Feature.html#variant.Digit),
+    ///   [`Feature::Space`](./enum.Feature.html#variant.Space),
+    ///   [`Feature::Word`](./enum.Feature.html#variant.Word) and
+    ///   [`Feature::Repetition`](./enum.Feature.html#variant.Repetition)
+    /// - the minimum substring length to 1
+    /// - the preference of the Kleene star over redundant optional repetitions
+    Compact,
+
+    /// Produces expressions which only consist of printable ASCII characters and
+    /// avoid shorthand classes, so that they work with most regular expression engines. It sets:
+    /// - no conversion features at all
+    /// - the escaping of non-ASCII characters, with astral code points as surrogate pairs,
+    ///   since escapes beyond `\u{ffff}` are not understood by many engines
+    /// - the hexadecimal escaping of all control characters
+    Portable,
+
+    /// Produces expressions in verbose mode which document themselves. It sets:
+    /// - the conversion features to [`Feature::Repetition`](./enum.Feature.html#variant.Repetition) only
+    /// - the verbose mode
+    /// - the test cases as a leading comment
+    Verbose,
+}
@@ -0,0 +1,45 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// This enum specifies the events which are reported to the callback given to
+/// [`RegExpBuilder.with_progress_callback`](./struct.RegExpBuilder.html#method.with_progress_callback)
+/// while a regular expression is generated. The events are reported in the order
+/// in which they are listed here.
+#[derive(Clone, Copy, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum ProgressEvent {
+    /// The test cases have been checked against the predicate given to
+    /// [`RegExpBuilder.with_input_filter`](./struct.RegExpBuilder.html#method.with_input_filter).
+    /// This event is only reported if such a predicate has been specified.
+    TestCasesFiltered { excluded_count: usize },
+
+    /// The test cases have been preprocessed, sorted and split into grapheme clusters.
+    TestCasesIngested { test_case_count: usize },
+
+    /// The minimal automaton accepting all test cases has been built.
+    DfaBuilt { state_count: usize },
+
+    /// A state of the automaton has been eliminated while converting it into an expression.
+    /// This event is reported once for each of the automaton's states.
+    StateEliminated {
+        eliminated_count: usize,
+        state_count: usize,
+    },
+
+    /// The expression has been rendered as a string. This event is only reported by
+    /// [`RegExpBuilder.build`](./struct.RegExpBuilder.html#method.build) and
+    /// [`RegExpBuilder.try_build`](./struct.RegExpBuilder.html#method.try_build).
+    RenderingDone,
+}
@@ -15,64 +15,557 @@
  */
 
 use crate::ast::Expression;
-use crate::char::GraphemeCluster;
-use crate::fsm::Dfa;
+use crate::char::{opposite_case, GraphemeCluster};
+use crate::fsm::{Dfa, Nfa};
 use crate::regexp::config::RegExpConfig;
-use crate::regexp::Component;
+use crate::regexp::{Anchor, Component, Error, Feature, LeadingZeros, ProgressEvent};
+use crate::unicode_tables::is_dash;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Display, Formatter, Result};
+use std::rc::Rc;
+use std::time::Instant;
+
+const CONCATENATION_PRECEDENCE: u8 = 2;
+const MAXIMUM_COMMENTED_TEST_CASES: usize = 5;
+
+/// The configuration to build the expression with, together with the grapheme clusters
+/// of the test cases and of the negative test cases and the expressions of the test cases
+/// with optional parts.
+type PreparedTestCases = (
+    Rc<RegExpConfig>,
+    Vec<GraphemeCluster>,
+    Vec<GraphemeCluster>,
+    Vec<Expression>,
+);
+
+/// A part of a test case split at the markers given by
+/// [`with_optional_markers`](./struct.RegExpBuilder.html#method.with_optional_markers),
+/// which is either literal text or a sequence of parts enclosed in the markers.
+#[derive(Debug)]
+pub(crate) enum MarkedPart {
+    Text(String),
+    Optional(Vec<MarkedPart>),
+}
 
 pub struct RegExp {
     ast: Expression,
-    config: RegExpConfig,
+    config: Rc<RegExpConfig>,
+    test_cases: Vec<String>,
+    branch_comments: Vec<String>,
 }
 
 impl RegExp {
-    pub(crate) fn from(test_cases: &mut Vec<String>, config: &RegExpConfig) -> Self {
+    /// Test cases are only copied if they need to be changed, such as by lowercasing them,
+    /// so that large sets of test cases can be borrowed from the builder.
+    pub(crate) fn from(
+        test_cases: &mut Vec<Cow<str>>,
+        negative_test_cases: &mut Vec<Cow<str>>,
+        config: &RegExpConfig,
+        progress: Option<&dyn Fn(ProgressEvent)>,
+    ) -> std::result::Result<Self, Error> {
+        let (config, grapheme_clusters, negative_grapheme_clusters, marked_exprs) =
+            Self::grapheme_clusters_of(test_cases, negative_test_cases, config, progress)?;
+        let config = &config;
+        let deadline = config.time_budget.map(|budget| Instant::now() + budget);
+        let (ast, branch_comments) = match config.length_bucket_width {
+            Some(width) => Self::length_bucketed_ast(
+                test_cases,
+                grapheme_clusters,
+                negative_grapheme_clusters,
+                width,
+                config,
+                progress,
+                deadline,
+            )?,
+            None => {
+                let positional_ast = if config.is_positional_inference_enabled {
+                    Expression::new_positional(&grapheme_clusters, config)
+                } else {
+                    None
+                };
+                let ast = match positional_ast {
+                    Some(ast) => Some(ast),
+                    // All test cases may have optional parts, which are not part of the automaton.
+                    None if grapheme_clusters.is_empty() => None,
+                    None => Some(Self::automaton_ast(
+                        grapheme_clusters,
+                        negative_grapheme_clusters,
+                        config,
+                        progress,
+                        deadline,
+                    )?),
+                };
+                let ast = marked_exprs
+                    .into_iter()
+                    .fold(ast, |ast, expr| match ast {
+                        Some(ast) => Some(Expression::new_union(ast, expr, config)),
+                        None => Some(expr),
+                    })
+                    .unwrap();
+                (Self::refine_ast(ast, test_cases, config)?, vec![])
+            }
+        };
+        if let Some(limit) = config.maximum_nesting_depth {
+            let depth = ast.nesting_depth();
+            if depth > limit {
+                return Err(Error::NestingTooDeep { depth, limit });
+            }
+        }
+        if let Some(limit) = config.maximum_expression_size {
+            let size = ast.size();
+            if size > limit {
+                return Err(Error::ExpressionTooLarge { size, limit });
+            }
+        }
+        Ok(Self {
+            ast,
+            config: Rc::clone(config),
+            test_cases: if config.is_test_case_comment_added {
+                test_cases.iter().map(|it| it.to_string()).collect_vec()
+            } else {
+                vec![]
+            },
+            branch_comments,
+        })
+    }
+
+    /// Builds the automaton accepting the test cases but none of the negative test cases,
+    /// before it is converted into an expression. Test cases with optional parts are
+    /// turned into expressions directly, so they are not part of the automaton.
+    pub(crate) fn dfa(
+        test_cases: &mut Vec<Cow<str>>,
+        negative_test_cases: &mut Vec<Cow<str>>,
+        config: &RegExpConfig,
+    ) -> std::result::Result<Dfa, Error> {
+        let (config, grapheme_clusters, negative_grapheme_clusters, _) =
+            Self::grapheme_clusters_of(test_cases, negative_test_cases, config, None)?;
+        Ok(Self::new_dfa(
+            grapheme_clusters,
+            negative_grapheme_clusters,
+            &config,
+        ))
+    }
+
+    /// Normalizes and sorts the test cases and the negative test cases and converts them
+    /// into grapheme clusters, together with the configuration to build them with.
+    fn grapheme_clusters_of(
+        test_cases: &mut Vec<Cow<str>>,
+        negative_test_cases: &mut Vec<Cow<str>>,
+        config: &RegExpConfig,
+        progress: Option<&dyn Fn(ProgressEvent)>,
+    ) -> std::result::Result<PreparedTestCases, Error> {
         if config.is_case_insensitive_matching() {
             Self::convert_to_lowercase(test_cases);
+            Self::convert_to_lowercase(negative_test_cases);
+        }
+        if let Some(replacement) = config.dash_replacement {
+            Self::replace_dashes(test_cases, replacement);
+            Self::replace_dashes(negative_test_cases, replacement);
+        }
+        // The zeros can only be made optional in front of the whole expression
+        // if it matches numbers only.
+        let config = &if config.leading_zeros == LeadingZeros::Optional
+            && !test_cases.iter().all(|it| is_number(it))
+        {
+            let mut config = config.clone();
+            config.leading_zeros = LeadingZeros::Kept;
+            config
+        } else {
+            config.clone()
+        };
+        if config.leading_zeros != LeadingZeros::Kept {
+            Self::remove_leading_zeros(test_cases);
+            Self::remove_leading_zeros(negative_test_cases);
+        }
+        let marked_test_cases = match config.optional_markers {
+            Some((open, close)) => Self::split_off_marked_test_cases(test_cases, open, close)?,
+            None => vec![],
+        };
+        if let Some(test_case) = test_cases
+            .iter()
+            .find(|&it| negative_test_cases.contains(it))
+        {
+            return Err(Error::ContradictingTestCase {
+                test_case: test_case.to_string(),
+            });
         }
+        // The order of appearance has to be recorded before the test cases are sorted.
+        let config = &Rc::new(if config.is_char_class_order_preserved {
+            config.with_char_positions(test_cases)
+        } else if config.is_char_class_ordered_by_frequency {
+            config.with_char_frequencies(test_cases)
+        } else {
+            config.clone()
+        });
         Self::sort(test_cases);
-        let grapheme_clusters = Self::grapheme_clusters(&test_cases, config);
-        let dfa = Dfa::from(grapheme_clusters, config);
-        let ast = Expression::from(dfa, config);
-        Self {
-            ast,
-            config: config.clone(),
+        let grapheme_clusters = Self::grapheme_clusters(test_cases, config);
+        if let Some(progress) = progress {
+            progress(ProgressEvent::TestCasesIngested {
+                test_case_count: test_cases.len(),
+            });
+        }
+        // Negative test cases are taken literally, so they are excluded
+        // before the conversion features generalize the automaton.
+        let negative_grapheme_clusters = negative_test_cases
+            .iter()
+            .map(|it| GraphemeCluster::from(it, config))
+            .collect_vec();
+        let marked_exprs = marked_test_cases
+            .iter()
+            .map(|parts| Expression::new_marked(parts, config))
+            .collect_vec();
+        // A marked test case stands for several strings, so each of them
+        // has to be checked against the negative test cases.
+        if let Some(test_case) = negative_test_cases.iter().find(|&it| {
+            marked_exprs
+                .iter()
+                .any(|expr| expr.to_nfa(config).accepts(it))
+        }) {
+            return Err(Error::ContradictingTestCase {
+                test_case: test_case.to_string(),
+            });
+        }
+        Ok((
+            Rc::clone(config),
+            grapheme_clusters,
+            negative_grapheme_clusters,
+            marked_exprs,
+        ))
+    }
+
+    /// Removes the test cases with parts enclosed in the markers `open` and `close` and
+    /// returns them split at the markers. The remaining test cases lose their escaping
+    /// backslashes, so that they can be converted into grapheme clusters like any others.
+    fn split_off_marked_test_cases(
+        test_cases: &mut Vec<Cow<str>>,
+        open: char,
+        close: char,
+    ) -> std::result::Result<Vec<Vec<MarkedPart>>, Error> {
+        let mut marked_test_cases = vec![];
+        let mut plain_test_cases = vec![];
+        for test_case in test_cases.drain(..) {
+            let parts = split_at_optional_markers(&test_case, open, close)?;
+            match parts.as_slice() {
+                [] => plain_test_cases.push(test_case),
+                [MarkedPart::Text(text)] if *text == *test_case => plain_test_cases.push(test_case),
+                [MarkedPart::Text(text)] => plain_test_cases.push(Cow::Owned(text.clone())),
+                _ => marked_test_cases.push(parts),
+            }
+        }
+        *test_cases = plain_test_cases;
+        Ok(marked_test_cases)
+    }
+
+    /// Applies the settings which rework the expression built from the test cases as a whole.
+    fn refine_ast(
+        mut ast: Expression,
+        test_cases: &[Cow<str>],
+        config: &Rc<RegExpConfig>,
+    ) -> std::result::Result<Expression, Error> {
+        if config.minimum_common_substring_length > 1 {
+            ast = ast.with_expanded_short_affixes();
+        }
+        if config.is_prefix_branch_kept_distinct {
+            ast = ast.with_distinct_prefix_branches();
+        }
+        if let Some(limit) = config.maximum_alternation_branches {
+            let branch_count = ast.alternation_branch_count();
+            if branch_count > limit as usize {
+                if config.is_alternation_fallback_replaced_by_error {
+                    return Err(Error::TooManyAlternationBranches {
+                        branch_count,
+                        limit: limit as usize,
+                    });
+                }
+                ast = Expression::new_fallback(test_cases, config);
+            }
+        }
+        if config.leading_zeros == LeadingZeros::Optional {
+            ast = ast.with_optional_leading_zeros(config);
+        }
+        if let Some(separators) = &config.separators {
+            ast = ast.with_separators(separators);
+        }
+        if config.is_word_boundary_inserted {
+            ast = ast.with_word_boundaries();
+        }
+        if config.is_hex_converted() {
+            ast = ast.with_collapsed_hex_class_repeats();
         }
+        // Collapsing would merge several capturing groups into one.
+        if config.is_alternation_repeat_collapsed
+            && !config.is_capturing_group_enabled()
+            && !config.is_repetition_disabled
+        {
+            ast = ast.with_collapsed_alternation_repeats();
+        }
+        Ok(ast)
+    }
+
+    /// Builds a separate expression for each group of test cases whose lengths fall
+    /// into the same bucket and returns the alternation of them in ascending order of
+    /// length, together with a comment describing the lengths of each of its branches.
+    /// The branches are alternations themselves in general, so they are not merged
+    /// with each other but rendered in groups.
+    fn length_bucketed_ast(
+        test_cases: &[Cow<str>],
+        grapheme_clusters: Vec<GraphemeCluster>,
+        negative_grapheme_clusters: Vec<GraphemeCluster>,
+        width: usize,
+        config: &Rc<RegExpConfig>,
+        progress: Option<&dyn Fn(ProgressEvent)>,
+        deadline: Option<Instant>,
+    ) -> std::result::Result<(Expression, Vec<String>), Error> {
+        let mut buckets = BTreeMap::new();
+        for (test_case, cluster) in test_cases.iter().zip(grapheme_clusters) {
+            let length = test_case.chars().count();
+            buckets
+                .entry(length / width)
+                .or_insert_with(Vec::new)
+                .push((test_case.clone(), cluster));
+        }
+        // A branch for the empty test case alone would be empty,
+        // so the empty test case is made optional in the next branch instead.
+        if buckets.len() > 1
+            && buckets
+                .get(&0)
+                .is_some_and(|bucket| bucket.iter().all(|(test_case, _)| test_case.is_empty()))
+        {
+            let empty_test_cases = buckets.remove(&0).unwrap();
+            buckets
+                .values_mut()
+                .next()
+                .unwrap()
+                .splice(0..0, empty_test_cases);
+        }
+
+        let mut options = vec![];
+        let mut branch_comments = vec![];
+
+        for bucket in buckets.into_values() {
+            let (min, max) = bucket
+                .iter()
+                .map(|(test_case, _)| test_case.chars().count())
+                .minmax()
+                .into_option()
+                .unwrap();
+            branch_comments.push(if min == max {
+                format!("# length {}", min)
+            } else {
+                format!("# length {} to {}", min, max)
+            });
+            let (bucket_test_cases, bucket_clusters): (Vec<_>, Vec<_>) = bucket.into_iter().unzip();
+            let ast = Self::automaton_ast(
+                bucket_clusters,
+                negative_grapheme_clusters.clone(),
+                config,
+                progress,
+                deadline,
+            )?;
+            options.push(Self::refine_ast(ast, &bucket_test_cases, config)?);
+        }
+
+        if options.len() == 1 {
+            return Ok((options.remove(0), vec![]));
+        }
+        Ok((
+            Expression::Alternation(options, Rc::clone(config)),
+            branch_comments,
+        ))
+    }
+
+    /// Falls back to the plain alternation of the test cases
+    /// if the automaton cannot be converted before the deadline.
+    fn automaton_ast(
+        grapheme_clusters: Vec<GraphemeCluster>,
+        negative_grapheme_clusters: Vec<GraphemeCluster>,
+        config: &Rc<RegExpConfig>,
+        progress: Option<&dyn Fn(ProgressEvent)>,
+        deadline: Option<Instant>,
+    ) -> std::result::Result<Expression, Error> {
+        let fallback_clusters = deadline.map(|_| grapheme_clusters.clone());
+        let dfa = Self::new_dfa(grapheme_clusters, negative_grapheme_clusters, config);
+        if let Some(progress) = progress {
+            progress(ProgressEvent::DfaBuilt {
+                state_count: dfa.state_count(),
+            });
+        }
+        // The conversion into an expression is the most expensive step,
+        // so the limit has to be checked beforehand.
+        if let Some(limit) = config.maximum_dfa_states {
+            let state_count = dfa.state_count();
+            if state_count > limit {
+                return Err(Error::TooManyStates { state_count, limit });
+            }
+        }
+        match Expression::from(dfa, config, progress, deadline)? {
+            Some(ast) => Ok(ast),
+            None => Ok(Expression::new_raw_alternation(
+                fallback_clusters.unwrap(),
+                config,
+            )),
+        }
+    }
+
+    fn new_dfa(
+        mut grapheme_clusters: Vec<GraphemeCluster>,
+        negative_grapheme_clusters: Vec<GraphemeCluster>,
+        config: &Rc<RegExpConfig>,
+    ) -> Dfa {
+        // Repetitions are converted only now because the positional inference
+        // compares the test cases grapheme by grapheme.
+        if config.is_repetition_converted() {
+            for cluster in grapheme_clusters.iter_mut() {
+                cluster.convert_repetitions();
+            }
+        }
+        if negative_grapheme_clusters.is_empty() {
+            Dfa::from(grapheme_clusters, config)
+        } else {
+            Dfa::from_difference(grapheme_clusters, negative_grapheme_clusters, config)
+        }
+    }
+
+    /// Case-insensitive expressions are built from lowercased test cases,
+    /// so the uppercase counterparts of the first characters are added.
+    pub(crate) fn first_chars(&self) -> Option<BTreeSet<char>> {
+        let mut chars = self.ast.first_chars()?;
+        if self.config.is_case_insensitive_matching() {
+            let other_cases = chars.iter().filter_map(|&c| opposite_case(c)).collect_vec();
+            chars.extend(other_cases);
+        }
+        Some(chars)
+    }
+
+    pub(crate) fn nfa(&self) -> Nfa {
+        self.ast.to_nfa(&self.config)
+    }
+
+    pub(crate) fn required_flags(&self) -> BTreeSet<char> {
+        self.config
+            .output_wrapper
+            .required_flags(&self.ast.to_string())
+    }
+
+    pub(crate) fn applied_conversions(&self) -> BTreeSet<Feature> {
+        let mut conversions = self.ast.applied_conversions();
+        if self.config.is_capturing_group_enabled()
+            && (self.is_grouping_needed() || contains_capturing_group(&self.ast.to_string()))
+        {
+            conversions.insert(Feature::CapturingGroup);
+        }
+        conversions
+    }
+
+    /// The anchors are concatenated with the expression, so only
+    /// expressions of lower precedence than concatenation need to be grouped.
+    fn is_grouping_needed(&self) -> bool {
+        let is_anchored =
+            self.config.start_anchor != Anchor::None || self.config.end_anchor != Anchor::None;
+        is_anchored
+            && self.ast.precedence() < CONCATENATION_PRECEDENCE
+            && !self.ast.is_single_codepoint()
+    }
+
+    pub(crate) fn alternatives(&self) -> Vec<String> {
+        self.ast
+            .alternatives()
+            .iter()
+            .map(|it| it.to_string())
+            .collect_vec()
+    }
+
+    pub(crate) fn warnings(&self) -> Vec<String> {
+        self.ast.warnings()
+    }
+
+    pub(crate) fn diff(&self, other: &RegExp) -> Vec<String> {
+        self.ast.diff(&other.ast)
+    }
+
+    pub(crate) fn explain(&self) -> String {
+        format!("matches {}", self.ast.explain())
     }
 
-    fn convert_to_lowercase(test_cases: &mut Vec<String>) {
-        *test_cases = test_cases.iter().map(|it| it.to_lowercase()).collect_vec();
+    pub(crate) fn shortest_matches(&self, count: usize) -> Vec<String> {
+        self.ast.shortest_matches(count)
     }
 
-    fn sort(test_cases: &mut Vec<String>) {
+    pub(crate) fn required_prefix(&self) -> String {
+        self.ast.required_prefix()
+    }
+
+    pub(crate) fn maximum_match_length(&self) -> Option<usize> {
+        self.ast.maximum_match_length()
+    }
+
+    fn convert_to_lowercase(test_cases: &mut [Cow<str>]) {
+        for test_case in test_cases.iter_mut() {
+            if test_case
+                .chars()
+                .any(|c| !c.to_lowercase().eq(std::iter::once(c)))
+            {
+                *test_case = Cow::Owned(test_case.to_lowercase());
+            }
+        }
+    }
+
+    fn replace_dashes(test_cases: &mut [Cow<str>], replacement: char) {
+        for test_case in test_cases.iter_mut() {
+            if test_case.chars().any(is_dash) {
+                *test_case = Cow::Owned(
+                    test_case
+                        .chars()
+                        .map(|c| if is_dash(c) { replacement } else { c })
+                        .collect::<String>(),
+                );
+            }
+        }
+    }
+
+    fn remove_leading_zeros(test_cases: &mut [Cow<str>]) {
+        for test_case in test_cases.iter_mut() {
+            if is_number(test_case) && test_case.len() > 1 && test_case.starts_with('0') {
+                // A number consisting of zeros only keeps its last zero.
+                let trimmed = test_case.trim_start_matches('0');
+                let trimmed = if trimmed.is_empty() { "0" } else { trimmed };
+                *test_case = Cow::Owned(trimmed.to_string());
+            }
+        }
+    }
+
+    fn sort(test_cases: &mut Vec<Cow<str>>) {
         test_cases.sort();
         test_cases.dedup();
         test_cases.sort_by(|a, b| match a.len().cmp(&b.len()) {
-            Ordering::Equal => a.cmp(&b),
+            Ordering::Equal => a.cmp(b),
             other => other,
         });
     }
 
-    fn grapheme_clusters(test_cases: &[String], config: &RegExpConfig) -> Vec<GraphemeCluster> {
+    fn grapheme_clusters(
+        test_cases: &[Cow<str>],
+        config: &Rc<RegExpConfig>,
+    ) -> Vec<GraphemeCluster> {
         let mut clusters = test_cases
             .iter()
             .map(|it| GraphemeCluster::from(it, config))
             .collect_vec();
 
-        if config.is_char_class_feature_enabled() {
+        if let Some(wildcard) = config.wildcard {
             for cluster in clusters.iter_mut() {
-                cluster.convert_to_char_classes();
+                cluster.convert_wildcards(wildcard);
             }
         }
 
-        if config.is_repetition_converted() {
+        if config.is_char_class_feature_enabled() {
             for cluster in clusters.iter_mut() {
-                cluster.convert_repetitions();
+                cluster.convert_to_char_classes();
             }
         }
 
@@ -82,60 +575,286 @@ impl RegExp {
 
 impl Display for RegExp {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let ignore_case_flag = if self.config.is_case_insensitive_matching() {
-            Component::IgnoreCaseFlag.to_repr(self.config.is_output_colorized)
-        } else {
+        // In verbose mode, the flags are written on a separate line together with the flag x.
+        let inline_flags = self.config.inline_flags();
+        let inline_flags = if inline_flags.is_empty() || self.config.is_verbose_mode_enabled {
             String::new()
+        } else {
+            Component::InlineFlags(inline_flags).to_repr(self.config.is_output_colorized)
         };
-        let caret = Component::Caret.to_repr(self.config.is_output_colorized);
-        let dollar_sign = Component::DollarSign.to_repr(self.config.is_output_colorized);
-        let mut regexp = match self.ast {
-            Expression::Alternation(_, _) => {
-                format!(
-                    "{}{}{}{}",
-                    ignore_case_flag,
-                    caret,
-                    if self.config.is_capturing_group_enabled() {
-                        Component::CapturedParenthesizedExpression(self.ast.to_string())
-                            .to_repr(self.config.is_output_colorized)
-                    } else {
-                        Component::UncapturedParenthesizedExpression(self.ast.to_string())
-                            .to_repr(self.config.is_output_colorized)
-                    },
-                    dollar_sign
-                )
+        let caret = match self.config.start_anchor {
+            Anchor::None => String::new(),
+            Anchor::StringBoundary => Component::Caret.to_repr(self.config.is_output_colorized),
+            Anchor::WordBoundary => {
+                Component::WordBoundary.to_repr(self.config.is_output_colorized)
+            }
+        };
+        let dollar_sign = match self.config.end_anchor {
+            Anchor::None => String::new(),
+            Anchor::StringBoundary => {
+                Component::DollarSign.to_repr(self.config.is_output_colorized)
+            }
+            Anchor::WordBoundary => {
+                Component::WordBoundary.to_repr(self.config.is_output_colorized)
+            }
+        };
+        let is_grouping_needed = self.is_grouping_needed();
+        let is_length_bucketed = match &self.ast {
+            Expression::Alternation(options, _) => {
+                options.len() == self.branch_comments.len()
+                    && !options.iter().any(|option| option.is_empty())
+            }
+            _ => false,
+        };
+        let expr = match &self.ast {
+            Expression::Alternation(options, _) if self.config.is_branch_named => {
+                format_named_branches(options, &self.config)
             }
-            _ => {
+            Expression::Alternation(options, _) if is_length_bucketed => {
+                format_grouped_branches(options, &self.config)
+            }
+            _ => self.ast.to_string(),
+        };
+        let regexp = match is_grouping_needed {
+            true => {
                 format!(
                     "{}{}{}{}",
-                    ignore_case_flag,
+                    inline_flags,
                     caret,
-                    self.ast.to_string(),
+                    Component::group(expr, self.ast.is_fixed_length(), &self.config),
                     dollar_sign
                 )
             }
+            false => {
+                format!("{}{}{}{}", inline_flags, caret, expr, dollar_sign)
+            }
         };
 
-        if regexp.contains('\u{b}') {
-            regexp = regexp.replace("\u{b}", "\\v"); // U+000B Line Tabulation
+        let regexp = if self.config.is_verbose_mode_enabled {
+            let mut verbose_regexp = apply_verbose_mode(regexp, &self.config);
+            if is_length_bucketed {
+                // The branches are nested within the start anchor and the enclosing group.
+                let branch_nesting_level =
+                    usize::from(self.config.start_anchor == Anchor::StringBoundary)
+                        + usize::from(is_grouping_needed);
+                verbose_regexp = insert_branch_comments(
+                    verbose_regexp,
+                    &self.branch_comments,
+                    branch_nesting_level,
+                    &self.config,
+                );
+            }
+            if let Some(separator) = &self.config.alternation_separator {
+                verbose_regexp = replace_pipes(verbose_regexp, separator, &self.config);
+            }
+            if self.config.is_test_case_comment_added {
+                verbose_regexp = insert_test_case_comment(verbose_regexp, &self.test_cases);
+            }
+            verbose_regexp
+        } else {
+            regexp
+        };
+
+        write!(f, "{}", self.config.output_wrapper.wrap(&regexp))
+    }
+}
+
+fn format_named_branches(options: &[Expression], config: &RegExpConfig) -> String {
+    options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            Component::NamedParenthesizedExpression(format!("b{}", i), option.to_string())
+                .to_repr(config.is_output_colorized)
+        })
+        .join(&Component::Pipe.to_repr(config.is_output_colorized))
+}
+
+/// Writes the branches of the top-level alternation like an alternation of groups,
+/// so that branches which are alternations themselves are kept apart from each other.
+fn format_grouped_branches(options: &[Expression], config: &RegExpConfig) -> String {
+    options
+        .iter()
+        .map(|option| {
+            if option.precedence() < CONCATENATION_PRECEDENCE && !option.is_single_codepoint() {
+                Component::group(option.to_string(), option.is_fixed_length(), config)
+            } else {
+                option.to_string()
+            }
+        })
+        .join(&Component::Pipe.to_repr(config.is_output_colorized))
+}
+
+/// Inserts each comment in front of the respective branch of the top-level alternation.
+/// The branches start right after the line opening their nesting level and after
+/// each pipe on this level.
+fn insert_branch_comments(
+    verbose_regexp: String,
+    comments: &[String],
+    nesting_level: usize,
+    config: &RegExpConfig,
+) -> String {
+    let indentation = "  ".repeat(nesting_level);
+    let pipe = format!(
+        "{}{}",
+        indentation,
+        Component::Pipe.to_repr(config.is_output_colorized)
+    );
+    let mut comments = comments.iter();
+    let mut is_branch_start = true;
+    let mut lines = vec![];
+
+    // The first line holds the verbose mode flag.
+    for (i, line) in verbose_regexp.lines().enumerate() {
+        let line_nesting_level = (line.len() - line.trim_start_matches(' ').len()) / 2;
+        if i > 0 && is_branch_start && line_nesting_level == nesting_level {
+            if let Some(comment) = comments.next() {
+                lines.push(format!("{}{}", indentation, comment));
+            }
+            is_branch_start = false;
         }
+        lines.push(line.to_string());
+        if line == pipe {
+            is_branch_start = true;
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Replaces each line consisting of a pipe with the lines of the separator, which are indented
+/// like the pipe. Empty lines of the separator are kept empty to avoid trailing whitespace.
+fn replace_pipes(verbose_regexp: String, separator: &str, config: &RegExpConfig) -> String {
+    let pipe = Component::Pipe.to_repr(config.is_output_colorized);
+    let separator_lines = separator
+        .split('\n')
+        .map(|line| {
+            let (content, comment) = match line.find('#') {
+                Some(position) => line.split_at(position),
+                None => (line, ""),
+            };
+            format!("{}{}", content.replace('|', &pipe), comment)
+        })
+        .collect_vec();
+    let mut lines = vec![];
 
-        write!(
-            f,
-            "{}",
-            if self.config.is_verbose_mode_enabled {
-                apply_verbose_mode(regexp, &self.config)
+    for line in verbose_regexp.lines() {
+        let content = line.trim_start_matches(' ');
+        if content != pipe {
+            lines.push(line.to_string());
+            continue;
+        }
+        let indentation = &line[..line.len() - content.len()];
+        for separator_line in separator_lines.iter() {
+            if separator_line.trim().is_empty() {
+                lines.push(String::new());
             } else {
-                regexp
+                lines.push(format!("{}{}", indentation, separator_line));
             }
-        )
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn insert_test_case_comment(verbose_regexp: String, test_cases: &[String]) -> String {
+    let mut commented_test_cases = test_cases
+        .iter()
+        .take(MAXIMUM_COMMENTED_TEST_CASES)
+        .map(|it| it.escape_debug().to_string())
+        .collect_vec();
+    if test_cases.len() > MAXIMUM_COMMENTED_TEST_CASES {
+        commented_test_cases.push("...".to_string());
+    }
+    let comment = format!("# generated from: {}", commented_test_cases.join(", "));
+
+    // The comment must follow the verbose mode flag, otherwise it is not recognized as such.
+    match verbose_regexp.split_once('\n') {
+        Some((flag, remainder)) => format!("{}\n{}\n{}", flag, comment, remainder),
+        None => format!("{}\n{}", verbose_regexp, comment),
     }
 }
 
+fn is_number(test_case: &str) -> bool {
+    !test_case.is_empty() && test_case.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Splits a test case at the markers `open` and `close`, or returns an error if the markers
+/// are unbalanced. A marker preceded by a backslash is taken literally, and so is a backslash
+/// preceded by another backslash. All other backslashes are kept.
+fn split_at_optional_markers(
+    test_case: &str,
+    open: char,
+    close: char,
+) -> std::result::Result<Vec<MarkedPart>, Error> {
+    let unbalanced = || Error::UnbalancedOptionalMarkers {
+        test_case: test_case.to_string(),
+    };
+    // Each level of nesting holds the parts since its opening marker.
+    let mut levels = vec![vec![]];
+    let mut chars = test_case.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let literal = if c == '\\' {
+            chars
+                .next_if(|&next| next == open || next == close || next == '\\')
+                .unwrap_or(c)
+        } else if c == open {
+            levels.push(vec![]);
+            continue;
+        } else if c == close {
+            if levels.len() == 1 {
+                return Err(unbalanced());
+            }
+            let optional_parts = levels.pop().unwrap();
+            levels
+                .last_mut()
+                .unwrap()
+                .push(MarkedPart::Optional(optional_parts));
+            continue;
+        } else {
+            c
+        };
+        let parts = levels.last_mut().unwrap();
+        match parts.last_mut() {
+            Some(MarkedPart::Text(text)) => text.push(literal),
+            _ => parts.push(MarkedPart::Text(literal.to_string())),
+        }
+    }
+
+    if levels.len() == 1 {
+        Ok(levels.pop().unwrap())
+    } else {
+        Err(unbalanced())
+    }
+}
+
+/// Returns true if the expression contains an opening parenthesis which is neither
+/// escaped nor part of a character class and which is not followed by a question mark.
+fn contains_capturing_group(regexp: &str) -> bool {
+    let mut chars = regexp.chars().peekable();
+    let mut is_within_char_class = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => is_within_char_class = true,
+            ']' => is_within_char_class = false,
+            '(' if !is_within_char_class && chars.peek() != Some(&'?') => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
 fn apply_verbose_mode(regexp: String, config: &RegExpConfig) -> String {
     lazy_static! {
         static ref ASTERISK: String = Component::Asterisk.to_colored_string(true);
         static ref DIGIT: String = Component::CharClass("\\d".to_string()).to_colored_string(true);
+        static ref DOT_MATCHING_NEWLINE: String =
+            Component::DotMatchingNewline.to_colored_string(false);
         static ref HYPHEN: String = Component::Hyphen.to_colored_string(true);
         static ref LEFT_BRACKET: String = Component::LeftBracket.to_colored_string(true);
         static ref NON_DIGIT: String = Component::CharClass("\\D".to_string()).to_colored_string(true);
@@ -207,23 +926,25 @@ fn apply_verbose_mode(regexp: String, config: &RegExpConfig) -> String {
             Regex::new(r"\u{1b}\[\d+;\d+m[^\u{1b}]+\u{1b}\[0m|[^\u{1b}]+").unwrap();
         static ref VERBOSE_MODE_REGEX: Regex = Regex::new(
             r#"(?x)
-            \(\?i\)
+            \[\^\\n\] (?: [*+?] | \{ \d+ (?: ,\d* )? \} )?
             |
             \[[^\]]+\]
             |
-            \( (?: \?: )?
+            \(\?s:\.\) (?: [*+?] | \{ \d+ (?: ,\d* )? \} )?
+            |
+            \( (?: \?: | \?> | \?P<\w+> )?
             |
-            \) (?: \? | \{ \d+ (?: ,\d+ )? \} )?   
+            \) (?: \? | \{ \d+ (?: ,\d* )? \} )?   
             |   
             [\^|$]
             |
             (?:
                 (?: \\[\^$()|DdSsWw\\\ ] )+
-                (?: \\* [^\^$|()\\] )*
+                (?: \[\^\\n\] | \\* [^\^$|()\\] )*
             )+
             |
             (?:
-                (?: \\* [^\^$()|\\] )+
+                (?: \[\^\\n\] | \\* [^\^$()|\\] )+
                 (?: \\[\^$()|DdSsWw\\\ ] )*
             )+
             "#
@@ -231,20 +952,13 @@ fn apply_verbose_mode(regexp: String, config: &RegExpConfig) -> String {
         .unwrap();
     }
 
-    let verbose_mode_flag = if config.is_case_insensitive_matching() {
-        Component::IgnoreCaseAndVerboseModeFlag.to_repr(config.is_output_colorized)
-    } else {
-        Component::VerboseModeFlag.to_repr(config.is_output_colorized)
-    };
+    let inline_flags =
+        Component::InlineFlags(config.inline_flags()).to_repr(config.is_output_colorized);
 
-    let mut verbose_regexp = vec![verbose_mode_flag];
+    let mut verbose_regexp = vec![inline_flags];
     let mut nesting_level = 0;
 
     let regexp_with_replacements = regexp
-        .replace(
-            &Component::IgnoreCaseFlag.to_repr(config.is_output_colorized),
-            "",
-        )
         .replace("#", "\\#")
         .replace(" ", "\\s")
         .replace(" ", "\\s")
@@ -264,7 +978,9 @@ fn apply_verbose_mode(regexp: String, config: &RegExpConfig) -> String {
                 continue;
             }
 
-            let is_colored_element = element.starts_with("\u{1b}[");
+            // The dot matching newlines is a group syntactically, but not a nesting level.
+            let is_colored_element =
+                element.starts_with("\u{1b}[") && !element.contains(&*DOT_MATCHING_NEWLINE);
             if is_colored_element && (element.contains('$') || element.contains(')')) {
                 nesting_level -= 1;
             }
@@ -301,13 +1017,15 @@ fn apply_verbose_mode(regexp: String, config: &RegExpConfig) -> String {
             if element.is_empty() {
                 continue;
             }
+            let is_dot_matching_newline =
+                element.starts_with(&Component::DotMatchingNewline.to_string());
             if element == "$" || element.starts_with(')') {
                 nesting_level -= 1;
             }
             let indentation = "  ".repeat(nesting_level);
             verbose_regexp.push(format!("{}{}", indentation, element));
 
-            if element == "^" || element.starts_with('(') {
+            if element == "^" || (element.starts_with('(') && !is_dot_matching_newline) {
                 nesting_level += 1;
             }
         }
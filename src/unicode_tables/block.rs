@@ -0,0 +1,27 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This table is a hand-picked subset of the Unicode blocks whose names are
+// understood by both .NET and Java, listed with their full code point ranges.
+
+pub const BLOCKS: &[(&str, &[(char, char)])] = &[
+    ("BasicLatin", &[('\u{0}', '\u{7f}')]),
+    ("Latin-1Supplement", &[('\u{80}', '\u{ff}')]),
+    ("LatinExtended-A", &[('\u{100}', '\u{17f}')]),
+    ("LatinExtended-B", &[('\u{180}', '\u{24f}')]),
+    ("Greek", &[('\u{370}', '\u{3ff}')]),
+    ("Cyrillic", &[('\u{400}', '\u{4ff}')]),
+];
@@ -14,10 +14,16 @@
  * limitations under the License.
  */
 
+mod block;
+mod dash;
 mod decimal;
+mod script;
 mod space;
 mod word;
 
+pub use block::BLOCKS;
+pub use dash::{is_dash, DASH};
 pub use decimal::DECIMAL_NUMBER;
+pub use script::SCRIPTS;
 pub use space::WHITE_SPACE;
 pub use word::WORD;
@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 use assert_cmd::prelude::*;
 use indoc::indoc;
 use predicates::prelude::*;
@@ -125,7 +127,7 @@ mod no_conversion {
             grex.args(&["-f", file.path().to_str().unwrap()]);
             grex.assert()
                 .success()
-                .stdout(predicate::eq("^(?:b\\\\n|äöü|[ac♥])$\n"));
+                .stdout(predicate::eq("^(?:b\\\\n|äöü|[ac♥])?$\n"));
         }
 
         #[test]
@@ -3791,6 +3793,34 @@ mod word_non_word_conversion {
     }
 }
 
+mod posix_char_class_conversion {
+    use super::*;
+
+    #[test]
+    fn succeeds() {
+        let mut grex = init_command();
+        grex.args(&["--digits", "--spaces", "--posix-classes", "12 34"]);
+        grex.assert().success().stdout(predicate::eq(
+            "^[[:digit:]][[:digit:]][[:space:]][[:digit:]][[:digit:]]$\n",
+        ));
+    }
+
+    #[test]
+    fn succeeds_with_repetition_option() {
+        let mut grex = init_command();
+        grex.args(&[
+            "--digits",
+            "--spaces",
+            "--repetitions",
+            "--posix-classes",
+            "12 34",
+        ]);
+        grex.assert()
+            .success()
+            .stdout(predicate::eq("^[[:digit:]]{2}[[:space:]][[:digit:]]{2}$\n"));
+    }
+}
+
 fn init_command() -> Command {
     Command::cargo_bin("grex").unwrap()
 }
@@ -0,0 +1,64 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod fixtures;
+
+use fixtures::{mixed_test_cases, numeric_test_cases, word_test_cases};
+use grex::{Feature, RegExpBuilder};
+use rstest::rstest;
+
+#[rstest(
+    test_cases,
+    maximum_length,
+    case(numeric_test_cases(), 2680),
+    case(word_test_cases(), 314),
+    case(mixed_test_cases(), 702)
+)]
+fn output_length_does_not_regress_with_default_settings(
+    test_cases: Vec<String>,
+    maximum_length: usize,
+) {
+    let regexp = RegExpBuilder::from(&test_cases).build();
+    assert_that_length_does_not_regress(&regexp, maximum_length);
+}
+
+#[rstest(
+    test_cases,
+    maximum_length,
+    case(numeric_test_cases(), 9),
+    case(word_test_cases(), 9),
+    case(mixed_test_cases(), 61)
+)]
+fn output_length_does_not_regress_with_conversion_features(
+    test_cases: Vec<String>,
+    maximum_length: usize,
+) {
+    let regexp = RegExpBuilder::from(&test_cases)
+        .with_conversion_of(&[Feature::Digit, Feature::Word, Feature::Repetition])
+        .build();
+    assert_that_length_does_not_regress(&regexp, maximum_length);
+}
+
+fn assert_that_length_does_not_regress(regexp: &str, maximum_length: usize) {
+    let length = regexp.chars().count();
+    assert!(
+        length <= maximum_length,
+        "\n\nlength of regex has regressed from {} to {} characters:\n{}\n\n",
+        maximum_length,
+        length,
+        regexp
+    );
+}
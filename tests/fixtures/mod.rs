@@ -0,0 +1,75 @@
+/*
+ * Copyright © 2019-today Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Test cases shared by the benchmarks and the tests guarding against regressions,
+//! so that both of them measure the same inputs.
+
+#![allow(dead_code)]
+
+pub fn numeric_test_cases() -> Vec<String> {
+    (0..500).map(|it| (it * 37 + 1000).to_string()).collect()
+}
+
+pub fn word_test_cases() -> Vec<String> {
+    [
+        "abandon", "ability", "able", "about", "above", "absent", "absorb", "abstract", "absurd",
+        "abuse", "access", "accident", "account", "accuse", "achieve", "acid", "acoustic",
+        "acquire", "across", "act", "action", "actor", "actress", "actual", "adapt", "add",
+        "addict", "address", "adjust", "admit", "adult", "advance", "advice", "aerobic", "affair",
+        "afford", "afraid", "again", "age", "agent", "agree", "ahead", "aim", "air", "airport",
+        "aisle", "alarm", "album", "alcohol", "alert",
+    ]
+    .iter()
+    .map(|it| it.to_string())
+    .collect()
+}
+
+pub fn mixed_test_cases() -> Vec<String> {
+    (0..100)
+        .map(|it| format!("user-{}@host{}.example.com", it * 7, it % 13))
+        .collect()
+}
+
+/// A linear congruential generator, so that random test cases are reproducible
+/// without depending on a crate for random numbers.
+pub struct Lcg(u64);
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0
+    }
+}
+
+/// Distinct random strings barely share any prefixes or suffixes,
+/// so most pairs of states of their automaton are not connected.
+pub fn random_lowercase_test_cases(seed: u64, count: usize, length: usize) -> Vec<String> {
+    let mut lcg = Lcg::new(seed);
+    (0..count)
+        .map(|_| {
+            (0..length)
+                .map(|_| (b'a' + (lcg.next_u64() >> 59) as u8 % 26) as char)
+                .collect()
+        })
+        .collect()
+}
@@ -14,6 +14,9 @@
  * limitations under the License.
  */
 
+mod fixtures;
+
+use fixtures::random_lowercase_test_cases;
 use grex::{Feature, RegExpBuilder};
 use indoc::indoc;
 use regex::Regex;
@@ -68,7 +71,7 @@ mod no_conversion {
             case(vec!["abc", "abcde"], "^abc(?:de)?$"),
             case(vec!["ade", "abcde"], "^a(?:bc)?de$"),
             case(vec!["abcxy", "adexy"], "^a(?:bc|de)xy$"),
-            case(vec!["axy", "abcxy", "adexy"], "^a(?:(?:bc)?|de)xy$"), // goal: "^a(bc|de)?xy$"
+            case(vec!["axy", "abcxy", "adexy"], "^a(?:bc|de)?xy$"),
             case(vec!["abcxy", "abcw", "efgh"], "^(?:abc(?:xy|w)|efgh)$"),
             case(vec!["abcxy", "efgh", "abcw"], "^(?:abc(?:xy|w)|efgh)$"),
             case(vec!["efgh", "abcxy", "abcw"], "^(?:abc(?:xy|w)|efgh)$"),
@@ -76,11 +79,20 @@ mod no_conversion {
             case(vec!["abxy", "efgh", "cxy"], "^(?:(?:ab|c)xy|efgh)$"),
             case(vec!["efgh", "abxy", "cxy"], "^(?:(?:ab|c)xy|efgh)$"),
             case(vec!["a", "ä", "o", "ö", "u", "ü"], "^[aouäöü]$"),
+            case(vec!["\u{d7fe}", "\u{d7ff}", "\u{e000}", "\u{e001}"], "^[\u{d7fe}\u{d7ff}\u{e000}\u{e001}]$"),
+            case(vec!["\u{d7fd}", "\u{d7fe}", "\u{d7ff}", "\u{e000}", "\u{e001}", "\u{e002}"], "^[\u{d7fd}-\u{d7ff}\u{e000}-\u{e002}]$"),
+            case(vec!["👨‍👩‍👧x", "👨‍👩‍👧y"], "^👨‍👩‍👧[xy]$"),
+            case(vec!["👨‍👩‍👧x", "👨‍👩‍👧yz"], "^👨‍👩‍👧(?:yz|x)$"),
+            case(vec!["a👍🏽b", "c👍🏽b"], "^[ac]👍🏽b$"),
             case(vec!["y̆", "a", "z"], "^(?:y̆|[az])$"), // goal: "^[az]|y\\u{306}$"
             case(vec!["a", "b\n", "c"], "^(?:b\\n|[ac])$"),
             case(vec!["a", "b\\n", "c"], "^(?:b\\\\n|[ac])$"),
             case(vec!["[a-z]", "(d,e,f)"], "^(?:\\(d,e,f\\)|\\[a\\-z\\])$"),
             case(vec!["3.5", "4.5", "4,5"], "^(?:3\\.5|4[,.]5)$"),
+            case(vec!["fooabar", "foobbar", "foocbar"], "^foo[a-c]bar$"),
+            case(vec!["b", "bcc", "d"], "^(?:bcc|[bd])$"),
+            case(vec!["ada", "b", "bb", "c"], "^(?:ada|bb|[bc])$"),
+            case(vec!["ab", "b", "db", "dba", "dd"], "^(?:d(?:ba|[bd])|a?b)$"),
             case(vec!["\u{b}"], "^\\v$"), // U+000B Line Tabulation
             case(vec!["\\u{b}"], "^\\\\u\\{b\\}$"),
             case(vec!["I ♥ cake"], "^I ♥ cake$"),
@@ -336,9 +348,15 @@ mod no_conversion {
             case(vec!["abaaaabaaba"], "^abaa(?:a{2}b){2}a$"),
             case(vec!["bbaababb"], "^b{2}a{2}bab{2}$"),
             case(vec!["b", "ba"], "^ba?$"),
-            case(vec!["b", "ba", "baa"], "^b(?:a{1,2})?$"),
-            case(vec!["b", "ba", "baaa", "baa"], "^b(?:a{1,3})?$"),
+            case(vec!["b", "ba", "baa"], "^ba{0,2}$"),
+            case(vec!["b", "ba", "baaa", "baa"], "^ba{0,3}$"),
             case(vec!["b", "ba", "baaaa", "baa"], "^b(?:a{1,2}|a{4})?$"),
+            case(vec!["", "x", "xx"], "^x{0,2}$"),
+            case(vec!["", "ab", "abab"], "^(?:ab){0,2}$"),
+            case(vec!["", "ab", "abab", "ababab"], "^(?:ab){0,3}$"),
+            case(vec!["", "ab", "ababab"], "^(?:ab|(?:ab){3})?$"),
+            case(vec!["c", "cab", "cabab"], "^c(?:ab){0,2}$"),
+            case(vec!["x", "xy̆", "xy̆y̆"], "^x(?:y̆){0,2}$"),
             case(vec!["axy", "abcxyxy", "adexy"], "^a(?:(?:de)?xy|bc(?:xy){2})$"),
             case(vec!["xy̆y̆y̆y̆z"], "^x(?:y̆){4}z$"),
             case(vec!["xy̆y̆z", "xy̆y̆y̆z"], "^x(?:y̆){2,3}z$"),
@@ -575,6 +593,7 @@ mod no_conversion {
             case(vec!["aabababab"], "^a(?:ab){4}$"),
             case(vec!["xy̆y̆z", "xy̆y̆y̆y̆z"], "^x(?:y̆y̆|(?:y̆){4})z$"),
             case(vec!["aaa", "a", "aa"], "^a(?:aa?)?$"),
+            case(vec!["", "ab", "abab", "ababab"], "^(?:ab(?:ab(?:ab)?)?)?$"),
             case(vec!["a", "aa", "aaa", "aaaa"], "^(?:aaa|aa?|a{4})$"),
             case(vec!["a", "aa", "aaa", "aaaa", "aaaaa", "aaaaaa"], "^(?:aaa|aa?|a{4,6})$")
         )]
@@ -1941,11 +1960,3746 @@ mod word_non_word_conversion {
     }
 }
 
-fn assert_that_regexp_is_correct(regexp: String, expected_output: &str, test_cases: &[&str]) {
-    assert_eq!(
-        regexp, expected_output,
-        "\n\ninput: {:?}\nexpected: {}\nactual: {}\n\n",
-        test_cases, expected_output, regexp
+mod posix_char_class_conversion {
+    use super::*;
+
+    #[rstest(test_cases, conversion_features, expected_output,
+        case(vec!["1", "2"], vec![Feature::Digit], "^[[:digit:]]$"),
+        case(vec!["a b"], vec![Feature::Space], "^a[[:space:]]b$"),
+        case(vec!["a_1"], vec![Feature::Word], "^[[:alnum:]_][[:alnum:]_][[:alnum:]_]$"),
+        case(vec!["a1"], vec![Feature::NonDigit], "^[^[:digit:]]1$"),
+        case(vec!["a b"], vec![Feature::NonSpace], "^[^[:space:]] [^[:space:]]$"),
+        case(vec!["a.b"], vec![Feature::NonWord], "^a[^[:alnum:]_]b$"),
+        case(vec!["a1", "b22"], vec![Feature::Digit, Feature::Word], "^[[:alnum:]_][[:digit:]](?:[[:digit:]])?$"),
+        case(vec!["123", "4567"], vec![Feature::Digit, Feature::Repetition], "^[[:digit:]]{3,4}$"),
+        case(vec!["a1", "a12"], vec![Feature::Digit, Feature::Repetition], "^a[[:digit:]]{1,2}$"),
+        case(vec!["ab12ab12"], vec![Feature::Digit, Feature::Repetition], "^(?:ab[[:digit:]]{2}){2}$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, conversion_features: Vec<Feature>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&conversion_features)
+            .with_posix_char_classes()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn succeeds_without_conversion_features() {
+        let test_cases = vec!["a", "b", "-"];
+        let expected_output = "^[\\-ab]$";
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_posix_char_classes()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod preprocessing {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc", "abd"], "^AB[CD]$"),
+        case(vec!["abc", "ABC", "aBc"], "^ABC$"),
+        case(vec!["ä", "ö"], "^[ÄÖ]$")
+    )]
+    fn succeeds_with_uppercasing_preprocessor(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_preprocessor(|test_case| test_case.to_uppercase())
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        let uppercased_test_cases = test_cases
+            .iter()
+            .map(|it| it.to_uppercase())
+            .collect::<Vec<_>>();
+        assert_that_regexp_matches_test_cases(
+            expected_output,
+            uppercased_test_cases.iter().map(|it| it.as_str()).collect(),
+        );
+    }
+
+    #[test]
+    fn succeeds_with_prefix_stripping_preprocessor() {
+        let test_cases = vec!["id-123", "id-124", "125"];
+        let expected_output = "^12[3-5]$";
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_preprocessor(|test_case| test_case.trim_start_matches("id-").to_string())
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[test]
+    fn succeeds_when_building_repeatedly() {
+        let test_cases = vec!["ab"];
+        let mut builder = RegExpBuilder::from(&test_cases);
+        builder.with_preprocessor(|test_case| format!("{}!", test_case));
+        assert_eq!(builder.build(), "^ab!$");
+        assert_eq!(builder.build(), "^ab!$");
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["caf\u{e9}", "cafe\u{301}"], "^caf\u{e9}$"),
+        case(vec!["e\u{301}", "\u{e9}", "a"], "^[a\u{e9}]$")
+    )]
+    fn succeeds_with_normalizing_preprocessor(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_preprocessor(compose_e_acute)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_summed_weights_of_normalized_test_cases() {
+        let test_cases = vec![("caf\u{e9}", 3), ("cafe\u{301}", 3), ("tea", 4)];
+        let regexp = RegExpBuilder::from_weighted(&test_cases)
+            .with_preprocessor(compose_e_acute)
+            .with_minimum_weight(5)
+            .build();
+        assert_eq!(regexp, "^caf\u{e9}$");
+    }
+
+    /// Composes the decomposed form of `é` only, which is all that the tests need
+    /// of Unicode normalization form C.
+    fn compose_e_acute(test_case: &str) -> String {
+        test_case.replace("e\u{301}", "\u{e9}")
+    }
+}
+
+mod braced_quantifiers {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a"], "^a$"),
+        case(vec!["ab", "abc"], "^abc{0,1}$"),
+        case(vec!["a", "b", "bcd"], "^(?:b(?:cd){0,1}|a)$"),
+        case(vec!["a", "ab", "abc"], "^a(?:bc{0,1}){0,1}$"),
+        case(vec!["axy", "abcxy", "adexy"], "^a(?:bc|de){0,1}xy$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_braced_quantifiers()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["aa", "aaa"], "^a{2,3}$"),
+        case(vec!["abab", "ababab"], "^(?:ab){2,3}$")
+    )]
+    fn succeeds_with_repetition_option(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Repetition])
+            .with_braced_quantifiers()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod incremental_test_cases {
+    use super::*;
+
+    #[rstest(test_cases,
+        case(vec!["a"]),
+        case(vec!["a", "b", "bcd"]),
+        case(vec!["efgh", "abcxy", "abcw"]),
+        case(vec!["abc", "abc", "ab"])
+    )]
+    fn succeeds_with_same_result_as_batch_construction(test_cases: Vec<&str>) {
+        let mut builder = RegExpBuilder::new();
+        for test_case in test_cases.iter() {
+            builder.add_test_case(*test_case);
+        }
+        let expected_output = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(builder.build(), &expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(&expected_output, test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_test_cases_added_after_build() {
+        let mut builder = RegExpBuilder::new();
+        builder.add_test_case("a");
+        assert_eq!(builder.build(), "^a$");
+        builder.add_test_case("b");
+        assert_eq!(builder.build(), "^[ab]$");
+        builder.add_test_case("bc");
+        assert_eq!(builder.build(), "^(?:bc?|a)$");
+    }
+
+    #[test]
+    fn succeeds_with_test_cases_added_to_existing_ones() {
+        let regexp = RegExpBuilder::from(&["a", "b"])
+            .add_test_case("bc")
+            .with_conversion_of(&[Feature::CapturingGroup])
+            .build();
+        assert_eq!(regexp, "^(bc?|a)$");
+    }
+}
+
+mod alternation_branch_limit {
+    use super::*;
+    use grex::Error;
+
+    #[rstest(test_cases, limit, expected_output,
+        case(vec!["abc", "def", "ghi"], 3, "^(?:abc|def|ghi)$"),
+        case(vec!["abc", "def", "ghi"], 2, "^[adg].*$"),
+        case(vec!["abc", "axy", "a"], 2, "^a(?:bc|xy)?$"),
+        case(vec!["bc", "xy", "z"], 2, "^[bxz].*$"),
+        case(vec!["abc", "aef", "ahi"], 2, "^a.*$"),
+        case(vec!["abc", "def", "ghi", ""], 2, "^(?:[adg].*)?$"),
+        case(vec!["a", "b", "c"], 1, "^[a-c]$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, limit: u32, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_maximum_alternation_branches(limit)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc", "d\nef", "ghi"], "^[adg](?s:.)*$"),
+        case(vec!["a\r\nb", "c\r\nd", "e\r\nf"], "^[ace](?s:.)*$")
+    )]
+    fn succeeds_with_multiline_test_cases(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_maximum_alternation_branches(2)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, features, expected_output,
+        case(vec!["1ab", "2cd", "3ef"], vec![Feature::Digit], "^\\d.*$"),
+        case(vec!["1ab", "2cd", "xef"], vec![Feature::Digit], "^(?:\\d|x).*$"),
+        case(vec!["a b", "c d", " ef"], vec![Feature::Space, Feature::Word], "^(?:\\s|\\w).*$")
+    )]
+    fn succeeds_with_conversion_of_first_chars(
+        test_cases: Vec<&str>,
+        features: Vec<Feature>,
+        expected_output: &str,
+    ) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&features)
+            .with_maximum_alternation_branches(1)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_error_mode_within_limit() {
+        let regexp = RegExpBuilder::from(&["abc", "def", "ghi"])
+            .with_maximum_alternation_branches(3)
+            .with_error_instead_of_alternation_fallback()
+            .try_build();
+        assert_eq!(regexp, Ok("^(?:abc|def|ghi)$".to_string()));
+    }
+
+    #[test]
+    fn succeeds_with_fallback_mode_above_limit() {
+        let regexp = RegExpBuilder::from(&["abc", "def", "ghi"])
+            .with_maximum_alternation_branches(2)
+            .try_build();
+        assert_eq!(regexp, Ok("^[adg].*$".to_string()));
+    }
+
+    #[test]
+    fn fails_with_error_mode_above_limit() {
+        let regexp = RegExpBuilder::from(&["abc", "def", "ghi"])
+            .with_maximum_alternation_branches(2)
+            .with_error_instead_of_alternation_fallback()
+            .try_build();
+        assert_eq!(
+            regexp,
+            Err(Error::TooManyAlternationBranches {
+                branch_count: 3,
+                limit: 2
+            })
+        );
+    }
+
+    #[test]
+    fn succeeds_with_error_mode_and_disabled_repetitions() {
+        let regexp = RegExpBuilder::from(&["abc", "def"])
+            .with_maximum_alternation_branches(2)
+            .with_error_instead_of_alternation_fallback()
+            .with_disabled_repetitions()
+            .try_build();
+        assert_eq!(regexp, Ok("^(?:abc|def)$".to_string()));
+    }
+}
+
+mod anchors {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc"], "^abc$"),
+        case(vec!["a", "b"], "^[ab]$"),
+        case(vec!["ab", "ac"], "^a[bc]$"),
+        case(vec!["ab", "abc"], "^abc?$"),
+        case(vec!["a", "ab"], "^ab?$"),
+        case(vec!["abc", "abcde"], "^abc(?:de)?$"),
+        case(vec!["", "abc"], "^(?:abc)?$"),
+        case(vec!["abc", "def"], "^(?:abc|def)$"),
+        case(vec!["a", "bc"], "^(?:bc|a)$")
+    )]
+    fn succeeds_with_group_only_around_low_precedence_expressions(
+        test_cases: Vec<&str>,
+        expected_output: &str,
+    ) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod dashes {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a-b", "a–b"], "^a[\\-–]b$"),
+        case(vec!["a-b", "a–b", "a—b"], "^a[\\-–—]b$"),
+        case(vec!["1-2", "1–2", "3"], "^(?:1[\\-–]2|3)$")
+    )]
+    fn succeeds_without_dash_option(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a-b", "a–b"], "^a\\-b$"),
+        case(vec!["a-b", "a–b", "a—b", "a−b"], "^a\\-b$"),
+        case(vec!["1–2", "3"], "^(?:1\\-2|3)$")
+    )]
+    fn succeeds_with_replacement_of_dashes(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_replacement_of_dashes('-')
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a-b", "a–b"], "^a[\\-‐-―−﹘﹣－]b$"),
+        case(vec!["a-b", "a–b", "a—b"], "^a[\\-‐-―−﹘﹣－]b$"),
+        case(vec!["a-b", "axb"], "^a[\\-x]b$"),
+        case(vec!["a-b"], "^a\\-b$")
+    )]
+    fn succeeds_with_extension_of_dash_classes(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_extension_of_dash_classes()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, vec!["a-b"]);
+    }
+}
+
+mod shortest_matches {
+    use super::*;
+
+    #[rstest(test_cases, count, expected_matches,
+        case(vec!["abc"], 5, vec!["abc"]),
+        case(vec!["a", "aa", "aaa"], 5, vec!["a", "aa", "aaa"]),
+        case(vec!["a", "aa", "aaa"], 2, vec!["a", "aa"]),
+        case(vec!["bc", "a", ""], 5, vec!["", "a", "bc"]),
+        case(vec!["ab", "ac", "db"], 2, vec!["ab", "ac"]),
+        case(vec!["ab", "ac", "db"], 0, vec![])
+    )]
+    fn succeeds_with_default_settings(
+        test_cases: Vec<&str>,
+        count: usize,
+        expected_matches: Vec<&str>,
+    ) {
+        let matches = RegExpBuilder::from(&test_cases).build_shortest_matches(count);
+        assert_eq!(matches, expected_matches);
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_matches_test_cases(&regexp, matches.iter().map(|it| &**it).collect());
+    }
+
+    #[rstest(test_cases, count, expected_matches,
+        case(vec!["a1", "b22", "c333"], 3, vec!["a0", "a00", "a000"]),
+        case(vec!["1.5", "10.25"], 5, vec!["0.0", "00.00"])
+    )]
+    fn succeeds_with_conversion_features(
+        test_cases: Vec<&str>,
+        count: usize,
+        expected_matches: Vec<&str>,
+    ) {
+        let mut builder = RegExpBuilder::from(&test_cases);
+        builder.with_conversion_of(&[Feature::Digit, Feature::Word]);
+        let matches = builder.build_shortest_matches(count);
+        assert_eq!(matches, expected_matches);
+        let regexp = builder.build();
+        assert_that_regexp_matches_test_cases(&regexp, matches.iter().map(|it| &**it).collect());
+    }
+
+    #[rstest(test_cases, count, expected_matches,
+        case(vec!["aa", "aaaa", "abab"], 5, vec!["aa", "aaaa", "abab"]),
+        case(vec!["xyxyxy"], 5, vec!["xyxyxy"])
+    )]
+    fn succeeds_with_repetition_conversion(
+        test_cases: Vec<&str>,
+        count: usize,
+        expected_matches: Vec<&str>,
+    ) {
+        let mut builder = RegExpBuilder::from(&test_cases);
+        builder.with_conversion_of(&[Feature::Repetition]);
+        let matches = builder.build_shortest_matches(count);
+        assert_eq!(matches, expected_matches);
+        let regexp = builder.build();
+        assert_that_regexp_matches_test_cases(&regexp, matches.iter().map(|it| &**it).collect());
+    }
+}
+
+mod separators {
+    use super::*;
+
+    #[rstest(test_cases, separators, expected_output,
+        case(vec!["123"], vec![' ', '-'], "^1[ \\-]?2[ \\-]?3$"),
+        case(vec!["123"], vec!['.'], "^1\\.?2\\.?3$"),
+        case(vec!["a", "ab", "abc"], vec![' '], "^a ?(?:b ?c?)?$"),
+        case(vec!["ab", "cd"], vec!['/'], "^(?:a/?b|c/?d)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, separators: Vec<char>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_separators(&separators)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn succeeds_in_matching_separated_characters() {
+        let regexp = RegExpBuilder::from(&["123"])
+            .with_separators(&[' ', '-'])
+            .build();
+        assert_that_regexp_matches_test_cases(&regexp, vec!["1-2-3", "1 2 3", "12-3"]);
+        assert!(!Regex::new(&regexp).unwrap().is_match("1--2-3"));
+    }
+}
+
+mod inner_word_boundaries {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc-12"], "^\\w{3}\\b\\-\\b\\d{2}$"),
+        case(vec!["(555) 123"], "^\\(\\b\\d{3}\\b\\) \\b\\d{3}$"),
+        case(vec!["hello, world!"], "^\\w{5}\\b, \\b\\w{5}\\b!$"),
+        case(vec!["ab", "cd"], "^\\w{2}$")
+    )]
+    fn succeeds_with_word_and_repetition_conversion(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Digit, Feature::Word, Feature::Repetition])
+            .with_inner_word_boundaries()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a.b"], "^a\\.b$"),
+        case(vec!["a1-2"], "^a\\d\\b\\-\\b\\d$")
+    )]
+    fn succeeds_with_digit_conversion(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Digit])
+            .with_inner_word_boundaries()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod weighted_test_cases {
+    use super::*;
+
+    #[rstest(test_cases, minimum_weight, expected_output,
+        case(vec![("abc", 10), ("abd", 8), ("xyz", 1)], 0, "^(?:ab[cd]|xyz)$"),
+        case(vec![("abc", 10), ("abd", 8), ("xyz", 1)], 2, "^ab[cd]$"),
+        case(vec![("abc", 10), ("abd", 8), ("xyz", 1)], 10, "^abc$"),
+        case(vec![("abc", 2), ("xyz", 1), ("xyz", 1)], 2, "^(?:abc|xyz)$")
+    )]
+    fn succeeds_with_minimum_weight(
+        test_cases: Vec<(&str, u64)>,
+        minimum_weight: u64,
+        expected_output: &str,
+    ) {
+        let regexp = RegExpBuilder::from_weighted(&test_cases)
+            .with_minimum_weight(minimum_weight)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[test]
+    fn succeeds_in_excluding_low_weight_outlier() {
+        let test_cases = vec![("2021-03-01", 50), ("2021-04-15", 30), ("n/a", 1)];
+        let regexp = RegExpBuilder::from_weighted(&test_cases)
+            .with_conversion_of(&[Feature::Digit, Feature::Repetition])
+            .with_minimum_weight(5)
+            .build();
+        assert_eq!(regexp, "^\\d{4}(?:\\-\\d{2}){2}$");
+        assert_that_regexp_matches_test_cases(&regexp, vec!["2021-03-01", "2021-04-15"]);
+        assert!(!Regex::new(&regexp).unwrap().is_match("n/a"));
+    }
+}
+
+mod conflicting_options {
+    use super::*;
+    use grex::Error;
+
+    #[test]
+    fn fails_with_positional_inference_and_length_buckets() {
+        let result = RegExpBuilder::from(&["ab", "cd"])
+            .with_length_buckets(5)
+            .with_positional_inference()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_positional_inference",
+                b: "with_length_buckets"
+            })
+        );
+    }
+
+    #[test]
+    fn fails_with_replacement_and_extension_of_dashes() {
+        let result = RegExpBuilder::from(&["a-b", "a–b"])
+            .with_replacement_of_dashes('-')
+            .with_extension_of_dash_classes()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_replacement_of_dashes",
+                b: "with_extension_of_dash_classes"
+            })
+        );
+    }
+
+    #[test]
+    fn fails_with_separators_and_inner_word_boundaries() {
+        let result = RegExpBuilder::from(&["abc-12"])
+            .with_separators(&[' '])
+            .with_inner_word_boundaries()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_separators",
+                b: "with_inner_word_boundaries"
+            })
+        );
+    }
+
+    #[test]
+    fn fails_with_posix_char_classes_and_inner_word_boundaries() {
+        let result = RegExpBuilder::from(&["abc-12"])
+            .with_conversion_of(&[Feature::Word])
+            .with_posix_char_classes()
+            .with_inner_word_boundaries()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_posix_char_classes",
+                b: "with_inner_word_boundaries"
+            })
+        );
+    }
+
+    #[test]
+    fn fails_with_char_classes_in_order_of_appearance_and_frequency() {
+        let result = RegExpBuilder::from(&["a", "b"])
+            .with_char_classes_in_order_of_appearance()
+            .with_char_classes_in_order_of_frequency()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_char_classes_in_order_of_appearance",
+                b: "with_char_classes_in_order_of_frequency"
+            })
+        );
+    }
+
+    #[test]
+    fn succeeds_without_conflicting_options() {
+        let result = RegExpBuilder::from(&["a-b", "a–b"])
+            .with_extension_of_dash_classes()
+            .with_separators(&[' '])
+            .try_build();
+        assert!(result.is_ok());
+    }
+}
+
+mod control_chars {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a\tb"], "^a\\tb$"),
+        case(vec!["\t", "\n"], "^[\\t\\n]$"),
+        case(vec!["a\u{1}", "a\u{7f}"], "^a[\\x01\\x7F]$"),
+        case(vec!["\u{1b}[0m"], "^\\x1B\\[0m$")
+    )]
+    fn succeeds_with_default_escaping(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a\tb"], "^a\\x09b$"),
+        case(vec!["\t", "\n"], "^[\\x09\\x0A]$"),
+        case(vec!["\r\n"], "^\\x0D\\x0A$"),
+        case(vec!["\u{b}"], "^\\x0B$"),
+        case(vec!["a\u{1}", "a\u{7f}"], "^a[\\x01\\x7F]$")
+    )]
+    fn succeeds_with_hex_escaping(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_hex_escaping_of_control_chars()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod prefix_matching {
+    use super::*;
+
+    #[rstest(test_cases, expected_output, prefixes,
+        case(vec!["abc"], "^a(?:bc?)?$", vec!["a", "ab", "abc"]),
+        case(vec!["abc", "abd"], "^a(?:b[cd]?)?$", vec!["a", "ab", "abc", "abd"]),
+        case(vec!["ab", "xy"], "^(?:ab?|xy?)$", vec!["a", "ab", "x", "xy"]),
+        case(vec!["", "a"], "^a?$", vec!["", "a"])
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str, prefixes: Vec<&str>) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_prefix_matching()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, prefixes);
+    }
+
+    #[test]
+    fn succeeds_in_not_matching_other_strings() {
+        let regexp = RegExpBuilder::from(&["abc"]).with_prefix_matching().build();
+        let re = Regex::new(&regexp).unwrap();
+        assert!(!re.is_match(""));
+        assert!(!re.is_match("b"));
+        assert!(!re.is_match("abcd"));
+    }
+}
+
+mod substring_matching {
+    use super::*;
+
+    #[rstest(test_cases, expected_output, substrings,
+        case(vec!["abc"], "^(?:a(?:bc?)?|bc?|c)$", vec!["a", "b", "c", "ab", "bc", "abc"]),
+        case(vec!["aa"], "^aa?$", vec!["a", "aa"]),
+        case(vec!["ab", "ba"], "^(?:ba?|ab?)$", vec!["a", "b", "ab", "ba"])
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str, substrings: Vec<&str>) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_substring_matching()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, substrings);
+    }
+
+    #[test]
+    fn succeeds_in_not_matching_other_strings() {
+        let regexp = RegExpBuilder::from(&["abc"])
+            .with_substring_matching()
+            .build();
+        let re = Regex::new(&regexp).unwrap();
+        assert!(!re.is_match(""));
+        assert!(!re.is_match("ac"));
+        assert!(!re.is_match("cb"));
+        assert!(!re.is_match("xbc"));
+    }
+}
+
+mod common_affix_factoring {
+    use super::*;
+
+    #[rstest(test_cases, prefixes, suffixes, expected_output,
+        case(vec!["abc", "bc"], true, true, "^a?bc$"),
+        case(vec!["abc", "bc"], true, false, "^(?:abc|bc)$"),
+        case(vec!["abc", "bc"], false, true, "^a?bc$"),
+        case(vec!["abc", "ab"], true, false, "^abc?$"),
+        case(vec!["xabc", "bc"], false, false, "^(?:xabc|bc)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, prefixes: bool, suffixes: bool, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_factoring_of_common_affixes(prefixes, suffixes)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod explanation {
+    use super::*;
+
+    #[rstest(test_cases, expected_explanation,
+        case(vec!["abc"], "matches \"abc\""),
+        case(vec!["abc", "abcd"], "matches \"abc\", then optionally \"d\""),
+        case(vec!["a", "b", "c"], "matches one of 'a', 'b', 'c'"),
+        case(vec!["xy", "a"], "matches either \"xy\" or \"a\""),
+        case(vec!["abc", "abx", "ab"], "matches \"ab\", then optionally one of 'c', 'x'")
+    )]
+    fn succeeds_with_default_settings(test_cases: Vec<&str>, expected_explanation: &str) {
+        let explanation = RegExpBuilder::from(&test_cases).build_explanation();
+        assert_eq!(explanation, expected_explanation);
+    }
+
+    #[rstest(test_cases, expected_explanation,
+        case(vec!["abc", "ab1"], "matches \"ab\", then either a digit or \"c\""),
+        case(vec!["a1", "a22"], "matches \"a\", then a digit between 1 and 2 times"),
+        case(vec!["xyxy1"], "matches \"xy\" 2 times, then a digit")
+    )]
+    fn succeeds_with_conversion_features(test_cases: Vec<&str>, expected_explanation: &str) {
+        let explanation = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Digit, Feature::Repetition])
+            .build_explanation();
+        assert_eq!(explanation, expected_explanation);
+    }
+}
+
+mod test_case_comment {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a", "b", "bcd"], indoc!(
+            r#"
+            (?x)
+            # generated from: a, b, bcd
+            ^
+              (?:
+                b
+                (?:
+                  cd
+                )?
+                |
+                a
+              )
+            $"#
+        )),
+        case(vec!["a", "b", "c", "d", "e", "f"], indoc!(
+            r#"
+            (?x)
+            # generated from: a, b, c, d, e, ...
+            ^
+              [a-f]
+            $"#
+        )),
+        case(vec!["a\nb"], indoc!(
+            r#"
+            (?x)
+            # generated from: a\nb
+            ^
+              a\nb
+            $"#
+        ))
+    )]
+    fn succeeds_with_verbose_mode(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_verbose_mode()
+            .with_test_cases_as_comment()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn succeeds_without_verbose_mode() {
+        let regexp = RegExpBuilder::from(&["a", "b"])
+            .with_test_cases_as_comment()
+            .build();
+        assert_eq!(regexp, "^[ab]$");
+    }
+}
+
+mod anchor_combinations {
+    use super::*;
+    use grex::Anchor;
+
+    #[rstest(
+        start,
+        end,
+        expected_output,
+        case(Anchor::None, Anchor::None, "bc|a"),
+        case(Anchor::None, Anchor::StringBoundary, "(?:bc|a)$"),
+        case(Anchor::None, Anchor::WordBoundary, "(?:bc|a)\\b"),
+        case(Anchor::StringBoundary, Anchor::None, "^(?:bc|a)"),
+        case(Anchor::StringBoundary, Anchor::StringBoundary, "^(?:bc|a)$"),
+        case(Anchor::StringBoundary, Anchor::WordBoundary, "^(?:bc|a)\\b"),
+        case(Anchor::WordBoundary, Anchor::None, "\\b(?:bc|a)"),
+        case(Anchor::WordBoundary, Anchor::StringBoundary, "\\b(?:bc|a)$"),
+        case(Anchor::WordBoundary, Anchor::WordBoundary, "\\b(?:bc|a)\\b")
+    )]
+    fn succeeds_with_alternation(start: Anchor, end: Anchor, expected_output: &str) {
+        let test_cases = vec!["a", "bc"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_anchors(start, end)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(
+        start,
+        end,
+        expected_output,
+        case(Anchor::None, Anchor::None, "abc?"),
+        case(Anchor::WordBoundary, Anchor::None, "\\babc?"),
+        case(Anchor::StringBoundary, Anchor::WordBoundary, "^abc?\\b")
+    )]
+    fn succeeds_with_concatenation(start: Anchor, end: Anchor, expected_output: &str) {
+        let test_cases = vec!["ab", "abc"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_anchors(start, end)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn succeeds_in_matching_words_in_text() {
+        let regexp = RegExpBuilder::from(&["cat", "dog"])
+            .with_anchors(Anchor::WordBoundary, Anchor::None)
+            .build();
+        let re = Regex::new(&regexp).unwrap();
+        assert!(re.is_match("hot dogs"));
+        assert!(!re.is_match("bobcat"));
+    }
+
+    #[test]
+    fn fails_with_word_boundary_and_posix_char_classes() {
+        let result = RegExpBuilder::from(&["abc"])
+            .with_posix_char_classes()
+            .with_anchors(Anchor::WordBoundary, Anchor::StringBoundary)
+            .try_build();
+        assert_eq!(
+            result,
+            Err(grex::Error::ConflictingOptions {
+                a: "with_posix_char_classes",
+                b: "with_anchors"
+            })
+        );
+    }
+}
+
+mod hex_conversion {
+    use super::*;
+
+    #[rstest(
+        digits,
+        length,
+        expected_output,
+        case("0123456789abcdef", 1, "^[0-9a-f]$"),
+        case("0123456789ABCDEF", 1, "^[0-9A-F]$"),
+        case("0123456789abcdefABCDEF", 1, "^[0-9a-fA-F]$"),
+        case("0123456789abcdef", 2, "^[0-9a-f]{2}$"),
+        case("0123456789ABCDEF", 2, "^[0-9A-F]{2}$"),
+        case("0123456789abcdefABCDEF", 2, "^[0-9a-fA-F]{2}$")
+    )]
+    fn succeeds(digits: &str, length: usize, expected_output: &str) {
+        let test_cases = hex_tokens(digits, length);
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Hex])
+            .build();
+        let test_cases = test_cases.iter().map(|it| it.as_str()).collect::<Vec<_>>();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_prefix() {
+        let test_cases = hex_tokens("0123456789abcdef", 2)
+            .into_iter()
+            .map(|it| format!("0x{}", it))
+            .collect::<Vec<_>>();
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Hex])
+            .build();
+        assert_eq!(regexp, "^0x[0-9a-f]{2}$");
+        let test_cases = test_cases.iter().map(|it| it.as_str()).collect::<Vec<_>>();
+        assert_that_regexp_matches_test_cases(&regexp, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["bed", "cafe", "deadbeef"], "^(?:deadbeef|cafe|bed)$"),
+        case(vec!["123", "456"], "^(?:123|456)$"),
+        case(vec!["a", "b", "c", "d", "e", "f"], "^[a-f]$")
+    )]
+    fn succeeds_without_complete_hex_classes(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Hex])
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    fn hex_tokens(digits: &str, length: usize) -> Vec<String> {
+        (0..length).fold(vec![String::new()], |tokens, _| {
+            tokens
+                .iter()
+                .flat_map(|token| digits.chars().map(move |c| format!("{}{}", token, c)))
+                .collect()
+        })
+    }
+}
+
+mod escaping_of_strings {
+    use super::*;
+    use grex::{escape, RegExpConfig};
+
+    #[rstest(
+        input,
+        expected_output,
+        case("", ""),
+        case("abc", "abc"),
+        case("()[]{}", "\\(\\)\\[\\]\\{\\}"),
+        case("a+b*c?", "a\\+b\\*c\\?"),
+        case("-.|^$", "\\-\\.\\|\\^\\$"),
+        case("C:\\dir", "C:\\\\dir"),
+        case("tab\there", "tab\\there"),
+        case("♥ 💩", "♥ 💩")
+    )]
+    fn succeeds_with_default_config(input: &str, expected_output: &str) {
+        let escaped = escape(input, &RegExpConfig::default());
+        assert_eq!(escaped, expected_output);
+        assert_that_escaped_string_matches_input(&escaped, input);
+    }
+
+    #[rstest(
+        input,
+        expected_output,
+        case("♥ 💩", "\\u{2665} \\u{1f4a9}"),
+        case("ä.ö", "\\u{e4}\\.\\u{f6}")
+    )]
+    fn succeeds_with_escaping_of_non_ascii_chars(input: &str, expected_output: &str) {
+        let escaped = escape(
+            input,
+            RegExpBuilder::new()
+                .with_escaping_of_non_ascii_chars(false)
+                .config(),
+        );
+        assert_eq!(escaped, expected_output);
+        assert_that_escaped_string_matches_input(&escaped, input);
+    }
+
+    #[test]
+    fn succeeds_with_escaping_of_non_ascii_chars_using_surrogates() {
+        let escaped = escape(
+            "💩",
+            RegExpBuilder::new()
+                .with_escaping_of_non_ascii_chars(true)
+                .config(),
+        );
+        assert_eq!(escaped, "\\u{d83d}\\u{dca9}");
+    }
+
+    #[test]
+    fn matches_literals_of_built_regexp() {
+        let test_case = "a.b(c)♥";
+        let mut builder = RegExpBuilder::from(&[test_case]);
+        builder.with_escaping_of_non_ascii_chars(false);
+        let escaped = escape(test_case, builder.config());
+        assert_eq!(builder.build(), format!("^{}$", escaped));
+    }
+
+    fn assert_that_escaped_string_matches_input(escaped: &str, input: &str) {
+        let regexp = Regex::new(&format!("^{}$", escaped)).unwrap();
+        assert!(regexp.is_match(input));
+    }
+}
+
+mod maximum_repetition_count {
+    use super::*;
+
+    #[rstest(test_cases, limit, expected_output,
+        case(vec!["aaaaa"], 5, "^a{5}$"),
+        case(vec!["aaaaa"], 4, "^a+$"),
+        case(vec!["abab", "ababab"], 3, "^(?:ab){2,3}$"),
+        case(vec!["abab", "ababab"], 2, "^(?:ab)+$"),
+        case(vec!["aa", "bbbbbbbb"], 4, "^(?:a{2}|b+)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, limit: u32, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Repetition])
+            .with_maximum_repetition_count(limit)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_braced_quantifiers() {
+        let test_cases = vec!["aaaaa"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Repetition])
+            .with_maximum_repetition_count(2)
+            .with_braced_quantifiers()
+            .build();
+        assert_eq!(regexp, "^a{1,}$");
+    }
+
+    #[test]
+    fn succeeds_with_explanation() {
+        let explanation = RegExpBuilder::from(&["aaaaa"])
+            .with_conversion_of(&[Feature::Repetition])
+            .with_maximum_repetition_count(2)
+            .build_explanation();
+        assert_eq!(explanation, "matches \"a\" one or more times");
+    }
+}
+
+mod minimum_class_size {
+    use super::*;
+
+    #[rstest(test_cases, size, expected_output,
+        case(vec!["a", "b"], 2, "^[ab]$"),
+        case(vec!["a", "b"], 3, "^(?:a|b)$"),
+        case(vec!["a", "b", "c"], 3, "^[a-c]$"),
+        case(vec!["a", "b", "c"], 4, "^(?:a|b|c)$"),
+        case(vec!["xa", "xb", "yc"], 3, "^(?:x(?:a|b)|yc)$"),
+        case(vec!["b", "bcc", "d", "e"], 3, "^(?:bcc|[bde])$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, size: usize, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_minimum_class_size(size)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod wildcards {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["ab*c"], "^ab.*c$"),
+        case(vec!["*.txt", "*.md"], "^.*\\.(?:txt|md)$"),
+        case(vec!["a**b"], "^a.*b$"),
+        case(vec!["ab\\*c"], "^ab\\*c$"),
+        case(vec!["ab\\*c", "ab*c"], "^ab(?:.*|\\*)c$"),
+        case(vec!["a\\b*"], "^a\\\\b.*$"),
+        case(vec!["ab*c", "dc"], "^(?:ab.*|d)c$"),
+        case(vec!["xa*b", "xc"], "^x(?:a.*b|c)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_wildcard('*', false)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["ab%c"], "^ab\\S*c$"),
+        case(vec!["x%", "y%"], "^[xy]\\S*$"),
+        case(vec!["ab%c", "dc"], "^(?:ab\\S*|d)c$")
+    )]
+    fn succeeds_with_whitespace_excluded(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_wildcard('%', true)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a", "a*"], "^a.*$"),
+        case(vec!["a*b", "ab"], "^a.*b$"),
+        case(vec!["", "*"], "^.*$"),
+        case(vec!["a", "a%"], "^a\\S*$")
+    )]
+    fn succeeds_with_optional_wildcard(test_cases: Vec<&str>, expected_output: &str) {
+        let is_whitespace_excluded = test_cases.iter().any(|it| it.contains('%'));
+        let wildcard = if is_whitespace_excluded { '%' } else { '*' };
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_wildcard(wildcard, is_whitespace_excluded)
+            .build();
+        assert_eq!(regexp, expected_output);
+
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_wildcard(wildcard, is_whitespace_excluded)
+            .with_kleene_star_preferred()
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[test]
+    fn succeeds_with_posix_char_classes() {
+        let regexp = RegExpBuilder::from(&["ab%c"])
+            .with_wildcard('%', true)
+            .with_posix_char_classes()
+            .build();
+        assert_eq!(regexp, "^ab[^[:space:]]*c$");
+    }
+
+    #[test]
+    fn succeeds_with_explanation() {
+        let explanation = RegExpBuilder::from(&["ab*c"])
+            .with_wildcard('*', false)
+            .build_explanation();
+        assert_eq!(
+            explanation,
+            "matches \"ab\", then any characters, then \"c\""
+        );
+    }
+
+    #[test]
+    fn succeeds_with_shortest_matches() {
+        let matches = RegExpBuilder::from(&["ab*c"])
+            .with_wildcard('*', false)
+            .build_shortest_matches(1);
+        assert_eq!(matches, vec!["abc"]);
+    }
+
+    #[rstest(test_cases, matching_inputs,
+        case(vec!["ab*c"], vec!["abc", "abxyzc", "ab c"]),
+        case(vec!["*.txt"], vec![".txt", "notes.txt"])
+    )]
+    fn matches_expanded_wildcards(test_cases: Vec<&str>, matching_inputs: Vec<&str>) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_wildcard('*', false)
+            .build();
+        assert_that_regexp_matches_test_cases(&regexp, matching_inputs);
+    }
+}
+
+mod empty_test_cases {
+    use super::*;
+    use grex::{Anchor, Error};
+
+    #[rstest(test_cases, case(vec![""]), case(vec!["", ""]))]
+    fn succeeds_with_empty_strings_only(test_cases: Vec<&str>) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, "^$", &test_cases);
+        assert_that_regexp_matches_test_cases("^$", test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["", "a", "bc"], "^(?:bc|a)?$"),
+        case(vec!["", "a", "b"], "^[ab]?$"),
+        case(vec!["x", "xa", "xbc"], "^x(?:bc|a)?$"),
+        case(vec!["", "xa", "xbc"], "^(?:x(?:bc|a))?$")
+    )]
+    fn succeeds_with_empty_option(test_cases: Vec<&str>, expected_output: &str) {
+        for factoring in &[(true, true), (false, false)] {
+            let regexp = RegExpBuilder::from(&test_cases)
+                .with_factoring_of_common_affixes(factoring.0, factoring.1)
+                .build();
+            assert!(!regexp.contains("||") && !regexp.contains("|)") && !regexp.contains("(|"));
+            assert_that_regexp_matches_test_cases(&regexp, test_cases.clone());
+        }
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_empty_strings_only_and_without_anchors() {
+        let regexp = RegExpBuilder::from(&[""])
+            .with_anchors(Anchor::None, Anchor::None)
+            .build();
+        assert_eq!(regexp, "");
+    }
+
+    #[test]
+    fn succeeds_with_empty_strings_only_and_explanation() {
+        let explanation = RegExpBuilder::from(&[""]).build_explanation();
+        assert_eq!(explanation, "matches the empty string");
+    }
+
+    #[test]
+    fn succeeds_with_empty_strings_only_and_shortest_matches() {
+        let matches = RegExpBuilder::from(&["", ""]).build_shortest_matches(3);
+        assert_eq!(matches, vec![""]);
+    }
+
+    #[test]
+    fn fails_without_test_cases() {
+        let result = RegExpBuilder::new().try_build();
+        assert_eq!(result, Err(Error::NoTestCases { requirement: None }));
+    }
+
+    #[test]
+    fn fails_if_no_test_case_passes_input_filter() {
+        let result = RegExpBuilder::from(&["abc", "def"])
+            .with_input_filter(|it| it.len() > 3)
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::NoTestCases {
+                requirement: Some("passing the input filter")
+            })
+        );
+    }
+}
+
+mod unicode_block_names {
+    use super::*;
+    use grex::{Error, Flavor};
+
+    fn chars_between(first: char, last: char) -> Vec<String> {
+        (first..=last).map(|c| c.to_string()).collect()
+    }
+
+    #[rstest(
+        flavor,
+        expected_output,
+        case(Flavor::DotNet, "^\\p{IsBasicLatin}$"),
+        case(Flavor::Java, "^\\p{InBasicLatin}$")
+    )]
+    fn succeeds_with_basic_latin_block(flavor: Flavor, expected_output: &str) {
+        let test_cases = chars_between('\u{0}', '\u{7f}');
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_unicode_block_names(flavor)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[rstest(
+        flavor,
+        expected_output,
+        case(Flavor::DotNet, "^[\\p{IsBasicLatin}é]$"),
+        case(Flavor::Java, "^[\\p{InBasicLatin}é]$")
+    )]
+    fn succeeds_with_basic_latin_block_and_remaining_chars(flavor: Flavor, expected_output: &str) {
+        let mut test_cases = chars_between('\u{0}', '\u{7f}');
+        test_cases.push("é".to_string());
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_unicode_block_names(flavor)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[test]
+    fn succeeds_with_several_blocks() {
+        let mut test_cases = chars_between('\u{370}', '\u{3ff}');
+        test_cases.extend(chars_between('\u{400}', '\u{4ff}'));
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_unicode_block_names(Flavor::Java)
+            .build();
+        assert_eq!(regexp, "^[\\p{InGreek}\\p{InCyrillic}]$");
+    }
+
+    #[test]
+    fn succeeds_without_complete_block() {
+        let test_cases = vec!["a", "b", "c"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_unicode_block_names(Flavor::DotNet)
+            .build();
+        assert_that_regexp_is_correct(regexp, "^[a-c]$", &test_cases);
+    }
+
+    #[test]
+    fn fails_with_posix_char_classes() {
+        let result = RegExpBuilder::from(&["a"])
+            .with_unicode_block_names(Flavor::DotNet)
+            .with_posix_char_classes()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_posix_char_classes",
+                b: "with_unicode_block_names"
+            })
+        );
+    }
+}
+
+mod unicode_script_names {
+    use super::*;
+    use grex::Error;
+
+    fn chars_of_scripts(scripts: &str) -> Vec<String> {
+        let text = (0..=0x10ffff)
+            .filter_map(char::from_u32)
+            .collect::<String>();
+        Regex::new(scripts)
+            .unwrap()
+            .find_iter(&text)
+            .map(|it| it.as_str().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn succeeds_with_latin_and_greek_scripts() {
+        let test_cases = chars_of_scripts("[\\p{Latin}\\p{Greek}]");
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_unicode_script_names()
+            .build();
+        assert_eq!(regexp, "^[\\p{Latin}\\p{Greek}]$");
+        let test_cases = test_cases.iter().map(|it| it.as_str()).collect::<Vec<_>>();
+        assert_that_regexp_matches_test_cases(&regexp, test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_script_and_remaining_chars() {
+        let mut test_cases = chars_of_scripts("\\p{Greek}");
+        test_cases.push("a".to_string());
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_unicode_script_names()
+            .build();
+        assert_eq!(regexp, "^[\\p{Greek}a]$");
+    }
+
+    #[test]
+    fn succeeds_with_incomplete_script() {
+        let test_cases = vec!["α", "β", "γ"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_unicode_script_names()
+            .build();
+        assert_that_regexp_is_correct(regexp, "^[α-γ]$", &test_cases);
+    }
+
+    #[test]
+    fn fails_with_posix_char_classes() {
+        let result = RegExpBuilder::from(&["a"])
+            .with_unicode_script_names()
+            .with_posix_char_classes()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_posix_char_classes",
+                b: "with_unicode_script_names"
+            })
+        );
+    }
+}
+
+mod distinct_prefix_branches {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["ab", "abc"], "^(?:abc|ab)$"),
+        case(vec!["a", "ab", "abc"], "^(?:abc|ab|a)$"),
+        case(vec!["ab", "abc", "abd"], "^(?:ab[cd]|ab)$"),
+        case(vec!["x", "ax"], "^a?x$"),
+        case(vec!["", "a"], "^a?$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_distinct_prefix_branches()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["ab", "abc"], "^abc?$"),
+        case(vec!["a", "ab", "abc"], "^a(?:bc?)?$")
+    )]
+    fn succeeds_with_collapsing_by_default(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod maximum_dfa_states {
+    use super::*;
+    use grex::Error;
+
+    #[test]
+    fn succeeds_within_limit() {
+        let regexp = RegExpBuilder::from(&["abc", "abd"])
+            .with_maximum_dfa_states(4)
+            .try_build();
+        assert_eq!(regexp, Ok("^ab[cd]$".to_string()));
+    }
+
+    #[test]
+    fn fails_above_limit() {
+        let regexp = RegExpBuilder::from(&["abc"])
+            .with_maximum_dfa_states(3)
+            .try_build();
+        assert_eq!(
+            regexp,
+            Err(Error::TooManyStates {
+                state_count: 4,
+                limit: 3
+            })
+        );
+    }
+
+    #[test]
+    fn fails_before_converting_large_automaton() {
+        // The automaton of these test cases has thousands of states
+        // whose conversion the limit has to prevent.
+        let test_cases = random_lowercase_test_cases(42, 200, 10);
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_maximum_dfa_states(100)
+            .try_build();
+        assert!(matches!(
+            regexp,
+            Err(Error::TooManyStates { state_count, limit: 100 }) if state_count > 1000
+        ));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The automaton built from the test cases has 4 states which exceeds the maximum of 3 states"
+    )]
+    fn build_panics_above_limit() {
+        RegExpBuilder::from(&["abc"])
+            .with_maximum_dfa_states(3)
+            .build();
+    }
+}
+
+mod progress_reporting {
+    use super::*;
+    use grex::ProgressEvent;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn reports_events_in_order() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorded_events = Arc::clone(&events);
+        let test_cases = (0..50).map(|it| format!("item-{}", it)).collect::<Vec<_>>();
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_progress_callback(move |event| recorded_events.lock().unwrap().push(event))
+            .build();
+        let compiled = Regex::new(&regexp).unwrap();
+        assert!(test_cases.iter().all(|it| compiled.is_match(it)));
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events.first(),
+            Some(&ProgressEvent::TestCasesIngested {
+                test_case_count: 50
+            })
+        );
+        let state_count = match events.get(1) {
+            Some(&ProgressEvent::DfaBuilt { state_count }) => state_count,
+            other => panic!("unexpected event {:?}", other),
+        };
+        assert!(state_count > 0);
+        assert_eq!(events.len(), state_count + 3);
+        for (i, event) in events[2..events.len() - 1].iter().enumerate() {
+            assert_eq!(
+                event,
+                &ProgressEvent::StateEliminated {
+                    eliminated_count: i + 1,
+                    state_count
+                }
+            );
+        }
+        assert_eq!(events.last(), Some(&ProgressEvent::RenderingDone));
+    }
+
+    #[test]
+    fn does_not_report_rendering_for_explanations() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorded_events = Arc::clone(&events);
+        RegExpBuilder::from(&["abc", "abd"])
+            .with_progress_callback(move |event| recorded_events.lock().unwrap().push(event))
+            .build_explanation();
+        assert!(!events
+            .lock()
+            .unwrap()
+            .contains(&ProgressEvent::RenderingDone));
+    }
+}
+
+mod optional_surrounding_quotes {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc", "\"abc\"", "\"abc", "abc\""], "^\"?abc\"?$"),
+        case(vec!["\"abc\"", "abc\"", "\"abc", "abc"], "^\"?abc\"?$"),
+        case(vec!["x", "(x", "x)", "(x)"], "^\\(?x\\)?$"),
+        case(vec!["abc", "\"abc\""], "^(?:\"abc\"|abc)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc", "\"abc\"", "\"abc", "abc\""], "^(?:\"abc\"?|abc\"?)$")
+    )]
+    fn succeeds_without_suffix_factoring(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_factoring_of_common_affixes(true, false)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod single_differing_position {
+    use super::*;
+    use grex::Anchor;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["cat", "cot", "cut"], "^c[aou]t$"),
+        case(vec!["cut", "cat", "cot"], "^c[aou]t$"),
+        case(vec!["bat", "bet", "bit", "bot", "but"], "^b[aeiou]t$"),
+        case(vec!["xay", "xby", "xcy", "xdy"], "^x[a-d]y$"),
+        case(vec!["prefix-a-suffix", "prefix-b-suffix"], "^prefix\\-[ab]\\-suffix$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["cat", "cot", "cut"], "c[aou]t")
+    )]
+    fn succeeds_without_anchors(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_anchors(Anchor::None, Anchor::None)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+}
+
+mod named_branches {
+    use super::*;
+    use grex::{Anchor, Error};
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc", "de", "f"], "^(?:(?P<b0>abc)|(?P<b1>de)|(?P<b2>f))$"),
+        case(vec!["a", "b", "c"], "^[a-c]$"),
+        case(vec!["abc", "abd"], "^ab[cd]$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_named_branches()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc", "de", "f"], "(?P<b0>abc)|(?P<b1>de)|(?P<b2>f)")
+    )]
+    fn succeeds_without_anchors(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_named_branches()
+            .with_anchors(Anchor::None, Anchor::None)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc", "de", "f"], indoc!(
+            r#"
+            (?x)
+            ^
+              (?:
+                (?P<b0>
+                  abc
+                )
+                |
+                (?P<b1>
+                  de
+                )
+                |
+                (?P<b2>
+                  f
+                )
+              )
+            $"#
+        ))
+    )]
+    fn succeeds_with_verbose_mode(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_named_branches()
+            .with_verbose_mode()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn reports_matching_branch() {
+        let regexp = RegExpBuilder::from(&["abc", "de", "f"])
+            .with_named_branches()
+            .build();
+        let captures = Regex::new(&regexp).unwrap().captures("de").unwrap();
+        assert!(captures.name("b0").is_none());
+        assert_eq!(captures.name("b1").map(|it| it.as_str()), Some("de"));
+        assert!(captures.name("b2").is_none());
+    }
+
+    #[test]
+    fn fails_with_posix_char_classes() {
+        let regexp = RegExpBuilder::from(&["abc", "de", "f"])
+            .with_named_branches()
+            .with_posix_char_classes()
+            .try_build();
+        assert_eq!(
+            regexp,
+            Err(Error::ConflictingOptions {
+                a: "with_posix_char_classes",
+                b: "with_named_branches"
+            })
+        );
+    }
+}
+
+mod maximum_nesting_depth {
+    use super::*;
+    use grex::Error;
+
+    #[test]
+    fn succeeds_within_limit() {
+        let regexp = RegExpBuilder::from(&["a", "ab", "abc"])
+            .with_maximum_nesting_depth(2)
+            .try_build();
+        assert_eq!(regexp, Ok("^a(?:bc?)?$".to_string()));
+    }
+
+    #[test]
+    fn fails_above_limit() {
+        let regexp = RegExpBuilder::from(&["a", "ab", "abc"])
+            .with_maximum_nesting_depth(1)
+            .try_build();
+        assert_eq!(regexp, Err(Error::NestingTooDeep { depth: 2, limit: 1 }));
+    }
+
+    #[test]
+    fn fails_before_building_pathologically_nested_expression() {
+        // Each test case is a prefix of the next one, so each of them opens another
+        // optional group within the previous one. Building the complete expression
+        // would overflow the stack, so the generation has to be aborted early.
+        let text = "abcdefghij".repeat(100);
+        let test_cases = (1..=text.len()).map(|i| &text[..i]).collect::<Vec<_>>();
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_maximum_nesting_depth(100)
+            .try_build();
+        assert_eq!(
+            regexp,
+            Err(Error::NestingTooDeep {
+                depth: 101,
+                limit: 100
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The generated expression has a nesting depth of 2 which exceeds the maximum of 1"
+    )]
+    fn build_panics_above_limit() {
+        RegExpBuilder::from(&["a", "ab", "abc"])
+            .with_maximum_nesting_depth(1)
+            .build();
+    }
+}
+
+mod maximum_expression_size {
+    use super::*;
+    use grex::Error;
+
+    #[test]
+    fn succeeds_within_limit() {
+        let regexp = RegExpBuilder::from(&["a", "ab", "abc"])
+            .with_maximum_expression_size(7)
+            .try_build();
+        assert_eq!(regexp, Ok("^a(?:bc?)?$".to_string()));
+    }
+
+    #[test]
+    fn fails_above_limit() {
+        let regexp = RegExpBuilder::from(&["a", "ab", "abc"])
+            .with_maximum_expression_size(6)
+            .try_build();
+        assert_eq!(regexp, Err(Error::ExpressionTooLarge { size: 7, limit: 6 }));
+    }
+
+    #[test]
+    fn fails_with_long_literal_above_limit() {
+        // The literal is built from its end, so the generation is aborted
+        // as soon as six of its graphemes have been combined.
+        let regexp = RegExpBuilder::from(&["abcdefgh"])
+            .with_maximum_expression_size(5)
+            .try_build();
+        assert_eq!(regexp, Err(Error::ExpressionTooLarge { size: 6, limit: 5 }));
+    }
+
+    #[test]
+    fn fails_before_building_pathologically_large_expression() {
+        // Each test case is a prefix of the next one, so the intermediate expressions
+        // grow with each eliminated state. The generation has to be aborted as soon
+        // as the first one of them exceeds the limit.
+        let text = "abcdefghij".repeat(100);
+        let test_cases = (1..=text.len()).map(|i| &text[..i]).collect::<Vec<_>>();
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_maximum_expression_size(100)
+            .try_build();
+        assert!(matches!(
+            regexp,
+            Err(Error::ExpressionTooLarge { size, limit: 100 }) if size > 100 && size < 110
+        ));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The generated expression consists of 7 subexpressions which exceeds the maximum of 6"
+    )]
+    fn build_panics_above_limit() {
+        RegExpBuilder::from(&["a", "ab", "abc"])
+            .with_maximum_expression_size(6)
+            .build();
+    }
+}
+
+mod alternation_separator {
+    use super::*;
+
+    #[rstest(test_cases, separator, expected_output,
+        case(vec!["abc", "def"], "\n|\n", indoc!(
+            r#"
+            (?x)
+            ^
+              (?:
+                abc
+
+                |
+
+                def
+              )
+            $"#
+        )),
+        case(vec!["abc", "def", "ghi"], "| # or", indoc!(
+            r#"
+            (?x)
+            ^
+              (?:
+                abc
+                | # or
+                def
+                | # or
+                ghi
+              )
+            $"#
+        )),
+        case(vec!["a", "ab", "xy"], "# ---\n|", indoc!(
+            r#"
+            (?x)
+            ^
+              (?:
+                ab?
+                # ---
+                |
+                xy
+              )
+            $"#
+        ))
+    )]
+    fn succeeds_in_verbose_mode(test_cases: Vec<&str>, separator: &str, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_verbose_mode()
+            .with_alternation_separator(separator)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn is_ignored_without_verbose_mode() {
+        let test_cases = vec!["abc", "def"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_alternation_separator("\n| # or\n")
+            .build();
+        assert_that_regexp_is_correct(regexp, "^(?:abc|def)$", &test_cases);
+    }
+}
+
+mod leading_zeros {
+    use super::*;
+    use grex::{Error, LeadingZeros};
+
+    #[rstest(test_cases, leading_zeros, expected_output,
+        case(vec!["7", "007"], LeadingZeros::Kept, "^(?:00)?7$"),
+        case(vec!["7", "007"], LeadingZeros::Removed, "^7$"),
+        case(vec!["7", "007"], LeadingZeros::Optional, "^0*7$"),
+        case(vec!["0", "000", "10", "042"], LeadingZeros::Removed, "^(?:1?0|42)$"),
+        case(vec!["0", "000", "10", "042"], LeadingZeros::Optional, "^0*(?:1?0|42)$"),
+        case(vec!["7", "007", "a"], LeadingZeros::Removed, "^[7a]$"),
+        case(vec!["7", "007", "a"], LeadingZeros::Optional, "^(?:007|[7a])$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, leading_zeros: LeadingZeros, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_leading_zeros(leading_zeros)
+            .build();
+        assert_eq!(regexp, expected_output);
+        if leading_zeros != LeadingZeros::Removed {
+            assert_that_regexp_matches_test_cases(expected_output, test_cases);
+        }
+    }
+
+    #[rstest(test_cases, leading_zeros, expected_output,
+        case(vec!["7", "007"], LeadingZeros::Kept, "^\\d(?:\\d\\d)?$"),
+        case(vec!["7", "007"], LeadingZeros::Removed, "^\\d$"),
+        case(vec!["7", "007"], LeadingZeros::Optional, "^0*\\d$")
+    )]
+    fn succeeds_with_digit_conversion(
+        test_cases: Vec<&str>,
+        leading_zeros: LeadingZeros,
+        expected_output: &str,
+    ) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Digit])
+            .with_leading_zeros(leading_zeros)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[test]
+    fn fails_with_negative_test_case_equal_after_removing_zeros() {
+        let regexp = RegExpBuilder::from(&["7", "42"])
+            .with_negative_test_cases(&["007"])
+            .with_leading_zeros(LeadingZeros::Optional)
+            .try_build();
+        assert_eq!(
+            regexp,
+            Err(Error::ContradictingTestCase {
+                test_case: "7".to_string()
+            })
+        );
+    }
+}
+
+mod output_wrappers {
+    use super::*;
+    use grex::OutputWrapper;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc"], "r\"^abc$\""),
+        case(vec!["a1", "a\\"], "r\"^a[1\\\\]$\""),
+        case(vec!["\"a\""], "r#\"^\"a\"$\"#"),
+        case(vec!["\"#a"], "r##\"^\"#a$\"##"),
+        case(vec!["a\"##", "\"b"], "r###\"^(?:a\"##|\"b)$\"###")
+    )]
+    fn succeeds_with_rust_raw_string(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_output_wrapper(OutputWrapper::RustRaw)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc"], "r\"^abc$\""),
+        case(vec!["\"a\""], "r\"^\\\"a\\\"$\""),
+        case(vec!["a\\\""], "r\"^a\\\\\\\"$\"")
+    )]
+    fn succeeds_with_python_raw_string(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_output_wrapper(OutputWrapper::PythonRaw)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[rstest(
+        wrapper,
+        expected_output,
+        case(OutputWrapper::JavaScript, r#""^(?:\\d\"\\\\|a\"\\d)$""#),
+        case(OutputWrapper::Java, r#""^(?:\\d\"\\\\|a\"\\d)$""#),
+        case(OutputWrapper::Json, r#""^(?:\\d\"\\\\|a\"\\d)$""#)
+    )]
+    fn succeeds_with_escaped_string(wrapper: OutputWrapper, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&["a\"1", "2\"\\"])
+            .with_conversion_of(&[Feature::Digit])
+            .with_output_wrapper(wrapper)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc"], "'^abc$'"),
+        case(vec!["a$'b"], r"'^a\$'\''b$'"),
+        case(vec!["`a`", "\"b\""], r#"'^(?:"b"|`a`)$'"#)
+    )]
+    fn succeeds_with_posix_shell_quotes(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_output_wrapper(OutputWrapper::PosixShell)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[rstest(
+        wrapper,
+        expected_output,
+        case(OutputWrapper::JavaScript, r#""(?x)\n^\n  a\\\\b\n$""#),
+        case(OutputWrapper::Java, r#""(?x)\n^\n  a\\\\b\n$""#),
+        case(OutputWrapper::Json, r#""(?x)\n^\n  a\\\\b\n$""#)
+    )]
+    fn succeeds_with_escaped_string_and_verbose_mode(
+        wrapper: OutputWrapper,
+        expected_output: &str,
+    ) {
+        let regexp = RegExpBuilder::from(&["a\\b"])
+            .with_verbose_mode()
+            .with_output_wrapper(wrapper)
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[rstest(test_cases, wrapper, expected_flags,
+        case(vec!["abc"], OutputWrapper::JavaScript, vec![]),
+        case(vec!["café"], OutputWrapper::JavaScript, vec![]),
+        case(vec!["a💩"], OutputWrapper::JavaScript, vec!['u']),
+        case(vec!["a💩"], OutputWrapper::Java, vec![]),
+        case(vec!["a💩"], OutputWrapper::None, vec![])
+    )]
+    fn reports_required_flags(
+        test_cases: Vec<&str>,
+        wrapper: OutputWrapper,
+        expected_flags: Vec<char>,
+    ) {
+        let flags = RegExpBuilder::from(&test_cases)
+            .with_output_wrapper(wrapper)
+            .build_required_flags();
+        assert_eq!(flags.into_iter().collect::<Vec<_>>(), expected_flags);
+    }
+
+    #[rstest(
+        use_surrogate_pairs,
+        expected_output,
+        case(false, r#""^caf\\u{e9}\\u{1f4a9}$""#),
+        case(true, r#""^caf\\u{e9}\\u{d83d}\\u{dca9}$""#)
+    )]
+    fn reports_unicode_flag_for_escaped_chars(use_surrogate_pairs: bool, expected_output: &str) {
+        let mut builder = RegExpBuilder::from(&["café💩"]);
+        builder
+            .with_escaping_of_non_ascii_chars(use_surrogate_pairs)
+            .with_output_wrapper(OutputWrapper::JavaScript);
+        assert_eq!(builder.build(), expected_output);
+        assert_eq!(
+            builder
+                .build_required_flags()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!['u']
+        );
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["\"a\""], indoc!(
+            r#"
+            r"""(?x)
+            ^
+              \"a\"
+            $""""#
+        ))
+    )]
+    fn succeeds_with_python_raw_string_and_verbose_mode(
+        test_cases: Vec<&str>,
+        expected_output: &str,
+    ) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_output_wrapper(OutputWrapper::PythonRaw)
+            .with_verbose_mode()
+            .build();
+        assert_eq!(regexp, expected_output);
+    }
+}
+
+mod char_classes_in_order_of_appearance {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["c", "a", "b"], "^[cab]$"),
+        case(vec!["tuc", "toc", "tac"], "^t[uoa]c$"),
+        case(vec!["3", "1", "2", "-"], "^[312\\-]$"),
+        case(vec!["b", "a", "b", "c"], "^[bac]$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_char_classes_in_order_of_appearance()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["c", "a", "b"], "^[a-c]$"),
+        case(vec!["tuc", "toc", "tac"], "^t[aou]c$"),
+        case(vec!["3", "1", "2", "-"], "^[\\-1-3]$")
+    )]
+    fn succeeds_with_sorted_classes_by_default(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod char_classes_in_order_of_frequency {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["xa", "xb", "xc", "cc", "ac"], "^(?:[ca]c|x[cab])$"),
+        case(vec!["ta", "tb", "tc", "ba"], "^(?:ba|t[abc])$"),
+        case(vec!["1b", "2b", "3b", "b3", "33"], "^(?:[12]b|3[3b]|b3)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_char_classes_in_order_of_frequency()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod optional_markers {
+    use super::*;
+    use grex::Error;
+
+    #[rstest(test_cases, expected_output, matched_test_cases,
+        case(vec!["colo[u]r"], "^colou?r$", vec!["color", "colour"]),
+        case(vec!["colo(u)r"], "^colo\\(u\\)r$", vec!["colo(u)r"]),
+        case(vec!["a[b[c]]"], "^a(?:bc?)?$", vec!["a", "ab", "abc"]),
+        case(vec!["[a]b[c]"], "^a?bc?$", vec!["b", "ab", "bc", "abc"]),
+        case(vec!["[ab]", "c"], "^(?:ab|c)?$", vec!["", "ab", "c"]),
+        case(vec!["colo\\[u\\]r"], "^colo\\[u\\]r$", vec!["colo[u]r"]),
+        case(vec!["a\\\\[b]"], "^a\\\\b?$", vec!["a\\", "a\\b"]),
+        case(vec!["a\\d[b]"], "^a\\\\db?$", vec!["a\\d", "a\\db"])
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str, matched_test_cases: Vec<&str>) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_optional_markers('[', ']')
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, matched_test_cases);
+    }
+
+    #[rstest(test_case, case("colo[ur"), case("color]"), case("a[b]]c"))]
+    fn fails_with_unbalanced_markers(test_case: &str) {
+        let result = RegExpBuilder::from(&[test_case])
+            .with_optional_markers('[', ']')
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::UnbalancedOptionalMarkers {
+                test_case: test_case.to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn succeeds_with_many_optional_parts() {
+        let test_case = ('a'..='z').map(|c| format!("[{}]", c)).collect::<String>();
+        let regexp = RegExpBuilder::from(&[test_case])
+            .with_optional_markers('[', ']')
+            .try_build()
+            .unwrap();
+        assert_eq!(
+            regexp,
+            format!(
+                "^{}$",
+                ('a'..='z').map(|c| format!("{}?", c)).collect::<String>()
+            )
+        );
+        let regexp = Regex::new(&regexp).unwrap();
+        for test_case in &["", "z", "acegik", "abcdefghijklmnopqrstuvwxyz"] {
+            assert!(regexp.is_match(test_case), "'{}' does not match", test_case);
+        }
+        assert!(!regexp.is_match("ba"));
+    }
+
+    #[test]
+    fn fails_with_negative_test_case_matched_by_optional_parts() {
+        let result = RegExpBuilder::from(&["colo[u]r"])
+            .with_optional_markers('[', ']')
+            .with_negative_test_cases(&["color"])
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ContradictingTestCase {
+                test_case: "color".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn fails_with_length_buckets() {
+        let result = RegExpBuilder::from(&["colo[u]r"])
+            .with_optional_markers('[', ']')
+            .with_length_buckets(2)
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_optional_markers",
+                b: "with_length_buckets"
+            })
+        );
+    }
+}
+
+mod group_marker {
+    use super::*;
+    use grex::GroupMarker;
+
+    #[rstest(test_cases, features, marker, expected_output,
+        case(vec!["xab", "xabab", "xcd"], vec![Feature::Digit], GroupMarker::NonCapturing, "^x(?:ab(?:ab)?|cd)$"),
+        case(vec!["xab", "xabab", "xcd"], vec![Feature::Digit], GroupMarker::Atomic, "^x(?:ab(?>ab)?|cd)$"),
+        case(vec!["xab", "xabab", "xcd"], vec![Feature::Digit], GroupMarker::Plain, "^x(ab(ab)?|cd)$"),
+        case(vec!["xab", "xabab", "xcd"], vec![Feature::Repetition], GroupMarker::Atomic, "^x(?:ab|cd|(?>ab){2})$"),
+        case(vec!["ab", "abab", "x", "cd"], vec![Feature::Repetition], GroupMarker::Atomic, "^(?:ab|cd|x|(?>ab){2})$"),
+        case(vec!["ab", "abab", "x", "cd"], vec![Feature::Repetition], GroupMarker::Plain, "^(ab|cd|x|(ab){2})$"),
+        case(vec!["ab", "abab", "x", "cd"], vec![Feature::Repetition, Feature::CapturingGroup], GroupMarker::Atomic, "^(ab|cd|x|(ab){2})$")
+    )]
+    fn succeeds(
+        test_cases: Vec<&str>,
+        features: Vec<Feature>,
+        marker: GroupMarker,
+        expected_output: &str,
+    ) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&features)
+            .with_group_marker(marker)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["xab", "xabab", "xcd"], "^x(?:ab(?:ab)?|cd)$"),
+        case(vec!["ab", "abab", "x", "cd"], "^(?:abab|ab|cd|x)$")
+    )]
+    fn succeeds_with_non_capturing_groups_by_default(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, features, expected_output,
+        case(vec!["ab", "abab", "x", "cd"], vec![Feature::Repetition], "^(?:ab|cd|x|(?>ab){2})$"),
+        case(vec!["a", "ab", "abc", "abcd"], vec![Feature::Digit], "^a(?:b(?:cd?)?)?$"),
+        case(vec!["ab", "abab", "ababab"], vec![Feature::Repetition], "^(?:ab|(?>ab){2,3})$"),
+        case(vec!["1", "12", "123"], vec![Feature::Digit], "^\\d(?:\\d(?>\\d)?)?$")
+    )]
+    #[ignore = "requires perl to be installed"]
+    fn succeeds_with_atomic_groups_under_backtracking_semantics(
+        test_cases: Vec<&str>,
+        features: Vec<Feature>,
+        expected_output: &str,
+    ) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&features)
+            .with_group_marker(GroupMarker::Atomic)
+            .build();
+        assert_eq!(regexp, expected_output);
+        assert_that_regexp_matches_test_cases_with_backtracking(&regexp, &test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_atomic_groups_in_verbose_mode() {
+        let regexp = RegExpBuilder::from(&["a", "abc"])
+            .with_group_marker(GroupMarker::Atomic)
+            .with_verbose_mode()
+            .build();
+        assert_eq!(
+            regexp,
+            indoc!(
+                r#"
+                (?x)
+                ^
+                  a
+                  (?>
+                    bc
+                  )?
+                $"#
+            )
+        );
+    }
+}
+
+mod selected_conversions {
+    use super::*;
+
+    #[rstest(test_cases, features, expected_output,
+        case(vec!["a 1", "b 22"], vec![Feature::Digit], "^(?:b \\d|a )\\d$"),
+        case(vec!["a 1", "b 22"], vec![Feature::Space], "^(?:b\\s22|a\\s1)$"),
+        case(vec!["a 1", "b 22"], vec![Feature::Word], "^\\w \\w(?:\\w)?$"),
+        case(vec!["a 1", "b 22"], vec![Feature::Digit, Feature::Word], "^\\w \\d(?:\\d)?$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, features: Vec<Feature>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&features)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod positional_inference {
+    use super::*;
+    use grex::Error;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["555-123-4567", "555-987-6543"], "^\\d{3}\\-\\d{3}\\-\\d{4}$"),
+        case(vec!["(555) 123-4567", "(312) 987-6543"], "^\\(\\d{3}\\) \\d{3}\\-\\d{4}$"),
+        case(vec!["ab-1", "cd-2"], "^[ac][bd]\\-\\d$")
+    )]
+    fn succeeds_with_digit_conversion(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Digit])
+            .with_positional_inference()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a1", "b2", "c3"], "^[a-c][1-3]$"),
+        case(vec!["aa", "bb"], "^[ab]{2}$"),
+        case(vec!["x12", "x34"], "^x[13][24]$"),
+        case(vec!["aaa"], "^aaa$"),
+        case(vec!["abc", "de"], "^(?:abc|de)$")
+    )]
+    fn succeeds_without_conversion(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_positional_inference()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["aaa"], "^a{3}$"),
+        case(vec!["aaab", "aaac"], "^a{3}[bc]$"),
+        case(vec!["abab1", "abab2"], "^(?:ab){2}[12]$")
+    )]
+    fn succeeds_with_repetition_conversion(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Repetition])
+            .with_positional_inference()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn explains_counted_classes() {
+        let explanation = RegExpBuilder::from(&["aa", "bb"])
+            .with_positional_inference()
+            .build_explanation();
+        assert_eq!(explanation, "matches one of 'a', 'b' 2 times");
+    }
+
+    #[test]
+    fn fails_with_substring_matching() {
+        let regexp = RegExpBuilder::from(&["ab", "cd"])
+            .with_positional_inference()
+            .with_substring_matching()
+            .try_build();
+        assert_eq!(
+            regexp,
+            Err(Error::ConflictingOptions {
+                a: "with_positional_inference",
+                b: "with_substring_matching"
+            })
+        );
+    }
+}
+
+mod structural_diff {
+    use super::*;
+
+    #[rstest(old_test_cases, new_test_cases, expected_changes,
+        case(vec!["a", "b"], vec!["a", "b", "c"], vec!["added 'c' to character class [ab]"]),
+        case(vec!["abc", "de"], vec!["abc", "de"], vec![]),
+        case(
+            vec!["abc", "de"],
+            vec!["abc", "fgh"],
+            vec!["removed branch de from alternation abc|de", "added branch fgh to alternation abc|de"]
+        ),
+        case(vec!["ab", "abc"], vec!["ab", "abcd"], vec!["replaced c with cd"])
+    )]
+    fn succeeds(old_test_cases: Vec<&str>, new_test_cases: Vec<&str>, expected_changes: Vec<&str>) {
+        let changes = RegExpBuilder::from(&old_test_cases)
+            .build_diff(&mut RegExpBuilder::from(&new_test_cases));
+        assert_eq!(changes, expected_changes);
+    }
+
+    #[test]
+    fn succeeds_with_changed_settings() {
+        let changes = RegExpBuilder::from(&["a1", "a2"])
+            .build_diff(RegExpBuilder::from(&["a1", "a2"]).with_conversion_of(&[Feature::Digit]));
+        assert_eq!(changes, vec!["replaced a[12] with a\\d"]);
+    }
+}
+
+mod complete_input_set {
+    use super::*;
+    use grex::Anchor;
+
+    #[rstest(test_cases, is_complete, expected_output,
+        case(vec!["a", "bc"], true, "^(?:bc|a)$"),
+        case(vec!["a", "bc"], false, "bc|a"),
+        case(vec!["abc"], true, "^abc$"),
+        case(vec!["abc"], false, "abc")
+    )]
+    fn succeeds(test_cases: Vec<&str>, is_complete: bool, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_complete_input_set(is_complete)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_anchors_given_last() {
+        let regexp = RegExpBuilder::from(&["abc"])
+            .with_complete_input_set(false)
+            .with_anchors(Anchor::WordBoundary, Anchor::WordBoundary)
+            .build();
+        assert_eq!(regexp, "\\babc\\b");
+    }
+}
+
+mod presets {
+    use super::*;
+    use grex::{Preset, RegExpConfig};
+
+    #[rstest(
+        preset,
+        expected_output,
+        case(Preset::Readable, "^(?:café\\t|(?:ab){2}(?:22|1))$"),
+        case(Preset::Compact, "^\\w{4}(?:\\d{1,2}|\\s)$"),
+        case(Preset::Portable, "^(?:abab(?:22|1)|caf\\u{e9}\\x09)$")
+    )]
+    fn succeeds(preset: Preset, expected_output: &str) {
+        let test_cases = vec!["abab1", "abab22", "café\t"];
+        let regexp = RegExpBuilder::from(&test_cases).with_preset(preset).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_astral_code_point_in_portable_preset() {
+        let test_cases = vec!["a😀"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_preset(Preset::Portable)
+            .build();
+        assert_that_regexp_is_correct(regexp, "^a\\u{d83d}\\u{de00}$", &test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_verbose_preset() {
+        let test_cases = vec!["abab1", "abab22", "café\t"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_preset(Preset::Verbose)
+            .build();
+        assert!(regexp.starts_with("(?x)\n# generated from: abab1, abab22, café\\t\n^\n"));
+        assert_that_regexp_matches_test_cases(&regexp, test_cases);
+    }
+
+    #[rstest(
+        preset,
+        case(Preset::Readable),
+        case(Preset::Compact),
+        case(Preset::Portable),
+        case(Preset::Verbose)
+    )]
+    fn succeeds_with_same_settings_as_config_preset(preset: Preset) {
+        let mut builder = RegExpBuilder::from(&["abc"]);
+        builder.with_preset(preset);
+        assert_eq!(builder.config(), &RegExpConfig::preset(preset));
+    }
+
+    #[test]
+    fn succeeds_with_settings_given_after_preset() {
+        let regexp = RegExpBuilder::from(&["abab1"])
+            .with_preset(Preset::Compact)
+            .with_conversion_of(&[Feature::Digit])
+            .build();
+        assert_eq!(regexp, "^abab\\d$");
+    }
+}
+
+mod case_insensitivity_as_char_classes {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["hello"], "^[Hh][Ee][Ll][Ll][Oo]$"),
+        case(vec!["Hello", "HELP"], "^[Hh][Ee][Ll](?:[Ll][Oo]|[Pp])$"),
+        case(vec!["a", "B", "c"], "^[A-Ca-c]$"),
+        case(vec!["Été", "x.y"], "^(?:[Xx]\\.[Yy]|[Éé][Tt][Éé])$"),
+        case(vec!["ab1", "AB22"], "^[Aa][Bb](?:22|1)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::CaseInsensitivity])
+            .with_case_insensitivity_as_char_classes()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["aaaa"], "^[Aa]{4}$"),
+        case(vec!["ab1", "AB22"], "^[Aa][Bb]\\d{1,2}$")
+    )]
+    fn succeeds_with_further_conversions(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[
+                Feature::CaseInsensitivity,
+                Feature::Repetition,
+                Feature::Digit,
+            ])
+            .with_case_insensitivity_as_char_classes()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[test]
+    fn succeeds_in_verbose_mode() {
+        let regexp = RegExpBuilder::from(&["hello"])
+            .with_conversion_of(&[Feature::CaseInsensitivity])
+            .with_case_insensitivity_as_char_classes()
+            .with_verbose_mode()
+            .build();
+        assert!(regexp.starts_with("(?x)\n"));
+        assert!(!regexp.contains("(?i"));
+        assert_that_regexp_matches_test_cases(&regexp, vec!["hello", "HeLLo"]);
+    }
+
+    #[test]
+    fn has_no_effect_without_case_insensitivity_feature() {
+        let regexp = RegExpBuilder::from(&["hello"])
+            .with_case_insensitivity_as_char_classes()
+            .build();
+        assert_eq!(regexp, "^hello$");
+    }
+}
+
+mod repeated_alternations {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abab", "abcd", "cdab", "cdcd"], "^(?:ab|cd){2}$"),
+        case(vec!["xabab", "xabcd", "xcdab", "xcdcd"], "^x(?:ab|cd){2}$"),
+        case(
+            vec!["ababab", "ababcd", "abcdab", "abcdcd", "cdabab", "cdabcd", "cdcdab", "cdcdcd"],
+            "^(?:ab|cd){3}$"
+        ),
+        case(vec!["abxab", "abxcd", "cdxab", "cdxcd"], "^(?:ab|cd)x(?:ab|cd)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_collapsed_alternation_repeats()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[test]
+    fn succeeds_without_collapsing_by_default() {
+        let test_cases = vec!["abab", "abcd", "cdab", "cdcd"];
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, "^(?:ab|cd)(?:ab|cd)$", &test_cases);
+    }
+
+    #[test]
+    fn succeeds_without_collapsing_capturing_groups() {
+        let test_cases = vec!["abab", "abcd", "cdab", "cdcd"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_collapsed_alternation_repeats()
+            .with_conversion_of(&[Feature::CapturingGroup])
+            .build();
+        assert_that_regexp_is_correct(regexp, "^(ab|cd)(ab|cd)$", &test_cases);
+    }
+}
+
+mod minimum_common_substring_length {
+    use super::*;
+
+    #[rstest(test_cases, length, expected_output,
+        case(vec!["abcd", "bd", "d"], 1, "^(?:abc|b)?d$"),
+        case(vec!["abcd", "bd", "d"], 2, "^(?:abcd|bd|d)$"),
+        case(vec!["aab", "ab", "b"], 2, "^(?:aab|ab|b)$"),
+        case(vec!["abc", "ade"], 1, "^a(?:bc|de)$"),
+        case(vec!["abc", "ade"], 2, "^(?:abc|ade)$"),
+        case(vec!["a", "abc"], 2, "^(?:abc|a)$"),
+        case(vec!["xabc", "xade"], 2, "^xa(?:bc|de)$"),
+        case(vec!["abcde", "bde", "de"], 2, "^(?:abc|b)?de$"),
+        case(vec!["abcde", "bde", "de"], 3, "^(?:abcde|bde|de)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, length: usize, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_minimum_common_substring_length(length)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+}
+
+mod permutation_diagnostics {
+    use super::*;
+
+    #[test]
+    fn succeeds_with_permutations() {
+        let test_cases = vec!["abc", "acb", "bac", "bca", "cab", "cba", "xyz"];
+        let diagnostics = RegExpBuilder::from(&test_cases).build_diagnostics();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "6 test cases such as 'abc', 'acb', 'bac' are permutations of the same characters, \
+                 so the expression will be a large alternation of them; consider matching \
+                 the characters with a character class and checking their counts separately"
+            ]
+        );
+    }
+
+    #[test]
+    fn succeeds_with_several_groups_of_permutations() {
+        let test_cases = vec![
+            "zyx", "zxy", "yzx", "yxz", "xzy", "xyz", "abc", "acb", "bac", "bca", "cab", "cba",
+        ];
+        let diagnostics = RegExpBuilder::from(&test_cases).build_diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].starts_with("6 test cases such as 'zyx', 'zxy', 'yzx'"));
+        assert!(diagnostics[1].starts_with("6 test cases such as 'abc', 'acb', 'bac'"));
+    }
+
+    #[rstest(test_cases,
+        case(vec!["abc", "bca", "cab"]),
+        case(vec!["aaa", "aaa", "aaa", "aaa", "aaa", "aaa"]),
+        case(vec!["abc", "abd", "abe", "abf", "abg", "abh"])
+    )]
+    fn succeeds_without_permutations(test_cases: Vec<&str>) {
+        let diagnostics = RegExpBuilder::from(&test_cases).build_diagnostics();
+        assert!(diagnostics.is_empty());
+    }
+}
+
+mod generalization_warnings {
+    use super::*;
+
+    #[test]
+    fn succeeds_with_digit_and_repetition_conversion() {
+        let warnings = RegExpBuilder::from(&["a1", "a22", "a333"])
+            .with_conversion_of(&[Feature::Digit, Feature::Repetition])
+            .build_warnings();
+        assert_eq!(
+            warnings,
+            vec![
+                "introduced '\\d' which matches any digit",
+                "introduced '{1,3}' which repeats '\\d' any number of times from 1 to 3"
+            ]
+        );
+    }
+
+    #[test]
+    fn succeeds_with_exceeded_repetition_count() {
+        let warnings = RegExpBuilder::from(&["aaaaaa", "b"])
+            .with_conversion_of(&[Feature::Repetition])
+            .with_maximum_repetition_count(3)
+            .build_warnings();
+        assert_eq!(
+            warnings,
+            vec!["introduced '+' which repeats 'a' without an upper bound"]
+        );
+    }
+
+    #[test]
+    fn succeeds_with_wildcard() {
+        let warnings = RegExpBuilder::from(&["a*b", "x"])
+            .with_wildcard('*', false)
+            .build_warnings();
+        assert_eq!(
+            warnings,
+            vec!["introduced '.*' which matches arbitrary text"]
+        );
+    }
+
+    #[test]
+    fn succeeds_with_each_warning_listed_once() {
+        let warnings = RegExpBuilder::from(&["1a2", "3b4"])
+            .with_conversion_of(&[Feature::Digit])
+            .build_warnings();
+        assert_eq!(warnings, vec!["introduced '\\d' which matches any digit"]);
+    }
+
+    #[test]
+    fn succeeds_without_generalization() {
+        let warnings = RegExpBuilder::from(&["abc", "abd"]).build_warnings();
+        assert!(warnings.is_empty());
+    }
+}
+
+mod candidates {
+    use super::*;
+    use itertools::Itertools;
+
+    #[rstest(test_cases, expected_candidates,
+        case(vec!["abcd", "bd", "d"], vec!["^(?:abc|b)?d$", "^(?:abcd|bd|d)$"]),
+        case(vec!["ac", "bc", "abc"], vec!["^(?:a?b|a)c$", "^(?:abc|bc|ac)$", "^(?:(?:ab|b)c|ac)$"]),
+        case(vec!["a"], vec!["^a$"])
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_candidates: Vec<&str>) {
+        let candidates = RegExpBuilder::from(&test_cases).build_candidates(10);
+        assert_eq!(candidates, expected_candidates);
+        for candidate in candidates {
+            assert_that_regexp_matches_test_cases(&candidate, test_cases.clone());
+        }
+    }
+
+    #[test]
+    fn succeeds_with_distinct_candidates_ranked_by_length() {
+        let test_cases = vec!["abcde", "bde", "de", "xade", "ade"];
+        let candidates = RegExpBuilder::from(&test_cases).build_candidates(10);
+        assert!(candidates.len() > 1);
+        assert_eq!(candidates.iter().unique().count(), candidates.len());
+        assert!(candidates
+            .iter()
+            .tuple_windows()
+            .all(|(a, b)| a.chars().count() <= b.chars().count()));
+        for candidate in candidates {
+            assert_that_regexp_matches_test_cases(&candidate, test_cases.clone());
+        }
+    }
+
+    #[test]
+    fn succeeds_with_limited_count() {
+        let candidates = RegExpBuilder::from(&["abcd", "bd", "d"]).build_candidates(1);
+        assert_eq!(candidates, vec!["^(?:abc|b)?d$"]);
+    }
+}
+
+mod dot_matching_newlines {
+    use super::*;
+    use grex::Error;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a*b", "x"], "^(?:a(?s:.)*b|x)$"),
+        case(vec!["*"], "^(?s:.)*$")
+    )]
+    fn succeeds_with_wildcard(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_wildcard('*', false)
+            .with_dot_matching_newlines()
+            .build();
+        assert_eq!(regexp, expected_output);
+        assert_that_regexp_matches_test_cases(&regexp, vec!["a\nb", "x"]);
+    }
+
+    #[test]
+    fn succeeds_with_wildcard_excluding_whitespace() {
+        let regexp = RegExpBuilder::from(&["a*b", "x"])
+            .with_wildcard('*', true)
+            .with_dot_matching_newlines()
+            .build();
+        assert_eq!(regexp, "^(?:a\\S*b|x)$");
+    }
+
+    #[test]
+    fn succeeds_with_fallback_for_too_many_branches() {
+        let test_cases = vec!["a\nb", "ab", "b\nc", "c", "d"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_maximum_alternation_branches(2)
+            .with_dot_matching_newlines()
+            .build();
+        assert_that_regexp_is_correct(regexp, "^[a-d](?s:.)*$", &test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_verbose_mode() {
+        let test_cases = vec!["a\nb", "ab", "b\nc", "c", "d"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_maximum_alternation_branches(2)
+            .with_dot_matching_newlines()
+            .with_verbose_mode()
+            .build();
+        assert_eq!(regexp, "(?x)\n^\n  [a-d](?s:.)*\n$");
+        assert_that_regexp_matches_test_cases(&regexp, test_cases);
+    }
+
+    #[test]
+    fn fails_with_posix_char_classes() {
+        let regexp = RegExpBuilder::from(&["a*b"])
+            .with_wildcard('*', false)
+            .with_dot_matching_newlines()
+            .with_posix_char_classes()
+            .try_build();
+        assert_eq!(
+            regexp,
+            Err(Error::ConflictingOptions {
+                a: "with_posix_char_classes",
+                b: "with_dot_matching_newlines"
+            })
+        );
+    }
+}
+
+mod explicit_newline_exclusion {
+    use super::*;
+    use grex::Error;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a*b", "x"], "^(?:a[^\\n]*b|x)$"),
+        case(vec!["*"], "^[^\\n]*$")
+    )]
+    fn succeeds_with_wildcard(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_wildcard('*', false)
+            .with_explicit_newline_exclusion()
+            .build();
+        assert_eq!(regexp, expected_output);
+        assert_that_regexp_matches_test_cases(&regexp, vec!["ab", "a-b", "x"]);
+        assert!(!Regex::new(&regexp).unwrap().is_match("a\nb"));
+    }
+
+    #[test]
+    fn succeeds_with_same_set_written_as_dot_otherwise() {
+        let test_cases = vec!["ab", "bc", "cd"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_maximum_alternation_branches(2)
+            .build();
+        assert_that_regexp_is_correct(regexp, "^[a-c].*$", &test_cases);
+
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_maximum_alternation_branches(2)
+            .with_explicit_newline_exclusion()
+            .build();
+        assert_that_regexp_is_correct(regexp, "^[a-c][^\\n]*$", &test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_verbose_mode() {
+        let regexp = RegExpBuilder::from(&["a*b", "x"])
+            .with_wildcard('*', false)
+            .with_explicit_newline_exclusion()
+            .with_verbose_mode()
+            .build();
+        assert_eq!(
+            regexp,
+            indoc!(
+                r#"
+                (?x)
+                ^
+                  (?:
+                    a[^\n]*b
+                    |
+                    x
+                  )
+                $"#
+            )
+        );
+        assert_that_regexp_matches_test_cases(&regexp, vec!["ab", "x"]);
+    }
+
+    #[rstest(
+        other_option,
+        case("with_dot_matching_newlines"),
+        case("with_posix_char_classes")
+    )]
+    fn fails_with_conflicting_option(other_option: &'static str) {
+        let mut builder = RegExpBuilder::from(&["a*b"]);
+        builder
+            .with_wildcard('*', false)
+            .with_explicit_newline_exclusion();
+        if other_option == "with_dot_matching_newlines" {
+            builder.with_dot_matching_newlines();
+        } else {
+            builder.with_posix_char_classes();
+        }
+        assert_eq!(
+            builder.try_build(),
+            Err(Error::ConflictingOptions {
+                a: other_option,
+                b: "with_explicit_newline_exclusion"
+            })
+        );
+    }
+}
+
+mod first_chars {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[rstest(test_cases, expected_chars,
+        case(vec!["ab", "b"], vec!['a', 'b']),
+        case(vec!["xc", "c", "yd", ""], vec!['c', 'x', 'y']),
+        case(vec!["aab", "b"], vec!['a', 'b']),
+        case(vec!["Abc", "def"], vec!['A', 'd'])
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_chars: Vec<char>) {
+        let first_chars = RegExpBuilder::from(&test_cases).build_first_chars();
+        assert_eq!(
+            first_chars,
+            Some(expected_chars.into_iter().collect::<BTreeSet<char>>())
+        );
+    }
+
+    #[test]
+    fn succeeds_with_case_insensitive_matching() {
+        let first_chars = RegExpBuilder::from(&["Abc", "def"])
+            .with_conversion_of(&[Feature::CaseInsensitivity])
+            .build_first_chars();
+        assert_eq!(
+            first_chars,
+            Some(
+                vec!['A', 'D', 'a', 'd']
+                    .into_iter()
+                    .collect::<BTreeSet<char>>()
+            )
+        );
+    }
+
+    #[test]
+    fn succeeds_with_hex_class() {
+        let test_cases = "0123456789abcdef"
+            .chars()
+            .map(|c| format!("{}x", c))
+            .collect::<Vec<_>>();
+        let first_chars = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Hex])
+            .build_first_chars();
+        assert_eq!(
+            first_chars,
+            Some(('0'..='9').chain('a'..='f').collect::<BTreeSet<char>>())
+        );
+    }
+
+    #[test]
+    fn fails_with_shorthand_class() {
+        let first_chars = RegExpBuilder::from(&["1a", "b"])
+            .with_conversion_of(&[Feature::Digit])
+            .build_first_chars();
+        assert_eq!(first_chars, None);
+    }
+}
+
+mod maximum_match_length {
+    use super::*;
+
+    #[rstest(test_cases, features, expected_length,
+        case(vec!["a", "abc"], vec![], Some(3)),
+        case(vec!["", "é"], vec![], Some(1)),
+        case(vec!["1a", "22b", "x"], vec![Feature::Digit], Some(3)),
+        case(vec!["aaa", "aaaaa"], vec![Feature::Repetition], Some(5)),
+        case(vec!["abab", "ababab", "x"], vec![Feature::Repetition], Some(6))
+    )]
+    fn succeeds(test_cases: Vec<&str>, features: Vec<Feature>, expected_length: Option<usize>) {
+        let mut builder = RegExpBuilder::from(&test_cases);
+        if !features.is_empty() {
+            builder.with_conversion_of(&features);
+        }
+        assert_eq!(builder.build_maximum_match_length(), expected_length);
+    }
+
+    #[test]
+    fn fails_with_unbounded_repetition() {
+        let length = RegExpBuilder::from(&["a", "aaaaaa"])
+            .with_conversion_of(&[Feature::Repetition])
+            .with_maximum_repetition_count(3)
+            .build_maximum_match_length();
+        assert_eq!(length, None);
+    }
+
+    #[test]
+    fn fails_with_wildcard() {
+        let length = RegExpBuilder::from(&["a*b"])
+            .with_wildcard('*', false)
+            .build_maximum_match_length();
+        assert_eq!(length, None);
+    }
+}
+
+mod required_prefix {
+    use super::*;
+
+    #[rstest(test_cases, features, expected_output, expected_prefix,
+        case(vec!["abcd", "abce"], vec![], "^abc[de]$", "abc"),
+        case(vec!["abcd", "abcee"], vec![], "^abc(?:ee|d)$", "abc"),
+        case(vec!["abc"], vec![], "^abc$", "abc"),
+        case(vec!["a", "abc"], vec![], "^a(?:bc)?$", "a"),
+        case(vec!["abc", "xyz"], vec![], "^(?:abc|xyz)$", ""),
+        case(vec!["abc1", "abc22"], vec![Feature::Digit], "^abc\\d(?:\\d)?$", "abc"),
+        case(vec!["xyxyz", "xyxyxyz"], vec![Feature::Repetition], "^(?:xy){2,3}z$", "xyxy"),
+        case(vec!["xyxyz"], vec![Feature::Repetition], "^(?:xy){2}z$", "xyxyz"),
+        case(vec!["x1a1a"], vec![Feature::Digit, Feature::Repetition], "^x(?:\\da){2}$", "x"),
+        case(vec!["12cd", "12ce"], vec![Feature::CaseInsensitivity], "(?i)^12c[de]$", "12")
+    )]
+    fn succeeds(
+        test_cases: Vec<&str>,
+        features: Vec<Feature>,
+        expected_output: &str,
+        expected_prefix: &str,
+    ) {
+        let mut builder = RegExpBuilder::from(&test_cases);
+        if !features.is_empty() {
+            builder.with_conversion_of(&features);
+        }
+        let (regexp, prefix) = builder.build_with_required_prefix();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_eq!(prefix, expected_prefix);
+    }
+}
+
+mod disabled_repetitions {
+    use super::*;
+    use grex::{Error, Preset};
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abab", "abcd", "cdab", "cdcd"], "^(?:ab|cd)(?:ab|cd)$"),
+        case(vec!["aaa", "aaaa", "b"], "^(?:aaaa?|b)$"),
+        case(vec!["xyxyxy", "1212"], "^(?:xyxyxy|1212)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::Repetition])
+            .with_disabled_repetitions()
+            .build();
+        assert!(!regexp.contains(['*', '+', '{']));
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_preset() {
+        let regexp = RegExpBuilder::from(&["aaa", "aaaa", "b"])
+            .with_preset(Preset::Compact)
+            .with_disabled_repetitions()
+            .build();
+        assert_eq!(regexp, "^\\w(?:\\w\\w(?:\\w)?)?$");
+    }
+
+    #[test]
+    fn fails_with_wildcard() {
+        let result = RegExpBuilder::from(&["a*"])
+            .with_wildcard('*', false)
+            .with_disabled_repetitions()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_wildcard",
+                b: "with_disabled_repetitions"
+            })
+        );
+    }
+
+    #[test]
+    fn fails_with_maximum_alternation_branches() {
+        let result = RegExpBuilder::from(&["a", "b"])
+            .with_maximum_alternation_branches(1)
+            .with_disabled_repetitions()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_maximum_alternation_branches",
+                b: "with_disabled_repetitions"
+            })
+        );
+    }
+}
+
+mod alternatives {
+    use super::*;
+
+    #[rstest(test_cases, expected_alternatives,
+        case(vec!["abc", "d", "xy"], vec!["abc", "xy", "d"]),
+        case(vec!["a", "b", "cde"], vec!["cde", "[ab]"]),
+        case(vec!["a", "abc"], vec!["a(?:bc)?"]),
+        case(vec!["abc"], vec!["abc"]),
+        case(vec!["abc", "d", "xy", ""], vec!["(?:abc|xy|d)?"])
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_alternatives: Vec<&str>) {
+        let alternatives = RegExpBuilder::from(&test_cases).build_alternatives();
+        assert_eq!(alternatives, expected_alternatives);
+    }
+}
+
+mod inline_flags {
+    use super::*;
+    use grex::Error;
+
+    #[test]
+    fn succeeds_with_all_flags_combined() {
+        let test_cases = vec!["Ab*c", "Dc"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::CaseInsensitivity])
+            .with_wildcard('*', false)
+            .with_dot_matching_newlines()
+            .with_leading_dot_all_flag()
+            .with_multiline_matching()
+            .build();
+        assert_eq!(regexp, "(?ims)^(?:ab.*|d)c$");
+        assert_eq!(regexp.matches("(?").count(), 2);
+        assert_that_regexp_matches_test_cases(&regexp, vec!["abxc", "AB\nC", "dc", "x\ndc"]);
+    }
+
+    #[rstest(test_cases, expected_output, multiline_input,
+        case(vec!["a", "b"], "(?m)^[ab]$", "x\na\nb"),
+        case(vec!["ab", "ac"], "(?m)^a[bc]$", "x\nab")
+    )]
+    fn succeeds_with_multiline_matching(
+        test_cases: Vec<&str>,
+        expected_output: &str,
+        multiline_input: &str,
+    ) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_multiline_matching()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, vec![multiline_input]);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a*b"], "(?s)^a.*b$"),
+        case(vec!["a*b", "c"], "(?s)^(?:a.*b|c)$")
+    )]
+    fn succeeds_with_leading_dot_all_flag(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_wildcard('*', false)
+            .with_dot_matching_newlines()
+            .with_leading_dot_all_flag()
+            .build();
+        assert_eq!(regexp, expected_output);
+        assert_that_regexp_matches_test_cases(expected_output, vec!["a\nb"]);
+    }
+
+    #[test]
+    fn succeeds_with_leading_dot_all_flag_only_if_dot_matches_newlines() {
+        let regexp = RegExpBuilder::from(&["a*b"])
+            .with_wildcard('*', false)
+            .with_leading_dot_all_flag()
+            .build();
+        assert_eq!(regexp, "^a.*b$");
+    }
+
+    #[test]
+    fn succeeds_with_combined_flags_in_verbose_mode() {
+        let regexp = RegExpBuilder::from(&["Ab", "c"])
+            .with_conversion_of(&[Feature::CaseInsensitivity])
+            .with_multiline_matching()
+            .with_verbose_mode()
+            .build();
+        assert_eq!(
+            regexp,
+            indoc!(
+                r#"
+                (?imx)
+                ^
+                  (?:
+                    ab
+                    |
+                    c
+                  )
+                $"#
+            )
+        );
+    }
+
+    #[test]
+    fn fails_with_posix_char_classes() {
+        let result = RegExpBuilder::from(&["a", "b"])
+            .with_posix_char_classes()
+            .with_multiline_matching()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_posix_char_classes",
+                b: "with_multiline_matching"
+            })
+        );
+    }
+}
+
+mod char_class_ranges {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["A", "Z", "a", "z"], "^[AZaz]$"),
+        case(vec!["A", "B", "C", "Z", "a", "b", "c", "z"], "^[A-CZa-cz]$"),
+        case(vec!["Y", "Z", "[", "\\", "]", "^", "_", "`", "a", "b"], "^[Y-b]$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+}
+
+mod shortest_char_classes {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a", "c", "e", "g", "i"], "^[^k]$"),
+        case(vec!["a", "c"], "^[ac]$"),
+        case(vec!["a", "c", "e"], "^[ace]$"),
+        case(vec!["a", "c", "e", "g"], "^[^ik]$"),
+        case(vec!["a", "c", "e", "g", "i", "k"], "^.$"),
+        case(vec!["xa", "xc", "xe", "xg", "xi", "x"], "^x[^k]?$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_shortest_char_classes(&['a', 'c', 'e', 'g', 'i', 'k'])
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a", "b", "\n"], "^[\\s\\S]$")
+    )]
+    fn succeeds_with_line_break_in_alphabet(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_shortest_char_classes(&['a', 'b', '\n'])
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn does_not_exclude_opposite_case_when_case_insensitive() {
+        let test_cases = vec!["a", "b", "d"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::CaseInsensitivity])
+            .with_shortest_char_classes(&['a', 'b', 'd', 'A', 'B', 'D', 'z'])
+            .build();
+        assert_that_regexp_is_correct(regexp, "(?i)^[^z]$", &test_cases);
+        assert_that_regexp_matches_test_cases("(?i)^[^z]$", vec!["a", "b", "d", "A", "B", "D"]);
+    }
+}
+
+mod negative_test_cases {
+    use super::*;
+    use grex::Error;
+
+    #[rstest(test_cases, negative_test_cases, expected_output,
+        case(vec!["abc"], vec!["ab"], "^a(?:bc)?$"),
+        case(vec!["abc", "abd"], vec!["ab", "x"], "^a(?:b[cd])?$"),
+        case(vec!["abcd"], vec!["a", "abc"], "^ab(?:cd)?$")
+    )]
+    fn succeeds_with_prefix_matching(
+        test_cases: Vec<&str>,
+        negative_test_cases: Vec<&str>,
+        expected_output: &str,
+    ) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_prefix_matching()
+            .with_negative_test_cases(&negative_test_cases)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_does_not_match_negative_test_cases(expected_output, negative_test_cases);
+    }
+
+    #[rstest(test_cases, negative_test_cases, expected_output,
+        case(vec!["abc"], vec!["b", "ab"], "^(?:a?bc|[ac])$")
+    )]
+    fn succeeds_with_substring_matching(
+        test_cases: Vec<&str>,
+        negative_test_cases: Vec<&str>,
+        expected_output: &str,
+    ) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_substring_matching()
+            .with_negative_test_cases(&negative_test_cases)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_does_not_match_negative_test_cases(expected_output, negative_test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_case_insensitive_negative_test_case() {
+        let test_cases = vec!["abc"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&[Feature::CaseInsensitivity])
+            .with_prefix_matching()
+            .with_negative_test_cases(&["AB"])
+            .build();
+        assert_that_regexp_is_correct(regexp, "(?i)^a(?:bc)?$", &test_cases);
+    }
+
+    #[test]
+    fn fails_with_contradicting_test_case() {
+        let result = RegExpBuilder::from(&["abc", "def"])
+            .with_negative_test_cases(&["def"])
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ContradictingTestCase {
+                test_case: "def".to_string()
+            })
+        );
+    }
+
+    fn assert_that_regexp_does_not_match_negative_test_cases(
+        expected_output: &str,
+        negative_test_cases: Vec<&str>,
+    ) {
+        let re = Regex::new(expected_output).unwrap();
+        for test_case in negative_test_cases {
+            assert!(
+                !re.is_match(test_case),
+                "\n\n\"{}\" matches regex {}\n\n",
+                test_case,
+                expected_output
+            );
+        }
+    }
+}
+
+mod nfa {
+    use super::*;
+    use grex::Transition;
+    use itertools::Itertools;
+
+    #[rstest(test_cases, features,
+        case(vec!["ab", "ac", "d", ""], vec![]),
+        case(vec!["abab", "ab", "c"], vec![Feature::Repetition]),
+        case(vec!["a1", "b22", "c"], vec![Feature::Digit]),
+        case(vec!["ab", "b c"], vec![Feature::Space, Feature::Word]),
+        case(vec!["AbC", "a"], vec![Feature::CaseInsensitivity]),
+        case(vec!["aaa1", "aa2"], vec![Feature::Repetition, Feature::Digit])
+    )]
+    fn accepts_exactly_the_strings_matched_by_the_regexp(
+        test_cases: Vec<&str>,
+        features: Vec<Feature>,
+    ) {
+        let mut builder = RegExpBuilder::from(&test_cases);
+        if !features.is_empty() {
+            builder.with_conversion_of(&features);
+        }
+        let regexp = builder.build();
+        let nfa = builder.build_nfa();
+        let re = Regex::new(&regexp).unwrap();
+
+        for test_case in test_cases.iter() {
+            assert!(nfa.accepts(test_case), "{:?} is not accepted", test_case);
+        }
+
+        let alphabet = ["a", "A", "b", "c", "1", "2", " "];
+        for length in 0..=4 {
+            for chars in itertools::repeat_n(alphabet.iter(), length).multi_cartesian_product() {
+                let s = chars.into_iter().join("");
+                assert_eq!(
+                    nfa.accepts(&s),
+                    re.is_match(&s),
+                    "automaton and regex {} disagree on {:?}",
+                    regexp,
+                    s
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn accepts_wildcards() {
+        let nfa = RegExpBuilder::from(&["a*b"])
+            .with_wildcard('*', true)
+            .build_nfa();
+        assert!(nfa.accepts("ab"));
+        assert!(nfa.accepts("axyzb"));
+        assert!(!nfa.accepts("ax zb"));
+        assert!(!nfa.accepts("abc"));
+    }
+
+    #[test]
+    fn has_single_initial_and_final_state() {
+        let nfa = RegExpBuilder::from(&["a", "b"]).build_nfa();
+        let transitions = nfa.transitions();
+        assert_eq!(nfa.state_count(), 2);
+        assert_eq!(
+            transitions,
+            vec![(
+                nfa.initial_state(),
+                &Transition::CharSet(vec!['a', 'b'].into_iter().collect()),
+                nfa.final_state()
+            )]
+        );
+    }
+}
+
+mod literal_quoting {
+    use super::*;
+    use grex::Error;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["(a)[b]{c}"], "^\\Q(a)[b]{c}\\E$"),
+        case(vec!["1.2.3.4.5.6"], "^\\Q1.2.3.4.5.6\\E$"),
+        case(vec!["x(a)[b]{c}yy", "x"], "^x(?:\\Q(a)[b]{c}yy\\E)?$"),
+        case(vec!["(((((\\E)))))"], "^\\Q(((((\\E\\\\E\\Q)))))\\E$"),
+        case(vec!["(((((\n)))))"], "^\\Q(((((\\E\\n\\Q)))))\\E$")
+    )]
+    fn succeeds_with_quoted_literals(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_literal_quoting()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["a.b*c+d?e"], "^a\\.b\\*c\\+d\\?e$"),
+        case(vec!["abc"], "^abc$"),
+        case(vec!["a.b", "a.c"], "^a\\.[bc]$")
+    )]
+    fn succeeds_with_escaped_literals_if_shorter(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_literal_quoting()
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_that_regexp_matches_test_cases(expected_output, test_cases);
+    }
+
+    #[test]
+    fn fails_with_posix_char_classes() {
+        let result = RegExpBuilder::from(&["(a)[b]{c}"])
+            .with_posix_char_classes()
+            .with_literal_quoting()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_posix_char_classes",
+                b: "with_literal_quoting"
+            })
+        );
+    }
+
+    #[test]
+    fn fails_with_verbose_mode() {
+        let result = RegExpBuilder::from(&["(a)[b]{c}"])
+            .with_verbose_mode()
+            .with_literal_quoting()
+            .try_build();
+        assert_eq!(
+            result,
+            Err(Error::ConflictingOptions {
+                a: "with_verbose_mode",
+                b: "with_literal_quoting"
+            })
+        );
+    }
+}
+
+mod input_filter {
+    use super::*;
+    use grex::ProgressEvent;
+    use std::sync::{Arc, Mutex};
+
+    #[rstest(test_cases, expected_output, excluded_test_cases,
+        case(vec!["abc", "abcdefgh", "abd"], "^ab[cd]$", vec!["abcdefgh"]),
+        case(vec!["12", "x1", "34"], "^(?:12|34)$", vec!["x1"]),
+        case(vec!["a", "", "b"], "^[ab]$", vec![""])
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str, excluded_test_cases: Vec<&str>) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_input_filter(|it| !it.is_empty() && it.len() <= 3 && !it.starts_with('x'))
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+
+        let re = Regex::new(expected_output).unwrap();
+        for test_case in excluded_test_cases {
+            assert!(
+                !re.is_match(test_case),
+                "excluded test case {:?} is matched by {}",
+                test_case,
+                expected_output
+            );
+        }
+    }
+
+    #[test]
+    fn applies_filter_before_preprocessor() {
+        let regexp = RegExpBuilder::from(&["abc", "ABC", "xyz"])
+            .with_input_filter(|it| it.chars().all(|c| c.is_lowercase()))
+            .with_preprocessor(|it| it.to_uppercase())
+            .build();
+        assert_eq!(regexp, "^(?:ABC|XYZ)$");
+    }
+
+    #[test]
+    fn reports_number_of_excluded_test_cases() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorded_events = Arc::clone(&events);
+        RegExpBuilder::from(&["a", "bb", "ccc", "dddd"])
+            .with_input_filter(|it| it.len() % 2 == 0)
+            .with_progress_callback(move |event| recorded_events.lock().unwrap().push(event))
+            .build();
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events[..2],
+            [
+                ProgressEvent::TestCasesFiltered { excluded_count: 2 },
+                ProgressEvent::TestCasesIngested { test_case_count: 2 }
+            ]
+        );
+    }
+}
+
+mod length_buckets {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["ab", "cd", "efghij", "klmnopq"], "^(?:(?:ab|cd)|(?:klmnopq|efghij))$"),
+        case(vec!["a", "bc", "defghijk"], "^(?:(?:bc|a)|defghijk)$"),
+        case(vec!["", "abc", "abcdefgh"], "^(?:(?:abc)?|abcdefgh)$"),
+        case(vec!["abc", "abd", "xyz"], "^(?:ab[cd]|xyz)$")
+    )]
+    fn succeeds(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_length_buckets(5)
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[test]
+    fn builds_one_branch_per_length_group() {
+        let test_cases = vec!["ab", "cd", "efghij", "klmnopq"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_length_buckets(5)
+            .with_conversion_of(&[Feature::CapturingGroup])
+            .build();
+        assert_eq!(regexp, "^((ab|cd)|(klmnopq|efghij))$");
+
+        let captures = Regex::new(&regexp).unwrap();
+        for (test_case, branch) in [("ab", 2), ("cd", 2), ("efghij", 3), ("klmnopq", 3)] {
+            let groups = captures.captures(test_case).unwrap();
+            assert!(groups.get(branch).is_some());
+            assert!(groups.get(5 - branch).is_none());
+        }
+    }
+
+    #[test]
+    fn annotates_branches_with_lengths_in_verbose_mode() {
+        let test_cases = vec!["ab", "cd", "efghij", "klmnopq"];
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_length_buckets(5)
+            .with_verbose_mode()
+            .build();
+        assert_that_regexp_is_correct(
+            regexp,
+            indoc!(
+                r#"
+                (?x)
+                ^
+                  (?:
+                    # length 2
+                    (?:
+                      ab
+                      |
+                      cd
+                    )
+                    |
+                    # length 6 to 7
+                    (?:
+                      klmnopq
+                      |
+                      efghij
+                    )
+                  )
+                $"#
+            ),
+            &test_cases,
+        );
+    }
+
+    #[test]
+    fn builds_single_branch_if_all_lengths_fall_into_same_bucket() {
+        let regexp = RegExpBuilder::from(&["ab", "cd", "efghij", "klmnopq"])
+            .with_length_buckets(10)
+            .with_verbose_mode()
+            .build();
+        assert!(!regexp.contains("# length"));
+    }
+}
+
+mod equivalence {
+    use super::*;
+    use grex::Error;
+
+    #[rstest(test_cases, other_test_cases,
+        case(vec!["abc", "abd"], vec!["abd", "abc"]),
+        case(vec!["a", "a", "b"], vec!["b", "a"]),
+        case(vec!["", "xyz"], vec!["xyz", ""])
+    )]
+    fn succeeds_for_same_test_cases(test_cases: Vec<&str>, other_test_cases: Vec<&str>) {
+        let result = RegExpBuilder::from(&test_cases)
+            .is_equivalent_to(&RegExpBuilder::from(&other_test_cases));
+        assert_eq!(result, Ok(true));
+    }
+
+    #[rstest(test_cases, other_test_cases,
+        case(vec!["abc", "abd"], vec!["abc", "abe"]),
+        case(vec!["a"], vec!["a", ""]),
+        case(vec!["ab"], vec!["abab"])
+    )]
+    fn fails_for_different_test_cases(test_cases: Vec<&str>, other_test_cases: Vec<&str>) {
+        let result = RegExpBuilder::from(&test_cases)
+            .is_equivalent_to(&RegExpBuilder::from(&other_test_cases));
+        assert_eq!(result, Ok(false));
+    }
+
+    #[test]
+    fn succeeds_for_test_cases_equivalent_under_conversion() {
+        let mut builder = RegExpBuilder::from(&["a1", "b2"]);
+        let mut other_builder = RegExpBuilder::from(&["b9", "a0"]);
+        assert_eq!(builder.is_equivalent_to(&other_builder), Ok(false));
+
+        builder.with_conversion_of(&[Feature::Digit]);
+        other_builder.with_conversion_of(&[Feature::Digit]);
+        assert_eq!(builder.is_equivalent_to(&other_builder), Ok(true));
+    }
+
+    #[test]
+    fn succeeds_for_test_cases_equivalent_under_case_insensitivity() {
+        let result = RegExpBuilder::from(&["ABC", "x"])
+            .with_conversion_of(&[Feature::CaseInsensitivity])
+            .is_equivalent_to(
+                RegExpBuilder::from(&["abc", "X"])
+                    .with_conversion_of(&[Feature::CaseInsensitivity]),
+            );
+        assert_eq!(result, Ok(true));
+    }
+
+    #[test]
+    fn takes_negative_test_cases_into_account() {
+        let mut builder = RegExpBuilder::from(&["abc"]);
+        builder.with_prefix_matching();
+        let mut other_builder = RegExpBuilder::from(&["ab", "abc"]);
+        other_builder
+            .with_prefix_matching()
+            .with_negative_test_cases(&["a"]);
+        assert_eq!(builder.is_equivalent_to(&other_builder), Ok(false));
+
+        builder.with_negative_test_cases(&["a"]);
+        assert_eq!(builder.is_equivalent_to(&other_builder), Ok(true));
+    }
+
+    #[test]
+    fn fails_without_test_cases() {
+        let result = RegExpBuilder::from(&["abc"]).is_equivalent_to(&RegExpBuilder::new());
+        assert_eq!(result, Err(Error::NoTestCases { requirement: None }));
+    }
+}
+
+mod columns {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["1,abc,x", "2,abd,y"], "^ab[cd]$"),
+        case(vec!["1,\"a,b\",x", "2,c"], "^(?:a,b|c)$"),
+        case(vec!["1,\"say \"\"hi\"\"\"", "2,\"\""], "^(?:say \"hi\")?$"),
+        case(vec!["1, a ,x", "2,b"], "^(?: a |b)$"),
+        case(vec!["1,abc", "2", "3,"], "^(?:abc)?$")
+    )]
+    fn succeeds_with_comma_separated_values(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases).with_column(',', 1).build();
+        assert_eq!(regexp, expected_output);
+    }
+
+    #[test]
+    fn succeeds_with_tab_separated_values_from_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "id\tcode\n1\tA-1\n2\tB-22\r\n3\tC-333").unwrap();
+
+        let regexp = RegExpBuilder::from_file(file.path())
+            .with_column('\t', 1)
+            .with_input_filter(|it| it != "code")
+            .with_conversion_of(&[Feature::Digit])
+            .build();
+        assert_eq!(regexp, "^(?:C\\-\\d\\d|B\\-\\d|A\\-)\\d$");
+        assert_that_regexp_matches_test_cases(&regexp, vec!["A-1", "B-22", "C-333"]);
+    }
+
+    #[test]
+    fn succeeds_with_separators() {
+        let regexp = RegExpBuilder::from(&["x;1234", "y;5678"])
+            .with_column(';', 1)
+            .with_separators(&[' '])
+            .with_conversion_of(&[Feature::Digit])
+            .build();
+        assert_eq!(regexp, "^\\d ?\\d ?\\d ?\\d$");
+        assert_that_regexp_matches_test_cases(&regexp, vec!["1234", "12 34", "5 6 7 8"]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "No test cases containing the selected column have been provided for regular expression generation"
+    )]
+    fn fails_without_selected_column() {
+        RegExpBuilder::from(&["a,b", "c"])
+            .with_column(',', 2)
+            .build();
+    }
+}
+
+mod trailing_punctuation {
+    use super::*;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["word", "word.", "word,"], "^word[,.]?$"),
+        case(vec!["word", "word.", "word,", "other", "other;"], "^(?:other;?|word[,.]?)$"),
+        case(vec!["a word", "a word!", "a word?", "a word."], "^a word[!.?]?$")
+    )]
+    fn succeeds_with_optional_trailing_punctuation(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases).build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+}
+
+mod time_budget {
+    use super::*;
+    use std::time::Duration;
+
+    #[rstest(test_cases, expected_output,
+        case(vec!["abc", "abd", "b"], "^(?:b|abc|abd)$"),
+        case(vec!["", "a", "b"], "^(?:a|b)?$"),
+        case(vec!["", ""], "^$")
+    )]
+    fn succeeds_with_fallback_if_budget_is_exceeded(test_cases: Vec<&str>, expected_output: &str) {
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_time_budget(Duration::from_nanos(1))
+            .build();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_fallback_for_many_test_cases() {
+        let test_cases = (0..2000)
+            .map(|it| format!("{:x}", it * 7919))
+            .collect::<Vec<_>>();
+        let regexp = RegExpBuilder::from(&test_cases)
+            .with_time_budget(Duration::from_nanos(1))
+            .build();
+        assert_eq!(regexp.matches('|').count(), test_cases.len() - 1);
+        assert_that_regexp_matches_test_cases(
+            &regexp,
+            test_cases.iter().map(|it| it.as_str()).collect(),
+        );
+    }
+
+    #[test]
+    fn succeeds_without_fallback_if_budget_is_sufficient() {
+        let regexp = RegExpBuilder::from(&["abc", "abd", "b"])
+            .with_time_budget(Duration::from_secs(60))
+            .build();
+        assert_eq!(regexp, "^(?:ab[cd]|b)$");
+    }
+}
+
+mod search_and_full_match {
+    use super::*;
+    use grex::Anchor;
+
+    #[rstest(test_cases, expected_search_regexp, expected_full_match_regexp,
+        case(vec!["a", "bc"], "bc|a", "^(?:bc|a)$"),
+        case(vec!["abc"], "abc", "^abc$"),
+        case(vec!["1", "2", "3"], "[1-3]", "^[1-3]$")
+    )]
+    fn succeeds(
+        test_cases: Vec<&str>,
+        expected_search_regexp: &str,
+        expected_full_match_regexp: &str,
+    ) {
+        let (search_regexp, full_match_regexp) =
+            RegExpBuilder::from(&test_cases).build_for_search_and_full_match();
+        assert_eq!(search_regexp, expected_search_regexp);
+        assert_eq!(full_match_regexp, expected_full_match_regexp);
+        assert_that_regexp_matches_test_cases(&search_regexp, test_cases.clone());
+        assert_that_regexp_matches_test_cases(&full_match_regexp, test_cases);
+    }
+
+    #[test]
+    fn succeeds_with_superstring_matched_by_search_form_only() {
+        let (search_regexp, full_match_regexp) =
+            RegExpBuilder::from(&["abc", "de"]).build_for_search_and_full_match();
+        assert!(Regex::new(&search_regexp).unwrap().is_match("xxabcxx"));
+        assert!(!Regex::new(&full_match_regexp).unwrap().is_match("xxabcxx"));
+    }
+
+    #[test]
+    fn succeeds_with_specified_anchors_replaced() {
+        let (search_regexp, full_match_regexp) = RegExpBuilder::from(&["a", "ab"])
+            .with_anchors(Anchor::WordBoundary, Anchor::None)
+            .build_for_search_and_full_match();
+        assert_eq!(search_regexp, "ab?");
+        assert_eq!(full_match_regexp, "^ab?$");
+    }
+}
+
+mod applied_conversions {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[rstest(test_cases, features, expected_output, expected_conversions,
+        case(vec!["1", "22"], vec![Feature::Digit], "^\\d(?:\\d)?$", vec![Feature::Digit]),
+        case(vec!["123", "456"], vec![Feature::Digit, Feature::Word, Feature::Repetition], "^\\d{3}$", vec![Feature::Digit, Feature::Repetition]),
+        case(vec!["a b", "c"], vec![Feature::Digit, Feature::Space, Feature::CapturingGroup], "^(a\\sb|c)$", vec![Feature::Space, Feature::CapturingGroup]),
+        case(vec!["123", "(4"], vec![Feature::CapturingGroup, Feature::CaseInsensitivity], "(?i)^(123|\\(4)$", vec![Feature::CapturingGroup]),
+        case(vec!["abc"], vec![Feature::CapturingGroup, Feature::CaseInsensitivity], "(?i)^abc$", vec![Feature::CaseInsensitivity]),
+        case(vec!["abc", "d"], vec![Feature::Digit, Feature::Repetition], "^(?:abc|d)$", vec![])
+    )]
+    fn succeeds(
+        test_cases: Vec<&str>,
+        features: Vec<Feature>,
+        expected_output: &str,
+        expected_conversions: Vec<Feature>,
+    ) {
+        let (regexp, conversions) = RegExpBuilder::from(&test_cases)
+            .with_conversion_of(&features)
+            .build_with_applied_conversions();
+        assert_that_regexp_is_correct(regexp, expected_output, &test_cases);
+        assert_eq!(
+            conversions,
+            expected_conversions.into_iter().collect::<BTreeSet<_>>()
+        );
+    }
+}
+
+mod borrowed_test_cases {
+    use super::*;
+
+    #[test]
+    fn succeeds_with_same_output_as_owned_test_cases() {
+        let owned_test_cases = vec!["abc".to_string(), "abd".to_string(), "xyz".to_string()];
+        let borrowed_test_cases = owned_test_cases
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let mut builder = RegExpBuilder::from(&borrowed_test_cases[..2]);
+        builder.add_test_case(borrowed_test_cases[2]);
+        assert_eq!(
+            builder.build(),
+            RegExpBuilder::from(&owned_test_cases).build()
+        );
+    }
+}
+
+fn assert_that_regexp_is_correct(regexp: String, expected_output: &str, test_cases: &[&str]) {
+    assert_eq!(
+        regexp, expected_output,
+        "\n\ninput: {:?}\nexpected: {}\nactual: {}\n\n",
+        test_cases, expected_output, regexp
+    );
+}
+
+/// Matches the test cases with Perl, whose backtracking engine supports atomic groups
+/// with the same semantics as PCRE, Java and .NET in contrast to Rust's regex crate.
+/// Perl is not a dependency of this crate, so the tests calling this are ignored by default.
+fn assert_that_regexp_matches_test_cases_with_backtracking(regexp: &str, test_cases: &[&str]) {
+    let status = std::process::Command::new("perl")
+        .args([
+            "-e",
+            "$r = shift; for (@ARGV) { exit 1 unless /$r/ }",
+            regexp,
+        ])
+        .args(test_cases)
+        .status()
+        .expect("perl is required to match atomic groups");
+    assert!(
+        status.success(),
+        "\n\nnot all of {:?} match regex {}\n\n",
+        test_cases,
+        regexp
     );
 }
 
@@ -14,7 +14,9 @@
  * limitations under the License.
  */
 
-use grex::{Feature, RegExpBuilder};
+#![cfg(feature = "property-tests")]
+
+use grex::{Anchor, Feature, RegExpBuilder};
 use proptest::prelude::*;
 use regex::{Error, Regex, RegexBuilder};
 
@@ -22,7 +24,6 @@ proptest! {
     #![proptest_config(ProptestConfig::with_cases(500))]
 
     #[test]
-    #[ignore]
     fn valid_regexes_with_default_settings(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10)
     ) {
@@ -32,7 +33,6 @@ proptest! {
     }
 
     #[test]
-    #[ignore]
     fn valid_regexes_with_escape_sequences(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10)
     ) {
@@ -44,7 +44,6 @@ proptest! {
     }
 
     #[test]
-    #[ignore]
     fn valid_regexes_with_verbose_mode(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10)
     ) {
@@ -56,7 +55,6 @@ proptest! {
     }
 
     #[test]
-    #[ignore]
     fn valid_regexes_with_escape_sequences_and_verbose_mode(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10)
     ) {
@@ -69,7 +67,6 @@ proptest! {
     }
 
     #[test]
-    #[ignore]
     fn valid_regexes_with_conversion_features(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10),
         conversion_features in prop::collection::hash_set(conversion_feature_strategy(), 1..=9),
@@ -86,7 +83,6 @@ proptest! {
     }
 
     #[test]
-    #[ignore]
     fn valid_regexes_with_conversion_features_and_escape_sequences(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10),
         conversion_features in prop::collection::hash_set(conversion_feature_strategy(), 1..=9),
@@ -104,7 +100,6 @@ proptest! {
     }
 
     #[test]
-    #[ignore]
     fn valid_regexes_with_conversion_features_and_verbose_mode(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10),
         conversion_features in prop::collection::hash_set(conversion_feature_strategy(), 1..=9),
@@ -122,19 +117,17 @@ proptest! {
     }
 
     #[test]
-    #[ignore]
     fn matching_regexes_with_default_settings(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10)
     ) {
         let test_cases_vec = test_cases.iter().cloned().collect::<Vec<_>>();
         let regexp = RegExpBuilder::from(&test_cases_vec).build();
         if let Ok(compiled_regexp) = compile_regexp(&regexp) {
-            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(&test_case)));
+            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(test_case)));
         }
     }
 
     #[test]
-    #[ignore]
     fn matching_regexes_with_escape_sequences(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10)
     ) {
@@ -143,12 +136,11 @@ proptest! {
             .with_escaping_of_non_ascii_chars(false)
             .build();
         if let Ok(compiled_regexp) = compile_regexp(&regexp) {
-            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(&test_case)));
+            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(test_case)));
         }
     }
 
     #[test]
-    #[ignore]
     fn matching_regexes_with_verbose_mode(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10)
     ) {
@@ -157,12 +149,11 @@ proptest! {
             .with_verbose_mode()
             .build();
         if let Ok(compiled_regexp) = compile_regexp(&regexp) {
-            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(&test_case)));
+            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(test_case)));
         }
     }
 
     #[test]
-    #[ignore]
     fn matching_regexes_with_escape_sequences_and_verbose_mode(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10)
     ) {
@@ -172,12 +163,11 @@ proptest! {
             .with_verbose_mode()
             .build();
         if let Ok(compiled_regexp) = compile_regexp(&regexp) {
-            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(&test_case)));
+            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(test_case)));
         }
     }
 
     #[test]
-    #[ignore]
     fn matching_regexes_with_conversion_features(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10),
         conversion_features in prop::collection::hash_set(conversion_feature_strategy(), 1..=9),
@@ -191,12 +181,11 @@ proptest! {
             .with_minimum_substring_length(minimum_substring_length)
             .build();
         if let Ok(compiled_regexp) = compile_regexp(&regexp) {
-            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(&test_case)));
+            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(test_case)));
         }
     }
 
     #[test]
-    #[ignore]
     fn matching_regexes_with_conversion_features_and_escape_sequences(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10),
         conversion_features in prop::collection::hash_set(conversion_feature_strategy(), 1..=9),
@@ -211,12 +200,11 @@ proptest! {
             .with_escaping_of_non_ascii_chars(false)
             .build();
         if let Ok(compiled_regexp) = compile_regexp(&regexp) {
-            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(&test_case)));
+            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(test_case)));
         }
     }
 
     #[test]
-    #[ignore]
     fn matching_regexes_with_conversion_features_and_verbose_mode(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10),
         conversion_features in prop::collection::hash_set(conversion_feature_strategy(), 1..=9),
@@ -231,12 +219,45 @@ proptest! {
             .with_verbose_mode()
             .build();
         if let Ok(compiled_regexp) = compile_regexp(&regexp) {
-            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(&test_case)));
+            prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(test_case)));
         }
     }
 
     #[test]
-    #[ignore]
+    fn exactly_matching_regexes_without_anchors(
+        test_cases in prop::collection::hash_set(".{0,20}", 1..=10)
+    ) {
+        let test_cases_vec = test_cases.iter().cloned().collect::<Vec<_>>();
+        let regexp = RegExpBuilder::from(&test_cases_vec)
+            .with_anchors(Anchor::None, Anchor::None)
+            .build();
+        let compiled_regexp = compile_regexp(&format!("^(?:{})$", regexp));
+        prop_assert!(compiled_regexp.is_ok());
+        let compiled_regexp = compiled_regexp.unwrap();
+        prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(test_case)));
+    }
+
+    #[test]
+    fn exactly_matching_regexes_with_conversion_features_and_without_anchors(
+        test_cases in prop::collection::hash_set(".{0,20}", 1..=10),
+        conversion_features in prop::collection::hash_set(conversion_feature_strategy(), 1..=9),
+        minimum_repetitions in 1..100u32,
+        minimum_substring_length in 1..100u32
+    ) {
+        let test_cases_vec = test_cases.iter().cloned().collect::<Vec<_>>();
+        let regexp = RegExpBuilder::from(&test_cases_vec)
+            .with_conversion_of(&conversion_features.into_iter().collect::<Vec<_>>())
+            .with_minimum_repetitions(minimum_repetitions)
+            .with_minimum_substring_length(minimum_substring_length)
+            .with_anchors(Anchor::None, Anchor::None)
+            .build();
+        let compiled_regexp = compile_regexp(&format!("^(?:{})$", regexp));
+        prop_assert!(compiled_regexp.is_ok());
+        let compiled_regexp = compiled_regexp.unwrap();
+        prop_assert!(test_cases.iter().all(|test_case| compiled_regexp.is_match(test_case)));
+    }
+
+    #[test]
     fn regexes_not_matching_other_strings_with_default_settings(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10),
         other_strings in prop::collection::hash_set(".{1,20}", 1..=10)
@@ -245,13 +266,12 @@ proptest! {
             let test_cases_vec = test_cases.iter().cloned().collect::<Vec<_>>();
             let regexp = RegExpBuilder::from(&test_cases_vec).build();
             if let Ok(compiled_regexp) = compile_regexp(&regexp) {
-                prop_assert!(other_strings.iter().all(|other_string| !compiled_regexp.is_match(&other_string)));
+                prop_assert!(other_strings.iter().all(|other_string| !compiled_regexp.is_match(other_string)));
             }
         }
     }
 
     #[test]
-    #[ignore]
     fn regexes_not_matching_other_strings_with_escape_sequences(
         test_cases in prop::collection::hash_set(".{1,20}", 1..=10),
         other_strings in prop::collection::hash_set(".{1,20}", 1..=10)
@@ -262,7 +282,7 @@ proptest! {
                 .with_escaping_of_non_ascii_chars(false)
                 .build();
             if let Ok(compiled_regexp) = compile_regexp(&regexp) {
-                prop_assert!(other_strings.iter().all(|other_string| !compiled_regexp.is_match(&other_string)));
+                prop_assert!(other_strings.iter().all(|other_string| !compiled_regexp.is_match(other_string)));
             }
         }
     }
@@ -276,6 +296,7 @@ fn conversion_feature_strategy() -> impl Strategy<Value = Feature> {
         Just(Feature::NonSpace),
         Just(Feature::Word),
         Just(Feature::NonWord),
+        Just(Feature::Hex),
         Just(Feature::Repetition),
         Just(Feature::CaseInsensitivity),
         Just(Feature::CapturingGroup)